@@ -1,4 +1,5 @@
 import { createSdkMcpServer, query, tool, type PermissionMode, type Query, type SDKMessage } from '@anthropic-ai/claude-agent-sdk';
+import os from 'node:os';
 import { z } from 'zod';
 import type { AgentType, FrontendApiBase, QueryOptions } from './protocol.js';
 import { parseEnvString, resolveClaudeCliPath } from './utils.js';
@@ -281,6 +282,17 @@ export class ClaudeSessionManager {
     this.sessions.delete(sessionId);
   }
 
+  /** Drop a session's state when the app moves it off this process. */
+  closeSession(sessionId: string) {
+    const session = this.sessions.get(sessionId);
+    session?.terminate?.();
+    this.sessions.delete(sessionId);
+  }
+
+  sessionCount() {
+    return this.sessions.size;
+  }
+
   async handlePermissionModeUpdate(sessionId: string, permissionMode: string) {
     const session = this.sessions.get(sessionId);
     if (!session?.query) {
@@ -369,6 +381,38 @@ export class ClaudeSessionManager {
     }
   }
 
+  async generateTitle(id: string, options: { prompt: string; model?: string }) {
+    const queryResult = query({
+      prompt: options.prompt,
+      options: {
+        cwd: os.tmpdir(),
+        model: options.model,
+        maxTurns: 1,
+        pathToClaudeCodeExecutable: resolveClaudeCliPath(),
+        settingSources: [],
+        // Title generation only needs a text reply: no tools, outside any workspace.
+        tools: [],
+      },
+    });
+    try {
+      for await (const message of queryResult) {
+        if (message.type !== 'result') continue;
+        if (message.subtype !== 'success') {
+          throw new Error(`Title generation failed: ${message.subtype}`);
+        }
+        return {
+          type: 'generate_title',
+          id,
+          agentType: 'claude' satisfies AgentType,
+          title: message.result,
+        };
+      }
+      throw new Error('No title response');
+    } finally {
+      await queryResult.interrupt();
+    }
+  }
+
   private async startStreaming(
     sessionId: string,
     session: ClaudeSessionState,
@@ -498,6 +542,7 @@ export class ClaudeSessionManager {
         },
         resume: options.resume,
         resumeSessionAt: options.resumeSessionAt,
+        forkSession: options.forkSession,
       };
 
       const queryResult = query({ prompt: promptStream, options: sdkOptions });
@@ -146,6 +146,16 @@ export class CodexSessionManager {
     }
   }
 
+  /** Drop a session's state when the app moves it off this process. */
+  closeSession(sessionId: string) {
+    this.sessions.get(sessionId)?.controller?.abort();
+    this.sessions.delete(sessionId);
+  }
+
+  sessionCount() {
+    return this.sessions.size;
+  }
+
   handleCancel(sessionId: string, frontend: FrontendApi) {
     const state = this.sessions.get(sessionId);
     if (!state?.controller) {
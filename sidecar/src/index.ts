@@ -12,7 +12,9 @@ import {
   SIDECAR_NOTIFICATIONS,
   isCancelRequest,
   isClaudeAuthRequest,
+  isCloseSessionRequest,
   isContextUsageRequest,
+  isGenerateTitleRequest,
   isQueryRequest,
   isUpdatePermissionModeRequest,
   isWorkspaceInitRequest,
@@ -130,11 +132,34 @@ class SidecarServer {
       });
     });
 
+    rpc.addMethod(SIDECAR_METHODS.GENERATE_TITLE, async (params) => {
+      if (!isGenerateTitleRequest(params)) {
+        throw new Error('Invalid generateTitle request');
+      }
+      return this.claudeManager.generateTitle(params.id, params.options);
+    });
+
     rpc.addMethod(SIDECAR_NOTIFICATIONS.UPDATE_PERMISSION_MODE, async (params) => {
       if (!isUpdatePermissionModeRequest(params)) return;
       await this.claudeManager.handlePermissionModeUpdate(params.id, params.permissionMode);
     });
 
+    rpc.addMethod(SIDECAR_NOTIFICATIONS.CLOSE_SESSION, (params) => {
+      if (!isCloseSessionRequest(params)) return;
+      this.claudeManager.closeSession(params.id);
+      this.codexManager.closeSession(params.id);
+    });
+
+    rpc.addMethod(SIDECAR_METHODS.STATS, () => {
+      const memory = process.memoryUsage();
+      return {
+        pid: process.pid,
+        rss: memory.rss,
+        heapUsed: memory.heapUsed,
+        sessions: this.claudeManager.sessionCount() + this.codexManager.sessionCount(),
+      };
+    });
+
     let buffer = '';
     socket.on('data', (data) => {
       buffer += data.toString();
@@ -3,11 +3,14 @@ export const SIDECAR_METHODS = {
   CLAUDE_AUTH: 'claudeAuth',
   WORKSPACE_INIT: 'workspaceInit',
   CONTEXT_USAGE: 'contextUsage',
+  GENERATE_TITLE: 'generateTitle',
+  STATS: 'stats',
 } as const;
 
 export const SIDECAR_NOTIFICATIONS = {
   QUERY: 'query',
   UPDATE_PERMISSION_MODE: 'updatePermissionMode',
+  CLOSE_SESSION: 'closeSession',
 } as const;
 
 export const FRONTEND_NOTIFICATIONS = {
@@ -42,6 +45,8 @@ export type QueryOptions = {
   resume?: string;
   /** Shared: resume timestamp for session rehydration. */
   resumeSessionAt?: string;
+  /** Claude-specific: continue a resumed session under a new session id. */
+  forkSession?: boolean;
   /** Claude-specific: reset streaming generator state. */
   shouldResetGenerator?: boolean;
   /** Claude-specific: environment variables to set for Claude CLI. */
@@ -95,6 +100,13 @@ export type ContextUsageRequest = {
   options: { cwd: string; claudeSessionId: string };
 };
 
+export type GenerateTitleRequest = {
+  type: 'generate_title';
+  id: string;
+  agentType: 'claude';
+  options: { prompt: string; model?: string };
+};
+
 export type UpdatePermissionModeRequest = {
   type: 'update_permission_mode';
   id: string;
@@ -103,6 +115,11 @@ export type UpdatePermissionModeRequest = {
   permissionMode: string;
 };
 
+export type CloseSessionRequest = {
+  type: 'close_session';
+  id: string;
+};
+
 const isRecord = (value: unknown): value is Record<string, unknown> =>
   typeof value === 'object' && value !== null;
 
@@ -161,6 +178,18 @@ export const isContextUsageRequest = (
   return isString(value.options.cwd) && isString(value.options.claudeSessionId);
 };
 
+export const isGenerateTitleRequest = (
+  value: unknown,
+): value is GenerateTitleRequest => {
+  if (!isRecord(value)) return false;
+  if (value.type !== 'generate_title' || !isString(value.id) || value.agentType !== 'claude') {
+    return false;
+  }
+  if (!isRecord(value.options)) return false;
+  if (value.options.model !== undefined && !isString(value.options.model)) return false;
+  return isString(value.options.prompt);
+};
+
 // NOTE: Permission mode updates are Claude-only; add a new request/guard for other agents.
 export const isUpdatePermissionModeRequest = (
   value: unknown,
@@ -173,3 +202,8 @@ export const isUpdatePermissionModeRequest = (
     isString(value.permissionMode)
   );
 };
+
+export const isCloseSessionRequest = (value: unknown): value is CloseSessionRequest => {
+  if (!isRecord(value)) return false;
+  return value.type === 'close_session' && isString(value.id);
+};
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS spotlight_instances (
+  workspace_id TEXT PRIMARY KEY,
+  repo_root TEXT NOT NULL,
+  workspace_path TEXT NOT NULL,
+  rollback_checkpoint_id TEXT NOT NULL,
+  sync_checkpoint_id TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN sidecar_restart_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+ALTER TABLE session_messages ADD COLUMN queue_position INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_session_messages_queue
+  ON session_messages(session_id, queue_position)
+  WHERE sent_at IS NULL;
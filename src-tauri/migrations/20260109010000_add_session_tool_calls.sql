@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS session_tool_calls (
+  id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  turn_id INTEGER NOT NULL,
+  message_id TEXT,
+  call_id TEXT NOT NULL,
+  tool_name TEXT NOT NULL,
+  input_json TEXT,
+  output TEXT,
+  status TEXT NOT NULL,
+  started_at_ms INTEGER NOT NULL,
+  finished_at_ms INTEGER,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  UNIQUE (session_id, turn_id, call_id),
+  FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+  FOREIGN KEY (message_id) REFERENCES session_messages(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_tool_calls_turn ON session_tool_calls(session_id, turn_id);
@@ -0,0 +1,18 @@
+-- Usage rows outlive their session so reports keep deleted sessions' costs.
+CREATE TABLE IF NOT EXISTS session_turn_usage (
+  session_id TEXT NOT NULL,
+  turn_id INTEGER NOT NULL,
+  workspace_id TEXT NOT NULL,
+  repo_id TEXT NOT NULL,
+  agent_type TEXT NOT NULL,
+  model TEXT,
+  input_tokens INTEGER NOT NULL DEFAULT 0,
+  output_tokens INTEGER NOT NULL DEFAULT 0,
+  cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+  cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+  cost_usd REAL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (session_id, turn_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_turn_usage_created_at ON session_turn_usage(created_at);
@@ -0,0 +1,66 @@
+CREATE VIRTUAL TABLE IF NOT EXISTS session_messages_fts USING fts5(
+  content,
+  content = 'session_messages',
+  content_rowid = 'rowid',
+  tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS session_tool_calls_fts USING fts5(
+  tool_name,
+  input_json,
+  content = 'session_tool_calls',
+  content_rowid = 'rowid',
+  tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS session_messages_fts_insert
+AFTER INSERT ON session_messages
+BEGIN
+  INSERT INTO session_messages_fts (rowid, content) VALUES (new.rowid, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_messages_fts_delete
+AFTER DELETE ON session_messages
+BEGIN
+  INSERT INTO session_messages_fts (session_messages_fts, rowid, content)
+  VALUES ('delete', old.rowid, old.content);
+END;
+
+-- Dispatching a queued message moves its rowid, so re-index on any update.
+CREATE TRIGGER IF NOT EXISTS session_messages_fts_update
+AFTER UPDATE ON session_messages
+WHEN old.rowid IS NOT new.rowid OR old.content IS NOT new.content
+BEGIN
+  INSERT INTO session_messages_fts (session_messages_fts, rowid, content)
+  VALUES ('delete', old.rowid, old.content);
+  INSERT INTO session_messages_fts (rowid, content) VALUES (new.rowid, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_tool_calls_fts_insert
+AFTER INSERT ON session_tool_calls
+BEGIN
+  INSERT INTO session_tool_calls_fts (rowid, tool_name, input_json)
+  VALUES (new.rowid, new.tool_name, new.input_json);
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_tool_calls_fts_delete
+AFTER DELETE ON session_tool_calls
+BEGIN
+  INSERT INTO session_tool_calls_fts (session_tool_calls_fts, rowid, tool_name, input_json)
+  VALUES ('delete', old.rowid, old.tool_name, old.input_json);
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_tool_calls_fts_update
+AFTER UPDATE ON session_tool_calls
+WHEN old.rowid IS NOT new.rowid
+  OR old.tool_name IS NOT new.tool_name
+  OR old.input_json IS NOT new.input_json
+BEGIN
+  INSERT INTO session_tool_calls_fts (session_tool_calls_fts, rowid, tool_name, input_json)
+  VALUES ('delete', old.rowid, old.tool_name, old.input_json);
+  INSERT INTO session_tool_calls_fts (rowid, tool_name, input_json)
+  VALUES (new.rowid, new.tool_name, new.input_json);
+END;
+
+INSERT INTO session_messages_fts (session_messages_fts) VALUES ('rebuild');
+INSERT INTO session_tool_calls_fts (session_tool_calls_fts) VALUES ('rebuild');
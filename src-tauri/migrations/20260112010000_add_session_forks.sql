@@ -0,0 +1,6 @@
+-- Source of a forked session; not a foreign key so forks outlive their source.
+ALTER TABLE sessions ADD COLUMN forked_from_session_id TEXT;
+ALTER TABLE sessions ADD COLUMN forked_from_turn_id INTEGER;
+-- Claude assistant message uuid to resume the agent session at, until the fork
+-- gets an agent session of its own.
+ALTER TABLE sessions ADD COLUMN resume_session_at TEXT;
@@ -0,0 +1,2 @@
+-- Where the title came from: 'default' ("Chat N"), 'generated' or 'user'.
+ALTER TABLE sessions ADD COLUMN title_source TEXT NOT NULL DEFAULT 'default';
@@ -0,0 +1,15 @@
+-- Workspace snapshot taken after the agent finished each turn. Unlike message
+-- rows, these survive a reset so the session can be moved forward again.
+CREATE TABLE IF NOT EXISTS session_turn_checkpoints (
+  session_id TEXT NOT NULL,
+  turn_id INTEGER NOT NULL,
+  checkpoint_id TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (session_id, turn_id),
+  FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+INSERT OR IGNORE INTO session_turn_checkpoints (session_id, turn_id, checkpoint_id, created_at)
+SELECT session_id, turn_id, checkpoint_id, created_at
+FROM session_messages
+WHERE role = 'assistant' AND checkpoint_id IS NOT NULL AND checkpoint_id != '';
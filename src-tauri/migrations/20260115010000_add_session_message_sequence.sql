@@ -0,0 +1,27 @@
+ALTER TABLE session_messages ADD COLUMN sequence INTEGER;
+
+UPDATE session_messages
+SET sequence = (
+  SELECT ordered.position
+  FROM (
+    SELECT rowid AS message_rowid,
+           ROW_NUMBER() OVER (PARTITION BY session_id ORDER BY created_at, rowid) AS position
+    FROM session_messages
+  ) AS ordered
+  WHERE ordered.message_rowid = session_messages.rowid
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_messages_sequence
+  ON session_messages(session_id, sequence);
+
+-- Transcript order within a session; dispatching a queued message moves it to the end.
+CREATE TRIGGER IF NOT EXISTS session_messages_sequence_insert
+AFTER INSERT ON session_messages
+WHEN new.sequence IS NULL
+BEGIN
+  UPDATE session_messages
+  SET sequence = (
+    SELECT COALESCE(MAX(sequence), 0) + 1 FROM session_messages WHERE session_id = new.session_id
+  )
+  WHERE rowid = new.rowid;
+END;
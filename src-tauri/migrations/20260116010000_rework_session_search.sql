@@ -0,0 +1,88 @@
+-- The search indexes keyed the implicit rowid, which VACUUM may renumber. Key them
+-- on an explicit `search_rowid` instead and keep their own copy of the text, so
+-- queued messages and streaming deltas can stay out of the message index.
+DROP TRIGGER IF EXISTS session_messages_fts_insert;
+DROP TRIGGER IF EXISTS session_messages_fts_delete;
+DROP TRIGGER IF EXISTS session_messages_fts_update;
+DROP TRIGGER IF EXISTS session_tool_calls_fts_insert;
+DROP TRIGGER IF EXISTS session_tool_calls_fts_delete;
+DROP TRIGGER IF EXISTS session_tool_calls_fts_update;
+DROP TABLE IF EXISTS session_messages_fts;
+DROP TABLE IF EXISTS session_tool_calls_fts;
+
+ALTER TABLE session_messages ADD COLUMN search_rowid INTEGER;
+UPDATE session_messages SET search_rowid = rowid;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_session_messages_search_rowid
+  ON session_messages(search_rowid);
+
+ALTER TABLE session_tool_calls ADD COLUMN search_rowid INTEGER;
+UPDATE session_tool_calls SET search_rowid = rowid;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_session_tool_calls_search_rowid
+  ON session_tool_calls(search_rowid);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS session_messages_fts USING fts5(
+  content,
+  tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS session_tool_calls_fts USING fts5(
+  tool_name,
+  input_json,
+  tokenize = 'unicode61 remove_diacritics 2'
+);
+
+-- Only sent messages are indexed. Streaming updates to `content` are not: the
+-- final text is re-indexed by `sessions::index_session_message`.
+CREATE TRIGGER IF NOT EXISTS session_messages_search_insert
+AFTER INSERT ON session_messages
+BEGIN
+  UPDATE session_messages
+  SET search_rowid = (SELECT COALESCE(MAX(search_rowid), 0) + 1 FROM session_messages)
+  WHERE id = new.id AND search_rowid IS NULL;
+  INSERT INTO session_messages_fts (rowid, content)
+  SELECT search_rowid, content FROM session_messages
+  WHERE id = new.id AND sent_at IS NOT NULL;
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_messages_search_sent
+AFTER UPDATE OF sent_at ON session_messages
+WHEN old.sent_at IS NULL AND new.sent_at IS NOT NULL
+BEGIN
+  INSERT INTO session_messages_fts (rowid, content) VALUES (new.search_rowid, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_messages_search_delete
+AFTER DELETE ON session_messages
+BEGIN
+  DELETE FROM session_messages_fts WHERE rowid = old.search_rowid;
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_tool_calls_search_insert
+AFTER INSERT ON session_tool_calls
+BEGIN
+  UPDATE session_tool_calls
+  SET search_rowid = (SELECT COALESCE(MAX(search_rowid), 0) + 1 FROM session_tool_calls)
+  WHERE id = new.id AND search_rowid IS NULL;
+  INSERT INTO session_tool_calls_fts (rowid, tool_name, input_json)
+  SELECT search_rowid, tool_name, input_json FROM session_tool_calls WHERE id = new.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_tool_calls_search_update
+AFTER UPDATE OF tool_name, input_json ON session_tool_calls
+WHEN old.tool_name IS NOT new.tool_name OR old.input_json IS NOT new.input_json
+BEGIN
+  DELETE FROM session_tool_calls_fts WHERE rowid = new.search_rowid;
+  INSERT INTO session_tool_calls_fts (rowid, tool_name, input_json)
+  VALUES (new.search_rowid, new.tool_name, new.input_json);
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_tool_calls_search_delete
+AFTER DELETE ON session_tool_calls
+BEGIN
+  DELETE FROM session_tool_calls_fts WHERE rowid = old.search_rowid;
+END;
+
+INSERT INTO session_messages_fts (rowid, content)
+SELECT search_rowid, content FROM session_messages WHERE sent_at IS NOT NULL;
+INSERT INTO session_tool_calls_fts (rowid, tool_name, input_json)
+SELECT search_rowid, tool_name, input_json FROM session_tool_calls;
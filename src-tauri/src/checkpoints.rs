@@ -1,3 +1,6 @@
+use crate::git;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fs;
 use std::io::Write;
@@ -7,6 +10,45 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 const ZERO_OID: &str = "0000000000000000000000000000000000000000";
 const CHECKPOINT_REF_PREFIX: &str = "refs/conductor-checkpoints";
+/// Temporary refs for sanitized copies of checkpoints being exported.
+const CHECKPOINT_EXPORT_REF_PREFIX: &str = "refs/conductor-checkpoints-export";
+/// Hard caps for gitignored files pulled in via `checkpointInclude`.
+const MAX_INCLUDED_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const MAX_INCLUDED_TOTAL_BYTES: u64 = 50 * 1024 * 1024;
+/// Never snapshot key material, even when an include pattern matches it.
+const SECRET_EXCLUDE_PATTERNS: &[&str] = &[
+  "**/*.pem",
+  "**/*.key",
+  "**/*.p12",
+  "**/*.pfx",
+  "**/id_rsa*",
+  "**/id_ecdsa*",
+  "**/id_ed25519*",
+  "**/.ssh/**",
+  "**/.aws/**",
+  "**/.gnupg/**",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointInfo {
+  pub id: String,
+  pub commit: String,
+  pub head: String,
+  pub index_tree: String,
+  pub worktree_tree: String,
+  pub created: i64,
+  pub label: Option<String>,
+  pub agent_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestorePreviewEntry {
+  pub path: String,
+  pub change: String,
+  pub untracked: bool,
+}
 
 #[derive(Debug)]
 pub enum CheckpointOutcome {
@@ -65,6 +107,77 @@ impl Drop for TempDirGuard {
 pub fn create_checkpoint(
   repo_path: &Path,
   checkpoint_id: &str,
+) -> Result<CheckpointOutcome, CheckpointError> {
+  write_checkpoint(repo_path, checkpoint_id, &[], None)
+}
+
+/// Create a checkpoint whose snapshot also leaves out untracked files matching
+/// the gitignore-style patterns in `excludes_file`.
+pub fn create_checkpoint_excluding(
+  repo_path: &Path,
+  checkpoint_id: &str,
+  excludes_file: &Path,
+) -> Result<CheckpointOutcome, CheckpointError> {
+  write_checkpoint(repo_path, checkpoint_id, &[], Some(excludes_file))
+}
+
+/// Create a checkpoint carrying a user-visible label in its metadata.
+pub fn create_named_checkpoint(
+  repo_path: &Path,
+  checkpoint_id: &str,
+  label: &str,
+) -> Result<CheckpointOutcome, CheckpointError> {
+  let label = label.split_whitespace().collect::<Vec<_>>().join(" ");
+  if label.is_empty() {
+    return Err(CheckpointError::InvalidState(
+      "Checkpoint label is required".to_string(),
+    ));
+  }
+  write_checkpoint(repo_path, checkpoint_id, &[("label", label.as_str())], None)
+}
+
+/// Id for a user-visible checkpoint of `workspace_id`. Checkpoint refs are shared
+/// by every worktree of a repository, so the id records which workspace owns it.
+pub fn named_checkpoint_id(workspace_id: &str) -> String {
+  let stamp = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_millis())
+    .unwrap_or(0);
+  format!("named-{workspace_id}-{stamp}")
+}
+
+/// Save the working tree as a named checkpoint before a restore overwrites it and
+/// return its id. Best-effort: a failed backup is logged and never blocks the
+/// restore that follows.
+pub fn backup_before_restore(repo_path: &Path, workspace_id: &str, label: &str) -> Option<String> {
+  let backup_id = named_checkpoint_id(workspace_id);
+  match create_named_checkpoint(repo_path, &backup_id, label) {
+    Ok(CheckpointOutcome::Created) => Some(backup_id),
+    Ok(CheckpointOutcome::Skipped { reason }) => {
+      eprintln!("[checkpoint] backup \"{label}\" skipped: {reason}");
+      None
+    }
+    Err(err) => {
+      eprintln!("[checkpoint] backup \"{label}\" failed: {err}");
+      None
+    }
+  }
+}
+
+/// Create a turn checkpoint tagged with the agent type that ran the turn.
+pub fn create_agent_checkpoint(
+  repo_path: &Path,
+  checkpoint_id: &str,
+  agent_type: &str,
+) -> Result<CheckpointOutcome, CheckpointError> {
+  write_checkpoint(repo_path, checkpoint_id, &[("agent", agent_type)], None)
+}
+
+fn write_checkpoint(
+  repo_path: &Path,
+  checkpoint_id: &str,
+  extra_meta: &[(&str, &str)],
+  excludes_file: Option<&Path>,
 ) -> Result<CheckpointOutcome, CheckpointError> {
   validate_checkpoint_id(checkpoint_id)?;
   ensure_repo(repo_path)?;
@@ -85,38 +198,29 @@ pub fn create_checkpoint(
   }
 
   let index_tree = run_git(repo_path, &["write-tree"], &[], None)?;
-
-  let temp_dir = create_temp_dir("supertree-checkpoint")?;
-  let tmp_index = temp_dir.path.join("index");
-  let index_env = [("GIT_INDEX_FILE", tmp_index.to_str().ok_or(CheckpointError::InvalidUtf8)?)];
-
-  run_git(
-    repo_path,
-    &["read-tree", index_tree.as_str()],
-    &index_env,
-    None,
-  )?;
-  // Capture the full working tree (tracked + untracked, excluding .gitignored files).
-  // Keep .gitignore up to date to avoid accidentally snapshotting sensitive local files.
-  run_git(repo_path, &["add", "-A", "--", "."], &index_env, None)?;
-  let worktree_tree = run_git(repo_path, &["write-tree"], &index_env, None)?;
+  let worktree_tree = snapshot_worktree_tree(repo_path, &index_tree, true, excludes_file)?;
 
   let now = format_timestamp()?;
-  let message = format!(
+  let mut message = format!(
     "checkpoint:{checkpoint_id}\nhead {head_oid}\nindex-tree {index_tree}\nworktree-tree {worktree_tree}\ncreated {now}\n"
   );
+  for (key, value) in extra_meta {
+    message.push_str(&format!("{key} {value}\n"));
+  }
 
+  let commit_env = checkpoint_commit_env(&now);
+  // Parent the snapshot on a commit of the index tree so the index stays reachable
+  // from the ref: `git gc` keeps it and bundles carry it along.
+  let index_commit = run_git(
+    repo_path,
+    &["commit-tree", index_tree.as_str()],
+    &commit_env,
+    Some(&format!("checkpoint-index:{checkpoint_id}\n")),
+  )?;
   let commit_oid = run_git(
     repo_path,
-    &["commit-tree", worktree_tree.as_str()],
-    &[
-      ("GIT_AUTHOR_NAME", "Checkpointer"),
-      ("GIT_AUTHOR_EMAIL", "checkpointer@noreply"),
-      ("GIT_AUTHOR_DATE", now.as_str()),
-      ("GIT_COMMITTER_NAME", "Checkpointer"),
-      ("GIT_COMMITTER_EMAIL", "checkpointer@noreply"),
-      ("GIT_COMMITTER_DATE", now.as_str()),
-    ],
+    &["commit-tree", worktree_tree.as_str(), "-p", index_commit.as_str()],
+    &commit_env,
     Some(&message),
   )?;
 
@@ -131,7 +235,246 @@ pub fn create_checkpoint(
   Ok(CheckpointOutcome::Created)
 }
 
+/// Tree object of the current working tree (tracked and untracked files, without
+/// gitignored extras), computed without touching the index or any ref. Untracked
+/// files matching `excludes_file` are left out as well.
+pub fn current_worktree_tree(
+  repo_path: &Path,
+  excludes_file: Option<&Path>,
+) -> Result<String, CheckpointError> {
+  ensure_repo(repo_path)?;
+  let index_tree = run_git(repo_path, &["write-tree"], &[], None)?;
+  snapshot_worktree_tree(repo_path, &index_tree, false, excludes_file)
+}
+
+/// Git config, passed through the environment, that adds `excludes_file` to the
+/// ignore rules in place of the user's global `core.excludesFile`.
+fn excludes_env(excludes_file: Option<&Path>) -> Result<Vec<(&'static str, &str)>, CheckpointError> {
+  let Some(excludes_file) = excludes_file else {
+    return Ok(Vec::new());
+  };
+  Ok(vec![
+    ("GIT_CONFIG_COUNT", "1"),
+    ("GIT_CONFIG_KEY_0", "core.excludesFile"),
+    ("GIT_CONFIG_VALUE_0", excludes_file.to_str().ok_or(CheckpointError::InvalidUtf8)?),
+  ])
+}
+
+/// Blob ids that git would record for the given worktree files, keyed by path;
+/// `None` marks a missing path. Directories and symlinks are left out.
+pub fn hash_worktree_paths(
+  repo_path: &Path,
+  paths: &[String],
+) -> Result<HashMap<String, Option<String>>, CheckpointError> {
+  let mut hashes = HashMap::new();
+  let mut files = Vec::new();
+  for path in paths {
+    match fs::symlink_metadata(repo_path.join(path)) {
+      Ok(metadata) if metadata.is_file() && !path.contains('\n') => files.push(path.as_str()),
+      Ok(_) => {}
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+        hashes.insert(path.clone(), None);
+      }
+      Err(err) => return Err(CheckpointError::Io(err)),
+    }
+  }
+  if files.is_empty() {
+    return Ok(hashes);
+  }
+  let output = run_git(
+    repo_path,
+    &["hash-object", "--stdin-paths"],
+    &[],
+    Some(&files.join("\n")),
+  )?;
+  for (path, oid) in files.into_iter().zip(output.lines()) {
+    hashes.insert(path.to_string(), Some(oid.trim().to_string()));
+  }
+  Ok(hashes)
+}
+
+/// Blob ids recorded in `tree` for the given paths, including the files below
+/// any path that is a directory there. Paths missing from the tree are absent.
+pub fn tree_blob_ids(
+  repo_path: &Path,
+  tree: &str,
+  paths: &[String],
+) -> Result<HashMap<String, String>, CheckpointError> {
+  if paths.is_empty() {
+    return Ok(HashMap::new());
+  }
+  let mut args = vec!["ls-tree", "-r", "-z", "--full-tree", tree, "--"];
+  args.extend(paths.iter().map(String::as_str));
+  let output = run_git_nul(repo_path, &args, &[("GIT_LITERAL_PATHSPECS", "1")], None)?;
+  Ok(
+    output
+      .split('\0')
+      .filter_map(|entry| entry.split_once('\t'))
+      .filter_map(|(meta, path)| {
+        let oid = meta.split_whitespace().nth(2)?;
+        Some((path.to_string(), oid.to_string()))
+      })
+      .collect(),
+  )
+}
+
+/// Paths that differ between two tree objects.
+pub fn changed_paths(
+  repo_path: &Path,
+  from_tree: &str,
+  to_tree: &str,
+) -> Result<Vec<String>, CheckpointError> {
+  let output = run_git_nul(
+    repo_path,
+    &["diff-tree", "-r", "-z", "--no-renames", "--name-only", from_tree, to_tree],
+    &[],
+    None,
+  )?;
+  Ok(
+    output
+      .split('\0')
+      .filter(|path| !path.is_empty())
+      .map(|path| path.to_string())
+      .collect(),
+  )
+}
+
+fn checkpoint_commit_env(date: &str) -> [(&'static str, &str); 6] {
+  [
+    ("GIT_AUTHOR_NAME", "Checkpointer"),
+    ("GIT_AUTHOR_EMAIL", "checkpointer@noreply"),
+    ("GIT_AUTHOR_DATE", date),
+    ("GIT_COMMITTER_NAME", "Checkpointer"),
+    ("GIT_COMMITTER_EMAIL", "checkpointer@noreply"),
+    ("GIT_COMMITTER_DATE", date),
+  ]
+}
+
+/// Write the current working tree to a tree object using a temporary index seeded
+/// from `index_tree`, leaving the real index untouched.
+fn snapshot_worktree_tree(
+  repo_path: &Path,
+  index_tree: &str,
+  include_ignored: bool,
+  excludes_file: Option<&Path>,
+) -> Result<String, CheckpointError> {
+  let temp_dir = create_temp_dir("supertree-checkpoint")?;
+  let tmp_index = temp_dir.path.join("index");
+  let index_env = [("GIT_INDEX_FILE", tmp_index.to_str().ok_or(CheckpointError::InvalidUtf8)?)];
+
+  run_git(repo_path, &["read-tree", index_tree], &index_env, None)?;
+  // Capture the full working tree (tracked + untracked, excluding .gitignored files).
+  // Keep .gitignore up to date to avoid accidentally snapshotting sensitive local files.
+  let mut add_env = index_env.to_vec();
+  add_env.extend(excludes_env(excludes_file)?);
+  run_git(repo_path, &["add", "-A", "--", "."], &add_env, None)?;
+  if include_ignored {
+    add_included_ignored_files(repo_path, &index_env)?;
+  }
+  run_git(repo_path, &["write-tree"], &index_env, None)
+}
+
+/// Force-add gitignored files matching the repo's `checkpointInclude` patterns to
+/// the temporary index so they are snapshotted and written back on restore.
+/// Restores only rewrite these files; ignored files created later are left alone.
+fn add_included_ignored_files(
+  repo_path: &Path,
+  index_env: &[(&str, &str)],
+) -> Result<(), CheckpointError> {
+  let patterns = match git::read_checkpoint_patterns(repo_path) {
+    Ok(patterns) => patterns,
+    Err(err) => {
+      eprintln!("[checkpoint] ignoring checkpoint patterns: {err}");
+      return Ok(());
+    }
+  };
+  if patterns.include.is_empty() {
+    return Ok(());
+  }
+  let mut pathspecs = vec![
+    "ls-files".to_string(),
+    "-z".to_string(),
+    "--others".to_string(),
+    "--ignored".to_string(),
+    "--exclude-standard".to_string(),
+    "--".to_string(),
+  ];
+  pathspecs.extend(patterns.include.iter().map(|pattern| format!(":(glob){pattern}")));
+  pathspecs.extend(
+    SECRET_EXCLUDE_PATTERNS
+      .iter()
+      .map(|pattern| pattern.to_string())
+      .chain(patterns.exclude.iter().cloned())
+      .map(|pattern| format!(":(glob,exclude){pattern}")),
+  );
+  let args = pathspecs.iter().map(String::as_str).collect::<Vec<_>>();
+  let output = run_git_nul(repo_path, &args, &[], None)?;
+
+  let mut total_bytes = 0u64;
+  let mut selected = Vec::new();
+  for path in output.split('\0').filter(|path| !path.is_empty()) {
+    let Ok(metadata) = fs::symlink_metadata(repo_path.join(path)) else {
+      continue;
+    };
+    if metadata.is_dir() {
+      continue;
+    }
+    if metadata.len() > MAX_INCLUDED_FILE_BYTES {
+      eprintln!("[checkpoint] skipping included file over size cap: {path}");
+      continue;
+    }
+    if total_bytes + metadata.len() > MAX_INCLUDED_TOTAL_BYTES {
+      eprintln!("[checkpoint] included files exceed size cap; skipping the rest from {path}");
+      break;
+    }
+    total_bytes += metadata.len();
+    selected.push(path);
+  }
+  if selected.is_empty() {
+    return Ok(());
+  }
+  let mut envs = index_env.to_vec();
+  envs.push(("GIT_LITERAL_PATHSPECS", "1"));
+  let input = selected.join("\0");
+  run_git(
+    repo_path,
+    &["add", "--force", "--pathspec-from-file=-", "--pathspec-file-nul"],
+    &envs,
+    Some(&input),
+  )?;
+  Ok(())
+}
+
 pub fn restore_checkpoint(repo_path: &Path, checkpoint_id: &str) -> Result<(), CheckpointError> {
+  restore_checkpoint_with_options(repo_path, checkpoint_id, false)
+}
+
+/// Restore a checkpoint; with `keep_untracked`, untracked files that are not in
+/// the snapshot are left in place instead of being removed by `git clean`.
+pub fn restore_checkpoint_with_options(
+  repo_path: &Path,
+  checkpoint_id: &str,
+  keep_untracked: bool,
+) -> Result<(), CheckpointError> {
+  restore_checkpoint_inner(repo_path, checkpoint_id, keep_untracked, None)
+}
+
+/// Restore a checkpoint, keeping untracked files that match the patterns in
+/// `excludes_file` as if they were gitignored.
+pub fn restore_checkpoint_excluding(
+  repo_path: &Path,
+  checkpoint_id: &str,
+  excludes_file: &Path,
+) -> Result<(), CheckpointError> {
+  restore_checkpoint_inner(repo_path, checkpoint_id, false, Some(excludes_file))
+}
+
+fn restore_checkpoint_inner(
+  repo_path: &Path,
+  checkpoint_id: &str,
+  keep_untracked: bool,
+  excludes_file: Option<&Path>,
+) -> Result<(), CheckpointError> {
   validate_checkpoint_id(checkpoint_id)?;
   ensure_repo(repo_path)?;
   let ref_name = format!("{CHECKPOINT_REF_PREFIX}/{checkpoint_id}");
@@ -149,6 +492,7 @@ pub fn restore_checkpoint(repo_path: &Path, checkpoint_id: &str) -> Result<(), C
     ));
   }
 
+  ensure_checkpoint_objects(repo_path, &head_oid, &index_tree, &worktree_tree)?;
   run_git(repo_path, &["reset", "--hard", head_oid.as_str()], &[], None)?;
   run_git(
     repo_path,
@@ -156,9 +500,11 @@ pub fn restore_checkpoint(repo_path: &Path, checkpoint_id: &str) -> Result<(), C
     &[],
     None,
   )?;
-  // Restores remove untracked files not present in the checkpoint snapshot.
-  // Callers should ensure explicit user confirmation before invoking restore.
-  run_git(repo_path, &["clean", "-fd"], &[], None)?;
+  // Restores remove untracked files not present in the checkpoint snapshot unless
+  // asked to keep them. Use `preview_restore` to show the user what will change.
+  if !keep_untracked {
+    run_git(repo_path, &["clean", "-fd"], &excludes_env(excludes_file)?, None)?;
+  }
   run_git(
     repo_path,
     &["read-tree", "--reset", index_tree.as_str()],
@@ -169,6 +515,526 @@ pub fn restore_checkpoint(repo_path: &Path, checkpoint_id: &str) -> Result<(), C
   Ok(())
 }
 
+/// Check that a checkpoint can be laid onto `repo_path` with
+/// `restore_checkpoint_tree`: no merge may be in progress and HEAD must contain
+/// the commit the checkpoint was taken on.
+pub fn ensure_tree_restorable(repo_path: &Path, checkpoint_id: &str) -> Result<(), CheckpointError> {
+  let (head_oid, _, _) = read_checkpoint_trees(repo_path, checkpoint_id)?;
+  ensure_tree_restore_target(repo_path, &head_oid)
+}
+
+/// Write a checkpoint's files into a working tree without moving HEAD: the files
+/// become uncommitted changes on top of the current branch. Tracked changes in
+/// the target are overwritten, so callers take a backup first; untracked files
+/// the checkpoint does not contain are kept, unlike `restore_checkpoint`'s
+/// `clean -fd`, and commits made since the checkpoint stay on the branch.
+pub fn restore_checkpoint_tree(repo_path: &Path, checkpoint_id: &str) -> Result<(), CheckpointError> {
+  let (head_oid, index_tree, worktree_tree) = read_checkpoint_trees(repo_path, checkpoint_id)?;
+  ensure_tree_restore_target(repo_path, &head_oid)?;
+  ensure_checkpoint_objects(repo_path, &head_oid, &index_tree, &worktree_tree)?;
+  run_git(
+    repo_path,
+    &["read-tree", "--reset", "-u", worktree_tree.as_str()],
+    &[],
+    None,
+  )?;
+  // The saved index only describes staged changes relative to the same HEAD.
+  let current_head = run_git(repo_path, &["rev-parse", "HEAD"], &[], None)?;
+  let index = if current_head == head_oid { index_tree.as_str() } else { "HEAD" };
+  run_git(repo_path, &["read-tree", "--reset", index], &[], None)?;
+  Ok(())
+}
+
+fn read_checkpoint_trees(
+  repo_path: &Path,
+  checkpoint_id: &str,
+) -> Result<(String, String, String), CheckpointError> {
+  validate_checkpoint_id(checkpoint_id)?;
+  ensure_repo(repo_path)?;
+  let ref_name = format!("{CHECKPOINT_REF_PREFIX}/{checkpoint_id}");
+  let commit_oid =
+    run_git(repo_path, &["rev-parse", "-q", "--verify", ref_name.as_str()], &[], None)?;
+  let commit_body = run_git(repo_path, &["cat-file", "commit", commit_oid.as_str()], &[], None)?;
+  let head_oid = extract_meta(&commit_body, "head")?;
+  if head_oid == ZERO_OID {
+    return Err(CheckpointError::InvalidState(
+      "Checkpoint saved with unborn HEAD and cannot be restored".to_string(),
+    ));
+  }
+  Ok((
+    head_oid,
+    extract_meta(&commit_body, "index-tree")?,
+    extract_meta(&commit_body, "worktree-tree")?,
+  ))
+}
+
+fn ensure_tree_restore_target(repo_path: &Path, head_oid: &str) -> Result<(), CheckpointError> {
+  if is_merge_in_progress(repo_path)? {
+    return Err(CheckpointError::InvalidState(
+      "Merge or rebase in progress in the target workspace".to_string(),
+    ));
+  }
+  if run_git_optional(repo_path, &["merge-base", "--is-ancestor", head_oid, "HEAD"])?.is_none() {
+    return Err(CheckpointError::InvalidState(
+      "The target workspace is on a branch that does not contain this checkpoint".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+/// Fail before touching the worktree when an object a restore needs is gone;
+/// older checkpoints did not keep their index tree reachable, so `git gc` may
+/// have collected it.
+fn ensure_checkpoint_objects(
+  repo_path: &Path,
+  head_oid: &str,
+  index_tree: &str,
+  worktree_tree: &str,
+) -> Result<(), CheckpointError> {
+  let objects = [
+    format!("{head_oid}^{{commit}}"),
+    format!("{index_tree}^{{tree}}"),
+    format!("{worktree_tree}^{{tree}}"),
+  ];
+  for object in &objects {
+    if run_git_optional(repo_path, &["cat-file", "-e", object.as_str()])?.is_none() {
+      return Err(CheckpointError::InvalidState(format!(
+        "Checkpoint object {object} is missing from the repository"
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// Describe what restoring a checkpoint would do to the current working tree.
+/// Paths are relative to the repository root; `change` is the effect of the
+/// restore (`modified`, `deleted` or `created`).
+pub fn preview_restore(
+  repo_path: &Path,
+  checkpoint_id: &str,
+  keep_untracked: bool,
+) -> Result<Vec<RestorePreviewEntry>, CheckpointError> {
+  let target = get_checkpoint(repo_path, checkpoint_id)?;
+  let index_tree = run_git(repo_path, &["write-tree"], &[], None)?;
+  let current_tree = snapshot_worktree_tree(repo_path, &index_tree, true, None)?;
+  let untracked = run_git_nul(
+    repo_path,
+    &["ls-files", "-z", "--others", "--exclude-standard"],
+    &[],
+    None,
+  )?;
+  let untracked: HashSet<&str> =
+    untracked.split('\0').filter(|path| !path.is_empty()).collect();
+  let output = run_git_nul(
+    repo_path,
+    &[
+      "diff-tree",
+      "-r",
+      "-z",
+      "--no-renames",
+      "--name-status",
+      current_tree.as_str(),
+      target.worktree_tree.as_str(),
+    ],
+    &[],
+    None,
+  )?;
+  let mut changes = Vec::new();
+  let mut fields = output.split('\0').filter(|field| !field.is_empty());
+  while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+    let change = match status {
+      "A" => "created",
+      "D" => "deleted",
+      _ => "modified",
+    };
+    changes.push((change, path));
+  }
+  // Ignored files missing from the snapshot are never touched by a restore.
+  let ignored = ignored_paths(
+    repo_path,
+    changes
+      .iter()
+      .filter(|(change, path)| *change == "deleted" && !untracked.contains(path))
+      .map(|(_, path)| *path),
+    None,
+  )?;
+  let mut entries = Vec::new();
+  for (change, path) in changes {
+    let is_untracked = untracked.contains(path);
+    if change == "deleted" && (ignored.contains(path) || (keep_untracked && is_untracked)) {
+      continue;
+    }
+    entries.push(RestorePreviewEntry {
+      path: path.to_string(),
+      change: change.to_string(),
+      untracked: is_untracked,
+    });
+  }
+  Ok(entries)
+}
+
+/// Paths that git ignores, with the patterns in `excludes_file` added to the
+/// repository's rules. Tracked paths are never ignored.
+pub fn ignored_paths<'a>(
+  repo_path: &Path,
+  paths: impl Iterator<Item = &'a str>,
+  excludes_file: Option<&Path>,
+) -> Result<HashSet<String>, CheckpointError> {
+  check_ignore(repo_path, paths, false, excludes_file)
+}
+
+/// Run `git check-ignore` over `paths`. With `no_index`, tracked paths are
+/// matched against the ignore rules too.
+fn check_ignore<'a>(
+  repo_path: &Path,
+  paths: impl Iterator<Item = &'a str>,
+  no_index: bool,
+  excludes_file: Option<&Path>,
+) -> Result<HashSet<String>, CheckpointError> {
+  let input = paths.collect::<Vec<_>>().join("\0");
+  if input.is_empty() {
+    return Ok(HashSet::new());
+  }
+  let mut args = vec!["check-ignore", "-z", "--stdin"];
+  if no_index {
+    args.push("--no-index");
+  }
+  let mut command = Command::new("git");
+  command
+    .current_dir(repo_path)
+    .args(&args)
+    .envs(excludes_env(excludes_file)?);
+  let output = git_output(&mut command, Some(&input))?;
+  // Exit code 1 means none of the paths are ignored.
+  if !output.status.success() && output.status.code() != Some(1) {
+    return Err(CheckpointError::Git {
+      command: "git check-ignore".to_string(),
+      message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    });
+  }
+  let stdout = String::from_utf8(output.stdout).map_err(|_| CheckpointError::InvalidUtf8)?;
+  Ok(
+    stdout
+      .split('\0')
+      .filter(|path| !path.is_empty())
+      .map(|path| path.to_string())
+      .collect(),
+  )
+}
+
+/// List every checkpoint ref in the repository with its parsed metadata.
+pub fn list_checkpoints(repo_path: &Path) -> Result<Vec<CheckpointInfo>, CheckpointError> {
+  ensure_repo(repo_path)?;
+  let prefix = format!("{CHECKPOINT_REF_PREFIX}/");
+  let output = run_git(
+    repo_path,
+    &[
+      "for-each-ref",
+      "--format=%1e%(refname)%1f%(objectname)%1f%(contents)",
+      prefix.as_str(),
+    ],
+    &[],
+    None,
+  )?;
+  let mut checkpoints = Vec::new();
+  for record in output.split('\x1e').filter(|record| !record.trim().is_empty()) {
+    let mut fields = record.splitn(3, '\x1f');
+    let ref_name = fields.next().unwrap_or_default().trim();
+    let commit = fields.next().unwrap_or_default().trim();
+    let body = fields.next().unwrap_or_default();
+    let Some(id) = ref_name.strip_prefix(prefix.as_str()) else {
+      continue;
+    };
+    match parse_checkpoint_info(id, commit, body) {
+      Ok(info) => checkpoints.push(info),
+      Err(err) => eprintln!("[checkpoint] skipping unreadable checkpoint {id}: {err}"),
+    }
+  }
+  checkpoints.sort_by_key(|checkpoint| std::cmp::Reverse(checkpoint.created));
+  Ok(checkpoints)
+}
+
+/// Read a single checkpoint's metadata.
+pub fn get_checkpoint(
+  repo_path: &Path,
+  checkpoint_id: &str,
+) -> Result<CheckpointInfo, CheckpointError> {
+  validate_checkpoint_id(checkpoint_id)?;
+  ensure_repo(repo_path)?;
+  let ref_name = format!("{CHECKPOINT_REF_PREFIX}/{checkpoint_id}");
+  let commit_oid =
+    run_git(repo_path, &["rev-parse", "-q", "--verify", ref_name.as_str()], &[], None)?;
+  let commit_body = run_git(repo_path, &["cat-file", "commit", commit_oid.as_str()], &[], None)?;
+  parse_checkpoint_info(checkpoint_id, &commit_oid, &commit_body)
+}
+
+/// Produce a diff between the worktree snapshots of two checkpoints.
+pub fn diff_checkpoints(
+  repo_path: &Path,
+  from_id: &str,
+  to_id: &str,
+  stat: bool,
+) -> Result<String, CheckpointError> {
+  let from = get_checkpoint(repo_path, from_id)?;
+  let to = get_checkpoint(repo_path, to_id)?;
+  let mut args = vec!["diff"];
+  if stat {
+    args.push("--stat");
+  }
+  args.push(from.worktree_tree.as_str());
+  args.push(to.worktree_tree.as_str());
+  run_git(repo_path, &args, &[], None)
+}
+
+/// Write a `git bundle` containing the checkpoint commit to `dest`. The bundle
+/// carries the snapshot trees; the base commit (`head`) must already exist in
+/// any repository that imports it. Gitignored files pulled in through
+/// `checkpointInclude` (such as `.env` files) are left out, since bundles are
+/// meant to be shared.
+pub fn export_checkpoint_bundle(
+  repo_path: &Path,
+  checkpoint_id: &str,
+  dest: &Path,
+) -> Result<CheckpointInfo, CheckpointError> {
+  let info = get_checkpoint(repo_path, checkpoint_id)?;
+  let ref_name = format!("{CHECKPOINT_REF_PREFIX}/{checkpoint_id}");
+  let export_ref = format!("{CHECKPOINT_EXPORT_REF_PREFIX}/{checkpoint_id}");
+  let dest = dest.to_str().ok_or(CheckpointError::InvalidUtf8)?;
+  let bundle_ref = match export_commit_without_ignored(repo_path, &info)? {
+    Some(commit_oid) => {
+      run_git(
+        repo_path,
+        &["update-ref", export_ref.as_str(), commit_oid.as_str()],
+        &[],
+        None,
+      )?;
+      export_ref.as_str()
+    }
+    None => ref_name.as_str(),
+  };
+  let result = run_git(
+    repo_path,
+    &["bundle", "create", "--quiet", dest, bundle_ref],
+    &[],
+    None,
+  );
+  if bundle_ref == export_ref {
+    run_git_optional(repo_path, &["update-ref", "-d", export_ref.as_str()])?;
+  }
+  result?;
+  Ok(info)
+}
+
+/// Copy of the checkpoint commit whose snapshot drops the gitignored files that
+/// were force-added to it, or `None` when it has none.
+fn export_commit_without_ignored(
+  repo_path: &Path,
+  info: &CheckpointInfo,
+) -> Result<Option<String>, CheckpointError> {
+  // Files added on top of the index snapshot are untracked or force-added ignored ones.
+  let added = run_git_nul(
+    repo_path,
+    &[
+      "diff-tree",
+      "-r",
+      "-z",
+      "--no-renames",
+      "--name-only",
+      "--diff-filter=A",
+      info.index_tree.as_str(),
+      info.worktree_tree.as_str(),
+    ],
+    &[],
+    None,
+  )?;
+  let added = added.split('\0').filter(|path| !path.is_empty());
+  let mut ignored = check_ignore(repo_path, added, true, None)?
+    .into_iter()
+    .collect::<Vec<_>>();
+  if ignored.is_empty() {
+    return Ok(None);
+  }
+  eprintln!(
+    "[checkpoint] leaving {} gitignored file(s) out of the export of {}",
+    ignored.len(),
+    info.id
+  );
+
+  let temp_dir = create_temp_dir("supertree-checkpoint-export")?;
+  let tmp_index = temp_dir.path.join("index");
+  let mut envs = vec![("GIT_INDEX_FILE", tmp_index.to_str().ok_or(CheckpointError::InvalidUtf8)?)];
+  run_git(repo_path, &["read-tree", info.worktree_tree.as_str()], &envs, None)?;
+  envs.push(("GIT_LITERAL_PATHSPECS", "1"));
+  ignored.sort();
+  let input = ignored.join("\0");
+  run_git(
+    repo_path,
+    &["rm", "--cached", "--quiet", "--pathspec-from-file=-", "--pathspec-file-nul"],
+    &envs,
+    Some(&input),
+  )?;
+  let tree = run_git(repo_path, &["write-tree"], &envs, None)?;
+
+  let body = run_git(repo_path, &["cat-file", "commit", info.commit.as_str()], &[], None)?;
+  let message = body
+    .split_once("\n\n")
+    .map(|(_, message)| message)
+    .unwrap_or_default()
+    .lines()
+    .map(|line| {
+      if line.starts_with("worktree-tree ") {
+        format!("worktree-tree {tree}")
+      } else {
+        line.to_string()
+      }
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+  let now = format_timestamp()?;
+  let parent = run_git_optional(
+    repo_path,
+    &["rev-parse", "--verify", "-q", &format!("{}^", info.commit)],
+  )?;
+  let mut args = vec!["commit-tree", tree.as_str()];
+  if let Some(parent) = parent.as_deref() {
+    args.push("-p");
+    args.push(parent);
+  }
+  let commit_oid = run_git(
+    repo_path,
+    &args,
+    &checkpoint_commit_env(&now),
+    Some(&format!("{message}\n")),
+  )?;
+  Ok(Some(commit_oid))
+}
+
+/// Import the checkpoint contained in a bundle under `checkpoint_id`, relabelled
+/// with `label`. Fails if the checkpoint's base commit is missing locally.
+pub fn import_checkpoint_bundle(
+  repo_path: &Path,
+  bundle_path: &Path,
+  checkpoint_id: &str,
+  label: &str,
+) -> Result<CheckpointInfo, CheckpointError> {
+  validate_checkpoint_id(checkpoint_id)?;
+  ensure_repo(repo_path)?;
+  let bundle = bundle_path.to_str().ok_or(CheckpointError::InvalidUtf8)?;
+  run_git(repo_path, &["bundle", "verify", "--quiet", bundle], &[], None)?;
+  let heads = run_git(repo_path, &["bundle", "list-heads", bundle], &[], None)?;
+  let prefixes = [
+    format!("{CHECKPOINT_REF_PREFIX}/"),
+    format!("{CHECKPOINT_EXPORT_REF_PREFIX}/"),
+  ];
+  let source_ref = heads
+    .lines()
+    .filter_map(|line| line.split_once(' '))
+    .map(|(_, ref_name)| ref_name.trim())
+    .find(|ref_name| prefixes.iter().any(|prefix| ref_name.starts_with(prefix.as_str())))
+    .ok_or_else(|| {
+      CheckpointError::InvalidState("Bundle does not contain a checkpoint".to_string())
+    })?
+    .to_string();
+
+  let staging_ref = format!("{CHECKPOINT_REF_PREFIX}-import/{checkpoint_id}");
+  run_git(
+    repo_path,
+    &[
+      "fetch",
+      "--quiet",
+      "--no-tags",
+      "--no-write-fetch-head",
+      bundle,
+      format!("+{source_ref}:{staging_ref}").as_str(),
+    ],
+    &[],
+    None,
+  )?;
+  let result = relabel_imported_checkpoint(repo_path, &staging_ref, checkpoint_id, label);
+  run_git_optional(repo_path, &["update-ref", "-d", staging_ref.as_str()])?;
+  result
+}
+
+fn relabel_imported_checkpoint(
+  repo_path: &Path,
+  staging_ref: &str,
+  checkpoint_id: &str,
+  label: &str,
+) -> Result<CheckpointInfo, CheckpointError> {
+  let source_oid = run_git(repo_path, &["rev-parse", "--verify", staging_ref], &[], None)?;
+  let body = run_git(repo_path, &["cat-file", "commit", source_oid.as_str()], &[], None)?;
+  let source = parse_checkpoint_info(checkpoint_id, &source_oid, &body)?;
+  let head_ref = format!("{}^{{commit}}", source.head);
+  if run_git_optional(repo_path, &["cat-file", "-e", head_ref.as_str()])?.is_none() {
+    return Err(CheckpointError::InvalidState(format!(
+      "Base commit {} is not available in this repository; fetch it first",
+      source.head
+    )));
+  }
+  let label = label.split_whitespace().collect::<Vec<_>>().join(" ");
+  let mut message = format!(
+    "checkpoint:{checkpoint_id}\nhead {}\nindex-tree {}\nworktree-tree {}\ncreated {} +0000\nlabel {label}\n",
+    source.head, source.index_tree, source.worktree_tree, source.created
+  );
+  if let Some(agent_type) = source.agent_type.as_deref() {
+    message.push_str(&format!("agent {agent_type}\n"));
+  }
+  let now = format_timestamp()?;
+  let parent = run_git_optional(repo_path, &["rev-parse", "--verify", "-q", &format!("{source_oid}^")])?;
+  let mut args = vec!["commit-tree", source.worktree_tree.as_str()];
+  if let Some(parent) = parent.as_deref() {
+    args.push("-p");
+    args.push(parent);
+  }
+  let commit_oid = run_git(repo_path, &args, &checkpoint_commit_env(&now), Some(&message))?;
+  let ref_name = format!("{CHECKPOINT_REF_PREFIX}/{checkpoint_id}");
+  run_git(
+    repo_path,
+    &["update-ref", ref_name.as_str(), commit_oid.as_str()],
+    &[],
+    None,
+  )?;
+  get_checkpoint(repo_path, checkpoint_id)
+}
+
+/// Delete many checkpoint refs in one `update-ref` transaction.
+pub fn delete_checkpoints(repo_path: &Path, checkpoint_ids: &[String]) -> Result<usize, CheckpointError> {
+  if checkpoint_ids.is_empty() {
+    return Ok(0);
+  }
+  ensure_repo(repo_path)?;
+  let mut input = String::new();
+  for checkpoint_id in checkpoint_ids {
+    validate_checkpoint_id(checkpoint_id)?;
+    input.push_str(&format!("delete {CHECKPOINT_REF_PREFIX}/{checkpoint_id}\n"));
+  }
+  run_git(repo_path, &["update-ref", "--stdin"], &[], Some(&input))?;
+  Ok(checkpoint_ids.len())
+}
+
+/// Run `git gc --prune` so objects only reachable from deleted checkpoint refs
+/// are removed once git's default grace period has passed; loose objects other
+/// worktrees just wrote are left alone. Returns the object store size in bytes
+/// before and after.
+pub fn gc_checkpoint_objects(repo_path: &Path) -> Result<(u64, u64), CheckpointError> {
+  ensure_repo(repo_path)?;
+  let before = object_store_bytes(repo_path)?;
+  run_git(repo_path, &["gc", "--prune", "--quiet"], &[], None)?;
+  let after = object_store_bytes(repo_path)?;
+  Ok((before, after))
+}
+
+fn object_store_bytes(repo_path: &Path) -> Result<u64, CheckpointError> {
+  let output = run_git(repo_path, &["count-objects", "-v"], &[], None)?;
+  let kib = output
+    .lines()
+    .filter_map(|line| line.split_once(':'))
+    .filter(|(key, _)| matches!(key.trim(), "size" | "size-pack" | "size-garbage"))
+    .filter_map(|(_, value)| value.trim().parse::<u64>().ok())
+    .sum::<u64>();
+  Ok(kib * 1024)
+}
+
 pub fn delete_checkpoint(repo_path: &Path, checkpoint_id: &str) -> Result<(), CheckpointError> {
   validate_checkpoint_id(checkpoint_id)?;
   ensure_repo(repo_path)?;
@@ -244,6 +1110,31 @@ fn extract_meta(body: &str, key: &str) -> Result<String, CheckpointError> {
   )))
 }
 
+fn parse_checkpoint_info(
+  checkpoint_id: &str,
+  commit: &str,
+  body: &str,
+) -> Result<CheckpointInfo, CheckpointError> {
+  let created = extract_meta(body, "created")?;
+  let created = created
+    .split_whitespace()
+    .next()
+    .and_then(|value| value.parse::<i64>().ok())
+    .ok_or_else(|| {
+      CheckpointError::MissingMetadata(format!("Checkpoint created time is invalid: {created}"))
+    })?;
+  Ok(CheckpointInfo {
+    id: checkpoint_id.to_string(),
+    commit: commit.to_string(),
+    head: extract_meta(body, "head")?,
+    index_tree: extract_meta(body, "index-tree")?,
+    worktree_tree: extract_meta(body, "worktree-tree")?,
+    created,
+    label: extract_meta(body, "label").ok(),
+    agent_type: extract_meta(body, "agent").ok(),
+  })
+}
+
 fn run_git_optional(
   repo_path: &Path,
   args: &[&str],
@@ -265,12 +1156,40 @@ fn run_git(
   args: &[&str],
   envs: &[(&str, &str)],
   input: Option<&str>,
+) -> Result<String, CheckpointError> {
+  run_git_nul(repo_path, args, envs, input).map(|stdout| stdout.trim().to_string())
+}
+
+/// `run_git` for `-z` output, returned untrimmed: NUL-separated paths may start
+/// or end with whitespace.
+fn run_git_nul(
+  repo_path: &Path,
+  args: &[&str],
+  envs: &[(&str, &str)],
+  input: Option<&str>,
 ) -> Result<String, CheckpointError> {
   let mut command = Command::new("git");
   command.current_dir(repo_path).args(args);
   for (key, value) in envs {
     command.env(key, value);
   }
+  let output = git_output(&mut command, input)?;
+  if !output.status.success() {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    return Err(CheckpointError::Git {
+      command: format!("git {}", args.join(" ")),
+      message: stderr.trim().to_string(),
+    });
+  }
+  String::from_utf8(output.stdout).map_err(|_| CheckpointError::InvalidUtf8)
+}
+
+/// Run `command`, feeding it `input` from a separate thread while its output is
+/// drained; writing everything first deadlocks once git fills the stdout pipe.
+fn git_output(
+  command: &mut Command,
+  input: Option<&str>,
+) -> Result<std::process::Output, CheckpointError> {
   if input.is_some() {
     command.stdin(std::process::Stdio::piped());
   }
@@ -279,21 +1198,25 @@ fn run_git(
     .stderr(std::process::Stdio::piped())
     .spawn()
     .map_err(CheckpointError::Io)?;
-  if let Some(input) = input {
-    if let Some(stdin) = child.stdin.as_mut() {
-      stdin.write_all(input.as_bytes()).map_err(CheckpointError::Io)?;
+  let writer = match (input, child.stdin.take()) {
+    (Some(input), Some(mut stdin)) => {
+      let input = input.as_bytes().to_vec();
+      Some(std::thread::spawn(move || stdin.write_all(&input)))
     }
-  }
+    _ => None,
+  };
   let output = child.wait_with_output().map_err(CheckpointError::Io)?;
-  if !output.status.success() {
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    return Err(CheckpointError::Git {
-      command: format!("git {}", args.join(" ")),
-      message: stderr.trim().to_string(),
-    });
+  let written = match writer {
+    Some(writer) => writer
+      .join()
+      .unwrap_or_else(|_| Err(std::io::Error::other("git stdin writer panicked"))),
+    None => Ok(()),
+  };
+  // A git that exits early closes the pipe; its own error explains more.
+  if output.status.success() {
+    written.map_err(CheckpointError::Io)?;
   }
-  let stdout = String::from_utf8(output.stdout).map_err(|_| CheckpointError::InvalidUtf8)?;
-  Ok(stdout.trim().to_string())
+  Ok(output)
 }
 
 fn create_temp_dir(prefix: &str) -> Result<TempDirGuard, CheckpointError> {
@@ -335,3 +1258,114 @@ fn format_timestamp() -> Result<String, CheckpointError> {
   // Git accepts Unix timestamp with timezone offset.
   Ok(format!("{stamp} +0000"))
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    backup_before_restore, changed_paths, create_named_checkpoint, ignored_paths, restore_checkpoint,
+    restore_checkpoint_tree, run_git,
+  };
+  use std::path::{Path, PathBuf};
+  use std::process::Command;
+
+  /// Throwaway repository on `main` with one commit, removed on drop.
+  struct TempRepo(PathBuf);
+
+  impl TempRepo {
+    fn new(name: &str) -> Self {
+      let path =
+        std::env::temp_dir().join(format!("supertree-checkpoints-{name}-{}", std::process::id()));
+      let _ = std::fs::remove_dir_all(&path);
+      std::fs::create_dir_all(&path).unwrap();
+      let repo = TempRepo(path);
+      repo.git(&["init", "-q", "-b", "main"]);
+      repo.write("a.txt", "a\n");
+      repo.write("b.txt", "b\n");
+      repo.git(&["add", "a.txt", "b.txt"]);
+      repo.git(&["commit", "-q", "-m", "init"]);
+      repo
+    }
+
+    fn path(&self) -> &Path {
+      &self.0
+    }
+
+    fn write(&self, name: &str, contents: &str) {
+      std::fs::write(self.0.join(name), contents).unwrap();
+    }
+
+    fn read(&self, name: &str) -> Option<String> {
+      std::fs::read_to_string(self.0.join(name)).ok()
+    }
+
+    fn git(&self, args: &[&str]) -> String {
+      let output = Command::new("git")
+        .current_dir(&self.0)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+      assert!(output.status.success(), "git {args:?}: {}", String::from_utf8_lossy(&output.stderr));
+      String::from_utf8(output.stdout).unwrap()
+    }
+  }
+
+  impl Drop for TempRepo {
+    fn drop(&mut self) {
+      let _ = std::fs::remove_dir_all(&self.0);
+    }
+  }
+
+  #[test]
+  fn fork_restores_over_uncommitted_agent_edits() {
+    let repo = TempRepo::new("fork-dirty");
+    repo.write("a.txt", "turn one\n");
+    repo.write("new.txt", "created in turn one\n");
+    create_named_checkpoint(repo.path(), "session-s1-turn-1-assistant", "turn 1").unwrap();
+
+    // Later turns keep editing without committing, as agents do.
+    repo.write("a.txt", "turn two\n");
+    std::fs::remove_file(repo.path().join("b.txt")).unwrap();
+    repo.write("notes.txt", "untracked scratch\n");
+
+    let backup = backup_before_restore(repo.path(), "ws1", "Before forking").unwrap();
+    restore_checkpoint_tree(repo.path(), "session-s1-turn-1-assistant").unwrap();
+    assert_eq!(repo.read("a.txt").as_deref(), Some("turn one\n"));
+    assert_eq!(repo.read("b.txt").as_deref(), Some("b\n"));
+    assert_eq!(repo.read("new.txt").as_deref(), Some("created in turn one\n"));
+    assert_eq!(repo.read("notes.txt").as_deref(), Some("untracked scratch\n"));
+    assert_eq!(repo.git(&["rev-list", "--count", "HEAD"]).trim(), "1");
+    // `new.txt` was untracked when the checkpoint was taken and stays so.
+    assert_eq!(repo.git(&["ls-files", "new.txt"]), "");
+
+    // The edits the fork overwrote can be brought back from the backup.
+    restore_checkpoint(repo.path(), &backup).unwrap();
+    assert_eq!(repo.read("a.txt").as_deref(), Some("turn two\n"));
+    assert_eq!(repo.read("b.txt"), None);
+  }
+
+  #[test]
+  fn check_ignore_handles_output_larger_than_a_pipe() {
+    let repo = TempRepo::new("ignore-many");
+    repo.write(".gitignore", "build/\n");
+    // Enough paths that git's output fills the pipe before all input is written.
+    let paths = (0..20_000)
+      .map(|index| format!("build/generated/output-file-{index:05}.o"))
+      .collect::<Vec<_>>();
+    let ignored = ignored_paths(repo.path(), paths.iter().map(String::as_str), None).unwrap();
+    assert_eq!(ignored.len(), paths.len());
+  }
+
+  #[test]
+  fn nul_separated_paths_keep_surrounding_whitespace() {
+    let repo = TempRepo::new("nul-paths");
+    let before = run_git(repo.path(), &["write-tree"], &[], None).unwrap();
+    repo.write(" leading.txt", "x\n");
+    repo.write("trailing.txt ", "y\n");
+    repo.git(&["add", "--all"]);
+    let after = run_git(repo.path(), &["write-tree"], &[], None).unwrap();
+    let mut paths = changed_paths(repo.path(), &before, &after).unwrap();
+    paths.sort();
+    assert_eq!(paths, vec![" leading.txt".to_string(), "trailing.txt ".to_string()]);
+  }
+}
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fs;
 use std::io::Read;
@@ -16,6 +16,14 @@ pub struct RepoScripts {
   pub run_script_mode: Option<String>,
 }
 
+/// Checkpoint snapshot patterns from `supertree.json` (git glob pathspecs).
+#[derive(Debug, Clone, Default)]
+pub struct CheckpointPatterns {
+  pub include: Vec<String>,
+  pub exclude: Vec<String>,
+}
+
+
 /// Basic git metadata used when adding repositories.
 #[derive(Debug, Clone)]
 pub struct RepoIdentity {
@@ -43,6 +51,57 @@ pub struct BranchSyncStatus {
   pub behind: i64,
 }
 
+/// Single line attribution parsed from `git blame --porcelain`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameLine {
+  pub line_number: u32,
+  pub commit: String,
+  pub author: String,
+  pub author_time: i64,
+  pub summary: String,
+  pub committed: bool,
+  pub content: String,
+}
+
+/// Commit touching a file, as reported by `git log --follow`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+  pub commit: String,
+  pub author: String,
+  pub author_email: String,
+  pub author_time: i64,
+  pub summary: String,
+  pub path: Option<String>,
+}
+
+/// Local branch summary relative to the repository default branch.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchDetails {
+  pub name: String,
+  pub last_commit: String,
+  pub last_commit_summary: String,
+  pub last_commit_time: i64,
+  pub ahead: i64,
+  pub behind: i64,
+  pub merged: bool,
+  pub worktree_path: Option<String>,
+}
+
+/// Stash entry from the repository-wide stash list.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StashEntry {
+  pub index: usize,
+  pub reference: String,
+  pub commit: String,
+  pub branch: Option<String>,
+  pub message: String,
+  pub created_at: i64,
+}
+
 #[derive(Debug)]
 pub enum GitError {
   Io(std::io::Error),
@@ -80,6 +139,9 @@ impl std::error::Error for GitError {
 struct SupertreeConfig {
   scripts: Option<SupertreeScripts>,
   run_script_mode: Option<String>,
+  checkpoint_include: Option<Vec<String>>,
+  checkpoint_exclude: Option<Vec<String>>,
+  spotlight_ignore: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -174,6 +236,65 @@ pub fn read_supertree_config(path: &Path) -> Result<Option<RepoScripts>, GitErro
   }))
 }
 
+/// Read `checkpointInclude` / `checkpointExclude` from `supertree.json` if present.
+pub fn read_checkpoint_patterns(path: &Path) -> Result<CheckpointPatterns, GitError> {
+  let config_path = path.join("supertree.json");
+  if !config_path.exists() {
+    return Ok(CheckpointPatterns::default());
+  }
+  let content = std::fs::read_to_string(&config_path).map_err(GitError::Io)?;
+  let parsed: SupertreeConfig =
+    serde_json::from_str(&content).map_err(|err| GitError::Parse(err.to_string()))?;
+  let clean = |patterns: Option<Vec<String>>| {
+    patterns
+      .unwrap_or_default()
+      .into_iter()
+      .map(|pattern| pattern.trim().to_string())
+      .filter(|pattern| !pattern.is_empty())
+      .collect::<Vec<_>>()
+  };
+  Ok(CheckpointPatterns {
+    include: clean(parsed.checkpoint_include),
+    exclude: clean(parsed.checkpoint_exclude),
+  })
+}
+
+/// Read the `spotlightIgnore` gitignore-style patterns from `supertree.json` if
+/// present. They add to `.gitignore`, which spotlight always honours.
+pub fn read_spotlight_ignore(path: &Path) -> Result<Vec<String>, GitError> {
+  let config_path = path.join("supertree.json");
+  if !config_path.exists() {
+    return Ok(Vec::new());
+  }
+  let content = std::fs::read_to_string(&config_path).map_err(GitError::Io)?;
+  let parsed: SupertreeConfig =
+    serde_json::from_str(&content).map_err(|err| GitError::Parse(err.to_string()))?;
+  Ok(
+    parsed
+      .spotlight_ignore
+      .unwrap_or_default()
+      .into_iter()
+      .map(|pattern| pattern.trim().to_string())
+      .filter(|pattern| !pattern.is_empty() && !pattern.starts_with('#'))
+      .collect(),
+  )
+}
+
+/// The user's global ignore file: `core.excludesFile`, or git's XDG default.
+pub fn global_excludes_file(path: &Path) -> Option<PathBuf> {
+  let path_str = path.to_str()?;
+  if let Ok(value) = run_git(&["-C", path_str, "config", "--path", "--get", "core.excludesFile"]) {
+    if !value.is_empty() {
+      return Some(PathBuf::from(value));
+    }
+  }
+  let config_home = std::env::var_os("XDG_CONFIG_HOME")
+    .filter(|value| !value.is_empty())
+    .map(PathBuf::from)
+    .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+  Some(config_home.join("git").join("ignore"))
+}
+
 /// Derive a repository name from a local path.
 pub fn repo_name_from_path(path: &Path) -> Option<String> {
   path.file_name()
@@ -220,6 +341,313 @@ pub fn branch_exists(path: &Path, branch: &str) -> Result<bool, GitError> {
   Ok(branches.iter().any(|name| name == branch))
 }
 
+/// List local branches with last commit info, divergence and merge state vs `base`.
+pub fn list_branch_details(path: &Path, base: &str) -> Result<Vec<BranchDetails>, GitError> {
+  let path_str = path.to_str().ok_or(GitError::InvalidUtf8)?;
+  let output = run_git(&[
+    "-C",
+    path_str,
+    "for-each-ref",
+    "--format=%(refname:short)%1f%(objectname)%1f%(committerdate:unix)%1f%(subject)",
+    "refs/heads",
+  ])?;
+  let merged = list_merged_branches(path, base)?;
+  let worktrees = list_worktree_branches(path)?;
+  let base_ref = resolve_merge_base_ref(path, base)?;
+  let mut details = Vec::new();
+  let mut rows = Vec::new();
+  for line in output.lines().filter(|line| !line.trim().is_empty()) {
+    let mut fields = line.split('\x1f');
+    let name = fields.next().unwrap_or_default().trim().to_string();
+    let last_commit = fields.next().unwrap_or_default().trim().to_string();
+    let last_commit_time = fields
+      .next()
+      .unwrap_or_default()
+      .trim()
+      .parse::<i64>()
+      .unwrap_or(0);
+    let last_commit_summary = fields.next().unwrap_or_default().to_string();
+    rows.push((name, last_commit, last_commit_time, last_commit_summary));
+  }
+  let divergence = match base_ref.as_deref() {
+    Some(base_ref) => count_all_divergence(path, base_ref)?,
+    None => HashMap::new(),
+  };
+  for (name, last_commit, last_commit_time, last_commit_summary) in rows {
+    let (ahead, behind) = if name != base {
+      divergence.get(&name).copied().unwrap_or((0, 0))
+    } else {
+      (0, 0)
+    };
+    details.push(BranchDetails {
+      merged: name != base && merged.iter().any(|branch| branch == &name),
+      worktree_path: worktrees.get(&name).cloned(),
+      name,
+      last_commit,
+      last_commit_summary,
+      last_commit_time,
+      ahead,
+      behind,
+    });
+  }
+  Ok(details)
+}
+
+/// Ref that branches are compared against: `origin/<base>` when it exists, since the
+/// local default branch is often behind, otherwise the local `base`.
+pub fn resolve_merge_base_ref(path: &Path, base: &str) -> Result<Option<String>, GitError> {
+  let base = base.trim();
+  if base.is_empty() {
+    return Ok(None);
+  }
+  let path_str = path.to_str().ok_or(GitError::InvalidUtf8)?;
+  for candidate in [format!("refs/remotes/origin/{base}"), format!("refs/heads/{base}")] {
+    if run_git(&["-C", path_str, "show-ref", "--verify", "--quiet", candidate.as_str()]).is_ok() {
+      return Ok(Some(candidate));
+    }
+  }
+  Ok(None)
+}
+
+/// List local branches whose tips are reachable from `base` (its upstream when present).
+pub fn list_merged_branches(path: &Path, base: &str) -> Result<Vec<String>, GitError> {
+  let Some(base_ref) = resolve_merge_base_ref(path, base)? else {
+    return Ok(Vec::new());
+  };
+  let merged_arg = format!("--merged={base_ref}");
+  let output = run_git(&[
+    "-C",
+    path.to_str().ok_or(GitError::InvalidUtf8)?,
+    "for-each-ref",
+    merged_arg.as_str(),
+    "--format=%(refname:short)",
+    "refs/heads",
+  ])?;
+  Ok(
+    output
+      .lines()
+      .map(|line| line.trim())
+      .filter(|line| !line.is_empty() && *line != base)
+      .map(|line| line.to_string())
+      .collect(),
+  )
+}
+
+/// Merged branches that can be deleted: not the base, not owned by a workspace
+/// (archived ones included) and not checked out anywhere. Sorted by name.
+pub fn prunable_branches(
+  merged: Vec<String>,
+  base: &str,
+  owned: &HashSet<String>,
+  worktrees: &HashMap<String, String>,
+) -> Vec<String> {
+  let mut branches: Vec<String> = merged
+    .into_iter()
+    .filter(|branch| branch != base && !owned.contains(branch) && !worktrees.contains_key(branch))
+    .collect();
+  branches.sort();
+  branches
+}
+
+/// Map branch names to the worktree paths that currently have them checked out.
+pub fn list_worktree_branches(path: &Path) -> Result<HashMap<String, String>, GitError> {
+  let output = run_git(&[
+    "-C",
+    path.to_str().ok_or(GitError::InvalidUtf8)?,
+    "worktree",
+    "list",
+    "--porcelain",
+  ])?;
+  let mut branches = HashMap::new();
+  let mut current_path: Option<String> = None;
+  for line in output.lines() {
+    if let Some(worktree) = line.strip_prefix("worktree ") {
+      current_path = Some(worktree.trim().to_string());
+    } else if let Some(branch) = line.strip_prefix("branch ") {
+      let branch = branch.trim();
+      let branch = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+      if let Some(worktree) = current_path.clone() {
+        branches.insert(branch.to_string(), worktree);
+      }
+    } else if line.trim().is_empty() {
+      current_path = None;
+    }
+  }
+  Ok(branches)
+}
+
+/// Delete a local branch, refusing when any worktree has it checked out.
+pub fn delete_branch(path: &Path, branch: &str, force: bool) -> Result<(), GitError> {
+  let branch = branch.trim();
+  if branch.is_empty() {
+    return Err(GitError::MissingPath("Branch name is required".to_string()));
+  }
+  if let Some(worktree) = list_worktree_branches(path)?.get(branch) {
+    return Err(GitError::CommandFailed {
+      command: format!("git branch -d {branch}"),
+      message: format!("Branch is checked out in worktree {worktree}"),
+    });
+  }
+  run_git(&[
+    "-C",
+    path.to_str().ok_or(GitError::InvalidUtf8)?,
+    "branch",
+    if force { "-D" } else { "-d" },
+    "--",
+    branch,
+  ])?;
+  Ok(())
+}
+
+/// List stashes; worktrees share one stash list, so each entry carries its source branch.
+pub fn list_stashes(path: &Path) -> Result<Vec<StashEntry>, GitError> {
+  let output = run_git(&[
+    "-C",
+    path.to_str().ok_or(GitError::InvalidUtf8)?,
+    "stash",
+    "list",
+    "--format=%gd%x1f%H%x1f%ct%x1f%gs",
+  ])?;
+  let mut entries = Vec::new();
+  for (index, line) in output.lines().filter(|line| !line.trim().is_empty()).enumerate() {
+    let mut fields = line.split('\x1f');
+    let reference = fields.next().unwrap_or_default().trim().to_string();
+    let commit = fields.next().unwrap_or_default().trim().to_string();
+    let created_at = fields
+      .next()
+      .unwrap_or_default()
+      .trim()
+      .parse::<i64>()
+      .unwrap_or(0);
+    let subject = fields.next().unwrap_or_default();
+    let (branch, message) = parse_stash_subject(subject);
+    entries.push(StashEntry {
+      index,
+      reference,
+      commit,
+      branch,
+      message,
+      created_at,
+    });
+  }
+  Ok(entries)
+}
+
+/// Stash worktree changes, keeping `.context` out of the stash. Returns the new entry, if any.
+pub fn stash_push(
+  path: &Path,
+  message: Option<&str>,
+  include_untracked: bool,
+) -> Result<Option<StashEntry>, GitError> {
+  let path_str = path.to_str().ok_or(GitError::InvalidUtf8)?;
+  let before = list_stashes(path)?.first().map(|entry| entry.commit.clone());
+  let mut args = vec!["-C", path_str, "stash", "push"];
+  if include_untracked {
+    args.push("--include-untracked");
+  }
+  if let Some(message) = message.map(str::trim).filter(|value| !value.is_empty()) {
+    args.push("-m");
+    args.push(message);
+  }
+  args.extend(["--", ".", ":(exclude).context"]);
+  run_git(&args)?;
+  let latest = list_stashes(path)?.into_iter().next();
+  Ok(latest.filter(|entry| Some(&entry.commit) != before.as_ref()))
+}
+
+/// Apply a stash identified by its commit id, optionally dropping it afterwards.
+pub fn stash_apply(path: &Path, commit: &str, pop: bool) -> Result<(), GitError> {
+  let entry = find_stash(path, commit)?;
+  run_git(&[
+    "-C",
+    path.to_str().ok_or(GitError::InvalidUtf8)?,
+    "stash",
+    if pop { "pop" } else { "apply" },
+    entry.reference.as_str(),
+  ])?;
+  Ok(())
+}
+
+/// Drop a stash identified by its commit id.
+pub fn stash_drop(path: &Path, commit: &str) -> Result<(), GitError> {
+  let entry = find_stash(path, commit)?;
+  run_git(&[
+    "-C",
+    path.to_str().ok_or(GitError::InvalidUtf8)?,
+    "stash",
+    "drop",
+    entry.reference.as_str(),
+  ])?;
+  Ok(())
+}
+
+// Stash indexes shift whenever another worktree pushes or drops, so callers address
+// entries by commit id and we resolve the current `stash@{n}` right before acting.
+fn find_stash(path: &Path, commit: &str) -> Result<StashEntry, GitError> {
+  let commit = commit.trim();
+  list_stashes(path)?
+    .into_iter()
+    .find(|entry| !commit.is_empty() && entry.commit.starts_with(commit))
+    .ok_or_else(|| GitError::MissingPath(format!("Stash not found: {commit}")))
+}
+
+fn parse_stash_subject(subject: &str) -> (Option<String>, String) {
+  let rest = subject
+    .strip_prefix("WIP on ")
+    .or_else(|| subject.strip_prefix("On "));
+  let Some((branch, message)) = rest.and_then(|value| value.split_once(": ")) else {
+    return (None, subject.to_string());
+  };
+  let branch = if branch == "(no branch)" {
+    None
+  } else {
+    Some(branch.to_string())
+  };
+  (branch, message.to_string())
+}
+
+/// Ahead/behind counts of every local branch against `base_ref`, keyed by branch name.
+/// Uses one `for-each-ref` call where git supports `ahead-behind` (2.41+).
+fn count_all_divergence(path: &Path, base_ref: &str) -> Result<HashMap<String, (i64, i64)>, GitError> {
+  let path_str = path.to_str().ok_or(GitError::InvalidUtf8)?;
+  let format = format!("--format=%(refname:short)%1f%(ahead-behind:{base_ref})");
+  if let Ok(output) = run_git(&["-C", path_str, "for-each-ref", format.as_str(), "refs/heads"]) {
+    let mut counts = HashMap::new();
+    for line in output.lines() {
+      let Some((name, value)) = line.split_once('\x1f') else {
+        continue;
+      };
+      let mut values = value.split_whitespace().map(|value| value.parse::<i64>());
+      if let (Some(Ok(ahead)), Some(Ok(behind))) = (values.next(), values.next()) {
+        counts.insert(name.trim().to_string(), (ahead, behind));
+      }
+    }
+    return Ok(counts);
+  }
+  let mut counts = HashMap::new();
+  for branch in list_branches(path)? {
+    counts.insert(branch.clone(), count_divergence(path, base_ref, &branch)?);
+  }
+  Ok(counts)
+}
+
+fn count_divergence(path: &Path, base_ref: &str, branch: &str) -> Result<(i64, i64), GitError> {
+  let range = format!("{base_ref}...refs/heads/{branch}");
+  let output = run_git(&[
+    "-C",
+    path.to_str().ok_or(GitError::InvalidUtf8)?,
+    "rev-list",
+    "--left-right",
+    "--count",
+    range.as_str(),
+  ])?;
+  let mut counts = output.split_whitespace().map(|value| value.parse::<i64>());
+  match (counts.next(), counts.next()) {
+    (Some(Ok(behind)), Some(Ok(ahead))) => Ok((ahead, behind)),
+    _ => Err(GitError::Parse(format!("Invalid rev-list count output: {output}"))),
+  }
+}
+
 /// Create a git worktree for the given branch.
 pub fn create_worktree(repo_path: &Path, workspace_path: &Path, branch: &str) -> Result<(), GitError> {
   let repo_str = repo_path.to_str().ok_or(GitError::InvalidUtf8)?;
@@ -448,6 +876,167 @@ pub fn branch_sync_status(path: &Path) -> Result<BranchSyncStatus, GitError> {
   })
 }
 
+/// Attribute each line of a worktree file to the commit that last touched it.
+pub fn blame_file(path: &Path, file: &Path) -> Result<Vec<BlameLine>, GitError> {
+  let normalized = normalize_diff_path(path, file)?;
+  let normalized_str = normalized.to_string_lossy().to_string();
+  let output = match run_git_raw(&[
+    "-C",
+    path.to_str().ok_or(GitError::InvalidUtf8)?,
+    "blame",
+    "--porcelain",
+    "--",
+    normalized_str.as_str(),
+  ]) {
+    Ok(output) => output,
+    // Nothing is committed yet, so every line is an uncommitted change.
+    Err(err) if is_missing_head(&err) => return uncommitted_blame(&path.join(&normalized)),
+    Err(err) => return Err(err),
+  };
+  parse_blame_porcelain(&output)
+}
+
+/// Blame for a file in a repository without commits, attributed the way git
+/// reports uncommitted lines.
+fn uncommitted_blame(file: &Path) -> Result<Vec<BlameLine>, GitError> {
+  let content = fs::read(file).map_err(GitError::Io)?;
+  let author_time = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|duration| duration.as_secs() as i64)
+    .unwrap_or(0);
+  Ok(
+    String::from_utf8_lossy(&content)
+      .lines()
+      .enumerate()
+      .map(|(index, line)| BlameLine {
+        line_number: index as u32 + 1,
+        commit: "0".repeat(40),
+        author: "Not Committed Yet".to_string(),
+        author_time,
+        summary: String::new(),
+        committed: false,
+        content: line.to_string(),
+      })
+      .collect(),
+  )
+}
+
+/// List commits that touched a file, following renames.
+pub fn file_history(
+  path: &Path,
+  file: &Path,
+  limit: usize,
+) -> Result<Vec<FileHistoryEntry>, GitError> {
+  let normalized = normalize_diff_path(path, file)?;
+  let normalized_str = normalized.to_string_lossy().to_string();
+  let limit_arg = format!("--max-count={}", limit.max(1));
+  let output = match run_git_raw(&[
+    "-C",
+    path.to_str().ok_or(GitError::InvalidUtf8)?,
+    "log",
+    "--follow",
+    "--name-only",
+    limit_arg.as_str(),
+    "--format=%x1e%H%x1f%an%x1f%ae%x1f%at%x1f%s",
+    "--",
+    normalized_str.as_str(),
+  ]) {
+    Ok(output) => output,
+    Err(err) if is_missing_head(&err) => return Ok(Vec::new()),
+    Err(err) => return Err(err),
+  };
+  let mut entries = Vec::new();
+  for record in output.split('\x1e') {
+    let mut lines = record.lines();
+    let Some(header) = lines.next().filter(|line| !line.trim().is_empty()) else {
+      continue;
+    };
+    let mut fields = header.split('\x1f');
+    let commit = fields.next().unwrap_or_default().trim().to_string();
+    let author = fields.next().unwrap_or_default().to_string();
+    let author_email = fields.next().unwrap_or_default().to_string();
+    let author_time = fields
+      .next()
+      .unwrap_or_default()
+      .trim()
+      .parse::<i64>()
+      .map_err(|_| GitError::Parse(format!("Invalid commit time for {commit}")))?;
+    let summary = fields.next().unwrap_or_default().to_string();
+    let path = lines
+      .map(|line| line.trim())
+      .find(|line| !line.is_empty())
+      .map(|line| line.to_string());
+    entries.push(FileHistoryEntry {
+      commit,
+      author,
+      author_email,
+      author_time,
+      summary,
+      path,
+    });
+  }
+  Ok(entries)
+}
+
+#[derive(Default, Clone)]
+struct BlameCommitInfo {
+  author: String,
+  author_time: i64,
+  summary: String,
+}
+
+fn parse_blame_porcelain(output: &str) -> Result<Vec<BlameLine>, GitError> {
+  let mut commits: HashMap<String, BlameCommitInfo> = HashMap::new();
+  let mut lines = Vec::new();
+  let mut current: Option<(String, u32)> = None;
+  for raw in output.lines() {
+    if let Some(content) = raw.strip_prefix('\t') {
+      let (commit, line_number) = current.take().ok_or_else(|| {
+        GitError::Parse("Blame content without a commit header".to_string())
+      })?;
+      let info = commits.get(&commit).cloned().unwrap_or_default();
+      lines.push(BlameLine {
+        line_number,
+        committed: commit.chars().any(|ch| ch != '0'),
+        commit,
+        author: info.author,
+        author_time: info.author_time,
+        summary: info.summary,
+        content: content.to_string(),
+      });
+      continue;
+    }
+    let (key, value) = raw.split_once(' ').unwrap_or((raw, ""));
+    if is_blame_header(key) {
+      let line_number = value
+        .split_whitespace()
+        .nth(1)
+        .and_then(|value| value.parse::<u32>().ok())
+        .ok_or_else(|| GitError::Parse(format!("Invalid blame header: {raw}")))?;
+      commits.entry(key.to_string()).or_default();
+      current = Some((key.to_string(), line_number));
+      continue;
+    }
+    let Some((commit, _)) = current.as_ref() else {
+      continue;
+    };
+    let Some(info) = commits.get_mut(commit) else {
+      continue;
+    };
+    match key {
+      "author" => info.author = value.to_string(),
+      "author-time" => info.author_time = value.trim().parse::<i64>().unwrap_or(0),
+      "summary" => info.summary = value.to_string(),
+      _ => {}
+    }
+  }
+  Ok(lines)
+}
+
+fn is_blame_header(token: &str) -> bool {
+  matches!(token.len(), 40 | 64) && token.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
 fn run_diff_with_base(
   path: &Path,
   file: Option<&Path>,
@@ -479,6 +1068,7 @@ fn is_missing_head(error: &GitError) -> bool {
         || lower.contains("unknown revision")
         || lower.contains("bad object")
         || lower.contains("unknown revision or path")
+        || lower.contains("no such ref")
     }
     _ => false,
   }
@@ -671,3 +1261,104 @@ fn run_git_raw(args: &[&str]) -> Result<String, GitError> {
   let stdout = String::from_utf8(output.stdout).map_err(|_| GitError::InvalidUtf8)?;
   Ok(stdout)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    blame_file, delete_branch, list_merged_branches, list_worktree_branches, parse_blame_porcelain,
+    prunable_branches,
+  };
+  use std::collections::HashSet;
+  use std::path::{Path, PathBuf};
+  use std::process::Command;
+
+  /// Throwaway repository on `main`, removed on drop.
+  struct TempRepo(PathBuf);
+
+  impl TempRepo {
+    /// Repository without any commits.
+    fn empty(name: &str) -> Self {
+      let path = std::env::temp_dir().join(format!("supertree-git-{name}-{}", std::process::id()));
+      let _ = std::fs::remove_dir_all(&path);
+      std::fs::create_dir_all(&path).unwrap();
+      let repo = TempRepo(path);
+      repo.git(&["init", "-q", "-b", "main"]);
+      repo
+    }
+
+    /// Repository with `a.txt` committed on `main`.
+    fn new(name: &str) -> Self {
+      let repo = TempRepo::empty(name);
+      std::fs::write(repo.path().join("a.txt"), "a\n").unwrap();
+      repo.git(&["add", "a.txt"]);
+      repo.git(&["commit", "-q", "-m", "init"]);
+      repo
+    }
+
+    fn path(&self) -> &Path {
+      &self.0
+    }
+
+    fn git(&self, args: &[&str]) -> String {
+      let output = Command::new("git")
+        .current_dir(&self.0)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+      assert!(output.status.success(), "git {args:?}: {}", String::from_utf8_lossy(&output.stderr));
+      String::from_utf8(output.stdout).unwrap()
+    }
+  }
+
+  impl Drop for TempRepo {
+    fn drop(&mut self) {
+      let _ = std::fs::remove_dir_all(&self.0);
+    }
+  }
+
+  #[test]
+  fn prune_keeps_branches_of_archived_workspaces() {
+    let repo = TempRepo::new("prune");
+    repo.git(&["branch", "archived-ws"]);
+    repo.git(&["branch", "stale"]);
+    let merged = list_merged_branches(repo.path(), "main").unwrap();
+    let worktrees = list_worktree_branches(repo.path()).unwrap();
+    // Archived workspaces have no worktree, only their database row.
+    let owned = HashSet::from(["archived-ws".to_string()]);
+    let candidates = prunable_branches(merged, "main", &owned, &worktrees);
+    assert_eq!(candidates, vec!["stale".to_string()]);
+    for branch in &candidates {
+      delete_branch(repo.path(), branch, false).unwrap();
+    }
+    let remaining = repo.git(&["branch", "--format=%(refname:short)"]);
+    assert_eq!(remaining.lines().collect::<Vec<_>>(), vec!["archived-ws", "main"]);
+  }
+
+  #[test]
+  fn parses_blame_porcelain_with_repeated_commits() {
+    let commit = "a".repeat(40);
+    let uncommitted = "0".repeat(40);
+    let output = format!(
+      "{commit} 1 1 2\nauthor Agent\nauthor-time 1700000000\nsummary Add file\nfilename src/lib.rs\n\tfirst\n{commit} 2 2\n\tsecond\n{uncommitted} 3 3 1\nauthor Not Committed Yet\nauthor-time 1700000100\nsummary Version of src/lib.rs from src/lib.rs\n\tthird\n"
+    );
+    let lines = parse_blame_porcelain(&output).expect("parse blame");
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1].author, "Agent");
+    assert_eq!(lines[1].author_time, 1_700_000_000);
+    assert_eq!(lines[1].content, "second");
+    assert!(lines[0].committed);
+    assert!(!lines[2].committed);
+  }
+
+  #[test]
+  fn blame_without_commits_reports_uncommitted_lines() {
+    let repo = TempRepo::empty("blame-unborn");
+    std::fs::write(repo.path().join("a.txt"), "one\ntwo\n").unwrap();
+    let lines = blame_file(repo.path(), Path::new("a.txt")).unwrap();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| !line.committed));
+    assert_eq!(lines[1].line_number, 2);
+    assert_eq!(lines[1].content, "two");
+  }
+}
@@ -7,10 +7,15 @@ mod attachments;
 mod path_utils;
 mod paths;
 mod repos;
+mod search;
 mod settings;
 mod sessions;
 mod sidecar;
 mod spotlight;
+mod titles;
+mod tool_calls;
+mod transcripts;
+mod usage;
 mod workspace;
 mod workspace_content;
 
@@ -26,7 +31,7 @@ use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 #[cfg(unix)]
 use std::time::Duration;
-use tauri::{Emitter, Manager};
+use tauri::{Emitter, Listener, Manager};
 use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
 #[cfg(unix)]
 use std::os::unix::process::CommandExt;
@@ -36,12 +41,29 @@ use libc;
 use crate::db::{Database, DbError};
 use crate::attachments::AttachmentRecord;
 use crate::checkpoints::{
+  backup_before_restore,
+  create_agent_checkpoint,
   create_checkpoint,
+  create_named_checkpoint,
   delete_checkpoint,
+  delete_checkpoints,
+  diff_checkpoints,
+  ensure_tree_restorable,
+  export_checkpoint_bundle,
+  gc_checkpoint_objects,
+  get_checkpoint,
+  import_checkpoint_bundle,
+  list_checkpoints,
+  named_checkpoint_id,
+  preview_restore,
   restore_checkpoint,
+  restore_checkpoint_tree,
+  restore_checkpoint_with_options,
+  CheckpointInfo,
   CheckpointOutcome,
+  RestorePreviewEntry,
 };
-use crate::spotlight::SpotlightManager;
+use crate::spotlight::{SpotlightManager, SpotlightRecord, SpotlightStatus};
 use crate::workspace_content::{read_notes, read_todos, write_notes, write_todos, ManualTodoItem};
 use crate::git::{
   branch_exists, clone_repo, create_worktree, diff as git_diff, inspect_repo, is_git_repo,
@@ -51,8 +73,9 @@ use crate::git::{
 use crate::paths::{ensure_dirs, resolve_paths, AppPaths};
 use crate::repos::{NewRepo, RepoRecord};
 use crate::settings::SettingEntry;
-use crate::sidecar::SidecarManager;
+use crate::sidecar::{SidecarManager, SidecarStats};
 use crate::sessions::{SessionMessageRecord, SessionRecord};
+use crate::tool_calls::SessionToolCallRecord;
 
 #[tauri::command]
 fn hello(name: String) -> String {
@@ -75,12 +98,60 @@ struct FilePreview {
   binary: bool,
 }
 
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SessionTurnLink {
+  session_id: String,
+  session_title: Option<String>,
+  turn_id: i64,
+  message_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileBlameLine {
+  #[serde(flatten)]
+  line: git::BlameLine,
+  session_turn: Option<SessionTurnLink>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileHistoryItem {
+  #[serde(flatten)]
+  entry: git::FileHistoryEntry,
+  session_turn: Option<SessionTurnLink>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct WorkspaceDiffResponse {
   diff: String,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BranchListItem {
+  #[serde(flatten)]
+  details: git::BranchDetails,
+  workspace_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BranchPruneFailure {
+  branch: String,
+  error: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PruneMergedBranchesResult {
+  dry_run: bool,
+  branches: Vec<String>,
+  failed: Vec<BranchPruneFailure>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct GithubAuthStatus {
@@ -145,6 +216,29 @@ struct RunOutputEvent {
   line: String,
 }
 
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SessionMessageDispatchedEvent {
+  session_id: String,
+  message: SessionMessageRecord,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SessionErrorEvent {
+  session_id: String,
+  error: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionStatusPayload {
+  session_id: String,
+  status: String,
+  #[serde(default)]
+  reason: Option<String>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 struct RunExitEvent {
@@ -165,6 +259,13 @@ struct TerminalExitEvent {
   terminal_id: String,
 }
 
+/// Serializes sending and queue dispatch so a session never starts two turns
+/// at once.
+#[derive(Default)]
+struct MessageQueue {
+  dispatch_lock: tokio::sync::Mutex<()>,
+}
+
 #[derive(Default, Clone)]
 struct RunManager {
   processes: Arc<Mutex<HashMap<String, RunProcess>>>,
@@ -239,6 +340,35 @@ struct SendSessionMessageRequest {
 struct ResetSessionToTurnRequest {
   session_id: String,
   turn_id: i64,
+  keep_untracked: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RedoSessionToTurnRequest {
+  session_id: String,
+  turn_id: i64,
+  keep_untracked: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForkSessionRequest {
+  session_id: String,
+  turn_id: i64,
+  /// Workspace to fork into; defaults to the source session's workspace.
+  workspace_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CompactCheckpointsResult {
+  dry_run: bool,
+  deleted_checkpoints: Vec<String>,
+  kept_checkpoints: usize,
+  bytes_before: u64,
+  bytes_after: u64,
+  bytes_reclaimed: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -760,6 +890,25 @@ async fn createSession(
   .map_err(|err| err.to_string())
 }
 
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn renameSession(
+  app: tauri::AppHandle,
+  db: tauri::State<'_, Database>,
+  session_id: String,
+  title: String,
+) -> Result<SessionRecord, String> {
+  let title = title.trim();
+  if title.is_empty() {
+    return Err("Title cannot be empty".to_string());
+  }
+  let session = sessions::rename_session(db.pool(), &session_id, title)
+    .await
+    .map_err(|err| err.to_string())?;
+  let _ = app.emit("session-updated", &session);
+  Ok(session)
+}
+
 #[allow(non_snake_case)]
 #[tauri::command]
 async fn updateSessionModel(
@@ -888,54 +1037,64 @@ async fn deleteAttachment(
 #[allow(non_snake_case)]
 #[tauri::command]
 async fn sendSessionMessage(
+  app_handle: tauri::AppHandle,
   db: tauri::State<'_, Database>,
   sidecar: tauri::State<'_, SidecarManager>,
+  queue: tauri::State<'_, MessageQueue>,
   payload: SendSessionMessageRequest,
 ) -> Result<SessionMessageRecord, String> {
   let prompt = payload.prompt.trim();
   if prompt.is_empty() {
     return Err("Prompt is required".to_string());
   }
+  let _dispatch = queue.dispatch_lock.lock().await;
   let session = sessions::get_session(db.pool(), &payload.session_id)
     .await
     .map_err(|err| err.to_string())?;
-  let workspace_record = workspace::get_workspace(db.pool(), &session.workspace_id)
+
+  let message_id = sessions::generate_message_id(db.pool())
     .await
     .map_err(|err| err.to_string())?;
-  let env_vars_raw = settings::get_env_vars(db.pool())
+
+  let queued = sessions::list_queued_session_messages(db.pool(), &session.id)
     .await
     .map_err(|err| err.to_string())?;
-
-  let message_id = sessions::generate_message_id(db.pool())
+  if session.status == "running" || !queued.is_empty() {
+    let metadata_json = payload
+      .permission_mode
+      .as_ref()
+      .map(|mode| json!({ "permissionMode": mode }).to_string());
+    let queued_message = sessions::insert_queued_session_message(
+      db.pool(),
+      sessions::NewQueuedSessionMessage {
+        id: message_id,
+        session_id: session.id.clone(),
+        content: prompt.to_string(),
+        metadata_json,
+      },
+    )
     .await
     .map_err(|err| err.to_string())?;
+    attach_message_attachments(&db, &session.id, &queued_message.id, &payload.attachment_ids)
+      .await?;
+    if session.status == "running" {
+      return Ok(queued_message);
+    }
+    // Messages left queued while idle (e.g. after a cancel) go out first.
+    let dispatched = dispatch_queued_message(&app_handle, &db, &sidecar, &session).await?;
+    return Ok(match dispatched {
+      Some(message) if message.id == queued_message.id => message,
+      _ => queued_message,
+    });
+  }
 
+  let workspace_record = workspace::get_workspace(db.pool(), &session.workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
   let next_turn_id = sessions::next_turn_id(db.pool(), &session.id)
     .await
     .map_err(|err| err.to_string())?;
-  let checkpoint_id = if session.agent_type == "claude" {
-    let checkpoint_id = format!("session-{}-turn-{}-user", session.id, next_turn_id);
-    let workspace_path = PathBuf::from(&workspace_record.path);
-    match create_checkpoint(&workspace_path, &checkpoint_id) {
-      Ok(CheckpointOutcome::Created) => Some(checkpoint_id),
-      Ok(CheckpointOutcome::Skipped { reason }) => {
-        eprintln!(
-          "[checkpoint] skipped for session {} turn {}: {}",
-          session.id, next_turn_id, reason
-        );
-        None
-      }
-      Err(err) => {
-        eprintln!(
-          "[checkpoint] failed for session {} turn {}: {}",
-          session.id, next_turn_id, err
-        );
-        None
-      }
-    }
-  } else {
-    None
-  };
+  let checkpoint_id = create_user_turn_checkpoint(&workspace_record.path, &session, next_turn_id);
 
   let user_message = sessions::insert_session_message(
     db.pool(),
@@ -951,21 +1110,261 @@ async fn sendSessionMessage(
   )
   .await
   .map_err(|err| err.to_string())?;
-  let turn_id = user_message.turn_id;
+  attach_message_attachments(&db, &session.id, &user_message.id, &payload.attachment_ids).await?;
 
-  if let Some(attachment_ids) = payload.attachment_ids.as_ref() {
-    if !attachment_ids.is_empty() {
-      attachments::attach_attachments_to_message(
-        db.pool(),
-        &session.id,
-        &user_message.id,
-        attachment_ids,
-      )
+  start_session_turn(
+    &db,
+    &sidecar,
+    &session,
+    &workspace_record,
+    prompt,
+    payload.permission_mode.clone(),
+    user_message.turn_id,
+  )
+  .await?;
+
+  Ok(user_message)
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn listSessionToolCalls(
+  db: tauri::State<'_, Database>,
+  session_id: String,
+  turn_id: Option<i64>,
+) -> Result<Vec<SessionToolCallRecord>, String> {
+  tool_calls::list_session_tool_calls(db.pool(), &session_id, turn_id)
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// Render a session as `markdown` or `json`, writing it to `path` when given.
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn exportSession(
+  db: tauri::State<'_, Database>,
+  session_id: String,
+  format: String,
+  path: Option<String>,
+) -> Result<String, String> {
+  let export = transcripts::build_session_export(db.pool(), &session_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let contents = match format.as_str() {
+    "markdown" => transcripts::render_markdown(&export),
+    "json" => serde_json::to_string_pretty(&export).map_err(|err| err.to_string())?,
+    other => return Err(format!("Unsupported export format: {other}")),
+  };
+  if let Some(path) = path {
+    fs::write(&path, &contents).map_err(|err| err.to_string())?;
+  }
+  Ok(contents)
+}
+
+/// Recreate a session from a JSON file written by `exportSession`.
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn importSession(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  path: String,
+) -> Result<SessionRecord, String> {
+  workspace::get_workspace(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let contents = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+  let export: transcripts::SessionExport =
+    serde_json::from_str(&contents).map_err(|err| format!("Invalid session export: {err}"))?;
+  transcripts::import_session_export(db.pool(), &workspace_id, export)
+    .await
+    .map_err(|err| err.to_string())
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn searchSessions(
+  db: tauri::State<'_, Database>,
+  query: String,
+  filters: Option<search::SessionSearchFilters>,
+) -> Result<Vec<search::SessionSearchResult>, String> {
+  search::search_sessions(db.pool(), &query, &filters.unwrap_or_default())
+    .await
+    .map_err(|err| err.to_string())
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn getUsageReport(
+  db: tauri::State<'_, Database>,
+  range: String,
+  group_by: String,
+) -> Result<usage::UsageReport, String> {
+  usage::usage_report(db.pool(), &range, &group_by)
+    .await
+    .map_err(|err| err.to_string())
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn reorderQueuedMessages(
+  db: tauri::State<'_, Database>,
+  session_id: String,
+  message_ids: Vec<String>,
+) -> Result<Vec<SessionMessageRecord>, String> {
+  sessions::reorder_queued_session_messages(db.pool(), &session_id, &message_ids)
+    .await
+    .map_err(|err| err.to_string())?;
+  sessions::list_queued_session_messages(db.pool(), &session_id)
+    .await
+    .map_err(|err| err.to_string())
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn cancelQueuedMessage(
+  db: tauri::State<'_, Database>,
+  session_id: String,
+  message_id: String,
+) -> Result<(), String> {
+  sessions::delete_queued_session_message(db.pool(), &session_id, &message_id)
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// Send the next queued message of an idle session. Called when the sidecar
+/// reports a turn finished; cancels and errors leave the queue alone.
+async fn dispatch_next_queued_message(app_handle: &tauri::AppHandle, session_id: &str) {
+  let db = app_handle.state::<Database>();
+  let sidecar = app_handle.state::<SidecarManager>();
+  let queue = app_handle.state::<MessageQueue>();
+  let _dispatch = queue.dispatch_lock.lock().await;
+  let Ok(session) = sessions::get_session(db.pool(), session_id).await else {
+    return;
+  };
+  if session.status == "running" {
+    return;
+  }
+  if let Err(err) = dispatch_queued_message(app_handle, &db, &sidecar, &session).await {
+    eprintln!("[queue] failed to send queued message for session {session_id}: {err}");
+    let _ = app_handle.emit(
+      "session-error",
+      SessionErrorEvent {
+        session_id: session_id.to_string(),
+        error: err,
+      },
+    );
+  }
+}
+
+/// Start a turn for the first queued message of `session`, if any. Callers hold
+/// `MessageQueue::dispatch_lock`.
+async fn dispatch_queued_message(
+  app_handle: &tauri::AppHandle,
+  db: &Database,
+  sidecar: &SidecarManager,
+  session: &SessionRecord,
+) -> Result<Option<SessionMessageRecord>, String> {
+  let queued = sessions::list_queued_session_messages(db.pool(), &session.id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let Some(next) = queued.into_iter().next() else {
+    return Ok(None);
+  };
+  let workspace_record = workspace::get_workspace(db.pool(), &session.workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let turn_id = sessions::next_turn_id(db.pool(), &session.id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let checkpoint_id = create_user_turn_checkpoint(&workspace_record.path, session, turn_id);
+  let message =
+    sessions::mark_session_message_sent(db.pool(), &next.id, turn_id, checkpoint_id.as_deref())
       .await
       .map_err(|err| err.to_string())?;
+  let permission_mode = message
+    .metadata_json
+    .as_deref()
+    .and_then(|raw| serde_json::from_str::<Value>(raw).ok())
+    .and_then(|metadata| {
+      metadata
+        .get("permissionMode")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+    });
+
+  start_session_turn(
+    db,
+    sidecar,
+    session,
+    &workspace_record,
+    &message.content,
+    permission_mode,
+    turn_id,
+  )
+  .await?;
+  let _ = app_handle.emit(
+    "session-message-dispatched",
+    SessionMessageDispatchedEvent {
+      session_id: session.id.clone(),
+      message: message.clone(),
+    },
+  );
+  Ok(Some(message))
+}
+
+fn create_user_turn_checkpoint(
+  workspace_path: &str,
+  session: &SessionRecord,
+  turn_id: i64,
+) -> Option<String> {
+  let checkpoint_id = format!("session-{}-turn-{}-user", session.id, turn_id);
+  let workspace_path = PathBuf::from(workspace_path);
+  match create_agent_checkpoint(&workspace_path, &checkpoint_id, &session.agent_type) {
+    Ok(CheckpointOutcome::Created) => Some(checkpoint_id),
+    Ok(CheckpointOutcome::Skipped { reason }) => {
+      eprintln!(
+        "[checkpoint] skipped for session {} turn {}: {}",
+        session.id, turn_id, reason
+      );
+      None
+    }
+    Err(err) => {
+      eprintln!(
+        "[checkpoint] failed for session {} turn {}: {}",
+        session.id, turn_id, err
+      );
+      None
     }
   }
+}
 
+async fn attach_message_attachments(
+  db: &Database,
+  session_id: &str,
+  message_id: &str,
+  attachment_ids: &Option<Vec<String>>,
+) -> Result<(), String> {
+  let Some(attachment_ids) = attachment_ids.as_ref().filter(|ids| !ids.is_empty()) else {
+    return Ok(());
+  };
+  attachments::attach_attachments_to_message(db.pool(), session_id, message_id, attachment_ids)
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// Query the agent for a user message that is already stored as `turn_id`.
+async fn start_session_turn(
+  db: &Database,
+  sidecar: &SidecarManager,
+  session: &SessionRecord,
+  workspace_record: &workspace::WorkspaceRecord,
+  prompt: &str,
+  permission_mode: Option<String>,
+  turn_id: i64,
+) -> Result<(), String> {
+  let env_vars_raw = settings::get_env_vars(db.pool())
+    .await
+    .map_err(|err| err.to_string())?;
   let mut additional_directories: Vec<String> = Vec::new();
   if let Some(linked_ids) = workspace_record.linked_workspace_ids.clone() {
     let mut seen = HashSet::new();
@@ -990,15 +1389,15 @@ async fn sendSessionMessage(
   }
 
   let options = build_session_query_options(
-    &session,
+    session,
     &workspace_record.path,
-    payload.permission_mode.clone(),
+    permission_mode,
     &env_vars_raw,
     &additional_directories,
     turn_id,
   );
   if let Err(err) = sidecar
-    .send_query(&session.id, &session.agent_type, prompt, options, turn_id)     
+    .send_query(&session.id, &session.agent_type, prompt, options, turn_id)
     .await
   {
     let _ = sessions::set_session_status(db.pool(), &session.id, "error").await;
@@ -1006,9 +1405,7 @@ async fn sendSessionMessage(
   }
   sessions::set_session_status(db.pool(), &session.id, "running")
     .await
-    .map_err(|err| err.to_string())?;
-
-  Ok(user_message)
+    .map_err(|err| err.to_string())
 }
 
 fn build_session_query_options(
@@ -1042,6 +1439,15 @@ fn build_session_query_options(
         .filter(|value| !value.trim().is_empty())
       {
         options.insert("resume".to_string(), Value::String(resume.clone()));
+        // A fork resumes its source's transcript up to the fork point under a new id.
+        if let Some(resume_at) = session
+          .resume_session_at
+          .as_ref()
+          .filter(|value| !value.trim().is_empty())
+        {
+          options.insert("resumeSessionAt".to_string(), Value::String(resume_at.clone()));
+          options.insert("forkSession".to_string(), Value::Bool(true));
+        }
       }
     }
     "codex" => {
@@ -1085,14 +1491,44 @@ async fn cancelSession(
   db: tauri::State<'_, Database>,
   sidecar: tauri::State<'_, SidecarManager>,
   session_id: String,
-) -> Result<(), String> {
+) -> Result<Vec<SessionMessageRecord>, String> {
   let session = sessions::get_session(db.pool(), &session_id)
     .await
     .map_err(|err| err.to_string())?;
-  sidecar
+  let cancelled = sidecar
     .cancel(&session.id, &session.agent_type)
     .await?;
   sessions::set_session_status(db.pool(), &session.id, "idle")
+    .await
+    .map_err(|err| err.to_string())?;
+  let Some(cancelled) = cancelled else {
+    return Ok(Vec::new());
+  };
+  tool_calls::finish_running_tool_calls(db.pool(), &session.id, cancelled.turn_id, "cancelled")
+    .await
+    .map_err(|err| err.to_string())?;
+
+  let turn_messages = sessions::list_turn_messages(db.pool(), &session.id, cancelled.turn_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  for message in turn_messages
+    .iter()
+    .filter(|message| message.role == "user" && message.cancelled_at.is_none())
+  {
+    sessions::set_session_message_cancelled(db.pool(), &message.id)
+      .await
+      .map_err(|err| err.to_string())?;
+  }
+  if let Some(message_id) = cancelled.assistant_message_id.as_deref() {
+    sessions::save_cancelled_assistant_message(
+      db.pool(),
+      message_id,
+      &cancelled.assistant_content,
+    )
+    .await
+    .map_err(|err| err.to_string())?;
+  }
+  sessions::list_turn_messages(db.pool(), &session.id, cancelled.turn_id)
     .await
     .map_err(|err| err.to_string())
 }
@@ -1202,7 +1638,12 @@ async fn resetSessionToTurn(
     }
   };
   // Restore the target checkpoint. If this fails, we must abort the reset.
-  restore_checkpoint(&workspace_path, &checkpoint_id).map_err(|err| err.to_string())?;
+  restore_checkpoint_with_options(
+    &workspace_path,
+    &checkpoint_id,
+    payload.keep_untracked.unwrap_or(false),
+  )
+  .map_err(|err| err.to_string())?;
   // Attempt DB reset. If it fails, try to restore the rollback checkpoint to avoid
   // leaving the workspace and DB out of sync. Rollback failures are logged and may
   // leave the workspace reverted while DB state remains unchanged.
@@ -1240,6 +1681,523 @@ async fn resetSessionToTurn(
   Ok(())
 }
 
+/// Move the workspace to the state recorded after the agent finished `turn_id`.
+/// Unlike `resetSessionToTurn`, no messages are deleted, so this can step both
+/// forward and backward through the session's history, including turns a reset
+/// removed. The current state is saved as a named checkpoint first; its id is returned.
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn redoSessionToTurn(
+  db: tauri::State<'_, Database>,
+  payload: RedoSessionToTurnRequest,
+) -> Result<Option<String>, String> {
+  let session = sessions::get_session(db.pool(), &payload.session_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let workspace_sessions = sessions::list_workspace_sessions(db.pool(), &session.workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  if workspace_sessions
+    .iter()
+    .any(|item| matches!(item.status.as_str(), "running"))
+  {
+    return Err("A session is running in this workspace".to_string());
+  }
+  let Some(checkpoint_id) =
+    sessions::get_turn_assistant_checkpoint(db.pool(), &session.id, payload.turn_id)
+      .await
+      .map_err(|err| err.to_string())?
+  else {
+    return Err("Checkpoint is unavailable for this turn".to_string());
+  };
+  let workspace_record = workspace::get_workspace(db.pool(), &session.workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let workspace_path = PathBuf::from(&workspace_record.path);
+  let backup_label = format!("Before moving to turn {}", payload.turn_id);
+  let keep_untracked = payload.keep_untracked.unwrap_or(false);
+  tauri::async_runtime::spawn_blocking(move || {
+    let backup = backup_before_restore(&workspace_path, &workspace_record.id, &backup_label);
+    restore_checkpoint_with_options(&workspace_path, &checkpoint_id, keep_untracked)
+      .map_err(|err| err.to_string())?;
+    Ok(backup)
+  })
+  .await
+  .map_err(|err| err.to_string())?
+}
+
+/// Branch a session after `turn_id` into a new session, in the same workspace or
+/// another one of the repository, and restore the turn's checkpoint there. Claude
+/// forks resume the agent transcript at that turn; other agents start fresh.
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn forkSession(
+  db: tauri::State<'_, Database>,
+  queue: tauri::State<'_, MessageQueue>,
+  payload: ForkSessionRequest,
+) -> Result<SessionRecord, String> {
+  let session = sessions::get_session(db.pool(), &payload.session_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let next_turn_id = sessions::next_turn_id(db.pool(), &session.id)
+    .await
+    .map_err(|err| err.to_string())?;
+  if payload.turn_id <= 0 || payload.turn_id >= next_turn_id {
+    return Err("Invalid turn_id".to_string());
+  }
+  let source_workspace = workspace::get_workspace(db.pool(), &session.workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let target_workspace_id = payload
+    .workspace_id
+    .unwrap_or_else(|| session.workspace_id.clone());
+  let target_workspace = workspace::get_workspace(db.pool(), &target_workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  // Checkpoint refs are shared by the worktrees of one repository only.
+  if target_workspace.repo_id != source_workspace.repo_id {
+    return Err("Sessions can only be forked within the same repository".to_string());
+  }
+  // Turns only start under the dispatch lock, so holding it until the tree is laid
+  // down keeps a session from starting in the target mid-restore.
+  let _dispatch = queue.dispatch_lock.lock().await;
+  let target_sessions = sessions::list_workspace_sessions(db.pool(), &target_workspace.id)
+    .await
+    .map_err(|err| err.to_string())?;
+  if target_sessions
+    .iter()
+    .any(|item| matches!(item.status.as_str(), "running"))
+  {
+    return Err("A session is running in the target workspace".to_string());
+  }
+  let Some(checkpoint_id) =
+    sessions::get_turn_assistant_checkpoint(db.pool(), &session.id, payload.turn_id)
+      .await
+      .map_err(|err| err.to_string())?
+  else {
+    return Err("Checkpoint is unavailable for this turn".to_string());
+  };
+  let workspace_path = PathBuf::from(&target_workspace.path);
+  {
+    let workspace_path = workspace_path.clone();
+    let checkpoint_id = checkpoint_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+      ensure_tree_restorable(&workspace_path, &checkpoint_id)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| err.to_string())?;
+  }
+
+  let resume_session_at = match (session.agent_type.as_str(), &session.claude_session_id) {
+    ("claude", Some(_)) => {
+      sessions::get_turn_agent_message_id(db.pool(), &session.id, payload.turn_id)
+        .await
+        .map_err(|err| err.to_string())?
+    }
+    _ => None,
+  };
+  let claude_session_id = resume_session_at
+    .as_ref()
+    .and(session.claude_session_id.clone());
+  let title = format!("{} (fork)", session.title.as_deref().unwrap_or("Chat"));
+  let fork = sessions::fork_session(
+    db.pool(),
+    sessions::NewSessionFork {
+      source_session_id: session.id.clone(),
+      workspace_id: target_workspace.id.clone(),
+      turn_id: payload.turn_id,
+      title: Some(title),
+      claude_session_id,
+      resume_session_at,
+    },
+  )
+  .await
+  .map_err(|err| err.to_string())?;
+
+  let backup_label = format!("Before forking {}", session.title.as_deref().unwrap_or("a chat"));
+  let target_workspace_id = target_workspace.id.clone();
+  let restored = tauri::async_runtime::spawn_blocking(move || {
+    backup_before_restore(&workspace_path, &target_workspace_id, &backup_label);
+    restore_checkpoint_tree(&workspace_path, &checkpoint_id)
+  })
+  .await
+  .map_err(|err| err.to_string())
+  .and_then(|result| result.map_err(|err| err.to_string()));
+  if let Err(err) = restored {
+    if let Err(cleanup_err) = sessions::delete_session(db.pool(), &fork.id).await {
+      eprintln!("[fork] failed to remove session {} after restore error: {cleanup_err}", fork.id);
+    }
+    return Err(format!("Failed to restore checkpoint: {err}"));
+  }
+  Ok(fork)
+}
+
+/// Checkpoint refs are shared by every worktree of a repository, so workspace
+/// ownership is derived from the id: named checkpoints embed the workspace id and
+/// turn checkpoints embed the session id. Transient rollback refs are hidden.
+fn checkpoint_belongs_to_workspace(
+  checkpoint_id: &str,
+  workspace_id: &str,
+  session_ids: &HashSet<String>,
+) -> bool {
+  if checkpoint_id.contains("-rollback-") {
+    return false;
+  }
+  if let Some(rest) = checkpoint_id.strip_prefix("named-") {
+    return rest
+      .strip_prefix(workspace_id)
+      .is_some_and(|rest| rest.starts_with('-'));
+  }
+  if let Some(rest) = checkpoint_id.strip_prefix("session-") {
+    return session_ids.iter().any(|session_id| {
+      rest
+        .strip_prefix(session_id.as_str())
+        .is_some_and(|rest| rest.starts_with("-turn-"))
+    });
+  }
+  false
+}
+
+async fn workspace_checkpoint_context(
+  db: &Database,
+  workspace_id: &str,
+) -> Result<(PathBuf, HashSet<String>), String> {
+  let workspace_record = workspace::get_workspace(db.pool(), workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let session_ids = sessions::list_workspace_sessions(db.pool(), workspace_id)
+    .await
+    .map_err(|err| err.to_string())?
+    .into_iter()
+    .map(|session| session.id)
+    .collect();
+  Ok((PathBuf::from(&workspace_record.path), session_ids))
+}
+
+fn ensure_workspace_checkpoint(
+  checkpoint_id: &str,
+  workspace_id: &str,
+  session_ids: &HashSet<String>,
+) -> Result<(), String> {
+  if checkpoint_belongs_to_workspace(checkpoint_id, workspace_id, session_ids) {
+    Ok(())
+  } else {
+    Err(format!("Checkpoint {checkpoint_id} does not belong to this workspace"))
+  }
+}
+
+/// Unreferenced turn checkpoints younger than this are left alone: they may belong
+/// to a turn that is being sent or a reset that is still in progress.
+const CHECKPOINT_PRUNE_GRACE_SECS: i64 = 60 * 60;
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn listCheckpoints(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+) -> Result<Vec<CheckpointInfo>, String> {
+  let (workspace_path, session_ids) = workspace_checkpoint_context(&db, &workspace_id).await?;
+  let checkpoints = tauri::async_runtime::spawn_blocking(move || list_checkpoints(&workspace_path))
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| err.to_string())?;
+  Ok(
+    checkpoints
+      .into_iter()
+      .filter(|checkpoint| {
+        checkpoint_belongs_to_workspace(&checkpoint.id, &workspace_id, &session_ids)
+      })
+      .collect(),
+  )
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn createNamedCheckpoint(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  label: String,
+) -> Result<CheckpointInfo, String> {
+  let workspace_record = workspace::get_workspace(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let workspace_path = PathBuf::from(&workspace_record.path);
+  let checkpoint_id = named_checkpoint_id(&workspace_id);
+  tauri::async_runtime::spawn_blocking(move || {
+    match create_named_checkpoint(&workspace_path, &checkpoint_id, &label)
+      .map_err(|err| err.to_string())?
+    {
+      CheckpointOutcome::Created => {
+        get_checkpoint(&workspace_path, &checkpoint_id).map_err(|err| err.to_string())
+      }
+      CheckpointOutcome::Skipped { reason } => Err(reason),
+    }
+  })
+  .await
+  .map_err(|err| err.to_string())?
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn diffCheckpoint(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  from_id: String,
+  to_id: String,
+  stat: Option<bool>,
+) -> Result<String, String> {
+  let (workspace_path, session_ids) = workspace_checkpoint_context(&db, &workspace_id).await?;
+  ensure_workspace_checkpoint(&from_id, &workspace_id, &session_ids)?;
+  ensure_workspace_checkpoint(&to_id, &workspace_id, &session_ids)?;
+  let stat = stat.unwrap_or(false);
+  tauri::async_runtime::spawn_blocking(move || {
+    diff_checkpoints(&workspace_path, &from_id, &to_id, stat)
+  })
+  .await
+  .map_err(|err| err.to_string())?
+  .map_err(|err| err.to_string())
+}
+
+/// List the files a restore would modify, delete or create, including untracked
+/// files that the restore's `git clean` would remove.
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn previewCheckpointRestore(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  checkpoint_id: String,
+  keep_untracked: Option<bool>,
+) -> Result<Vec<RestorePreviewEntry>, String> {
+  let (workspace_path, session_ids) = workspace_checkpoint_context(&db, &workspace_id).await?;
+  ensure_workspace_checkpoint(&checkpoint_id, &workspace_id, &session_ids)?;
+  let keep_untracked = keep_untracked.unwrap_or(false);
+  tauri::async_runtime::spawn_blocking(move || {
+    preview_restore(&workspace_path, &checkpoint_id, keep_untracked)
+  })
+  .await
+  .map_err(|err| err.to_string())?
+  .map_err(|err| err.to_string())
+}
+
+/// Restore a workspace checkpoint. The current state is saved as a named
+/// checkpoint first so the restore can itself be undone; its id is returned.
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn restoreCheckpoint(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  checkpoint_id: String,
+) -> Result<Option<String>, String> {
+  let (workspace_path, session_ids) = workspace_checkpoint_context(&db, &workspace_id).await?;
+  ensure_workspace_checkpoint(&checkpoint_id, &workspace_id, &session_ids)?;
+  let workspace_sessions = sessions::list_workspace_sessions(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  if workspace_sessions
+    .iter()
+    .any(|item| matches!(item.status.as_str(), "running"))
+  {
+    return Err("A session is running in this workspace".to_string());
+  }
+  tauri::async_runtime::spawn_blocking(move || {
+    let target = get_checkpoint(&workspace_path, &checkpoint_id).map_err(|err| err.to_string())?;
+    let backup_label = format!(
+      "Before restoring {}",
+      target.label.as_deref().unwrap_or(target.id.as_str())
+    );
+    let backup = backup_before_restore(&workspace_path, &workspace_id, &backup_label);
+    restore_checkpoint(&workspace_path, &checkpoint_id).map_err(|err| err.to_string())?;
+    Ok(backup)
+  })
+  .await
+  .map_err(|err| err.to_string())?
+}
+
+/// Export a checkpoint as a `git bundle` at `dest`, with its metadata written
+/// next to it as `<dest>.json`.
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn exportCheckpoint(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  checkpoint_id: String,
+  dest: String,
+) -> Result<CheckpointInfo, String> {
+  let (workspace_path, session_ids) = workspace_checkpoint_context(&db, &workspace_id).await?;
+  ensure_workspace_checkpoint(&checkpoint_id, &workspace_id, &session_ids)?;
+  let dest = PathBuf::from(dest.trim());
+  if dest.as_os_str().is_empty() {
+    return Err("Export destination is required".to_string());
+  }
+  tauri::async_runtime::spawn_blocking(move || {
+    let info = export_checkpoint_bundle(&workspace_path, &checkpoint_id, &dest)
+      .map_err(|err| err.to_string())?;
+    let mut metadata_path = dest.into_os_string();
+    metadata_path.push(".json");
+    let metadata = serde_json::to_string_pretty(&info).map_err(|err| err.to_string())?;
+    fs::write(PathBuf::from(metadata_path), metadata).map_err(|err| err.to_string())?;
+    Ok(info)
+  })
+  .await
+  .map_err(|err| err.to_string())?
+}
+
+/// Import a checkpoint bundle into a workspace as a named checkpoint. Use
+/// `restoreCheckpoint` afterwards to apply it to the working tree.
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn importCheckpoint(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  bundle_path: String,
+) -> Result<CheckpointInfo, String> {
+  let workspace_record = workspace::get_workspace(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let workspace_path = PathBuf::from(&workspace_record.path);
+  let bundle_path = PathBuf::from(bundle_path.trim());
+  if !bundle_path.is_file() {
+    return Err("Bundle file not found".to_string());
+  }
+  let checkpoint_id = named_checkpoint_id(&workspace_id);
+  let label = format!(
+    "Imported from {}",
+    bundle_path
+      .file_name()
+      .map(|name| name.to_string_lossy().to_string())
+      .unwrap_or_else(|| "bundle".to_string())
+  );
+  tauri::async_runtime::spawn_blocking(move || {
+    import_checkpoint_bundle(&workspace_path, &bundle_path, &checkpoint_id, &label)
+  })
+  .await
+  .map_err(|err| err.to_string())?
+  .map_err(|err| err.to_string())
+}
+
+/// Apply checkpoint retention for a repository and garbage-collect the objects
+/// that only deleted checkpoints referenced. Turn checkpoints are kept while a
+/// message still references them and their turn is among the newest N turns of
+/// the session; named checkpoints are never pruned. Refused while any session of
+/// the repository is running, since its agent may be writing objects.
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn compactCheckpoints(
+  db: tauri::State<'_, Database>,
+  repo_id: String,
+  dry_run: Option<bool>,
+) -> Result<CompactCheckpointsResult, String> {
+  let repo = repos::get_repo_by_id(db.pool(), &repo_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  if sessions::count_running_repo_sessions(db.pool(), &repo_id)
+    .await
+    .map_err(|err| err.to_string())?
+    > 0
+  {
+    return Err("A session is running in this repository".to_string());
+  }
+  let keep_per_session = settings::get_checkpoint_keep_per_session(db.pool())
+    .await
+    .map_err(|err| err.to_string())?;
+  let references = sessions::list_repo_message_checkpoints(db.pool(), &repo_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let turn_references = sessions::list_repo_turn_checkpoints(db.pool(), &repo_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let mut retained: HashSet<String> = HashSet::new();
+  // Forked sessions share their source's checkpoints, so one ref can back several messages.
+  let mut expired: HashMap<String, Vec<String>> = HashMap::new();
+  // A turn has a user and an assistant checkpoint; the limit counts turns.
+  let mut kept_turns: HashMap<String, HashSet<i64>> = HashMap::new();
+  for reference in references {
+    let turns = kept_turns.entry(reference.session_id).or_default();
+    if keep_per_session == 0
+      || turns.contains(&reference.turn_id)
+      || turns.len() < keep_per_session
+    {
+      turns.insert(reference.turn_id);
+      retained.insert(reference.checkpoint_id);
+    } else {
+      expired
+        .entry(reference.checkpoint_id)
+        .or_default()
+        .push(reference.message_id);
+    }
+  }
+  // Snapshots after each turn back `redoSessionToTurn` even once a reset removed
+  // the turn's messages, so the newest N turns per session stay.
+  let mut turns_per_session: HashMap<String, usize> = HashMap::new();
+  for reference in turn_references {
+    let count = turns_per_session.entry(reference.session_id).or_default();
+    if keep_per_session == 0 || *count < keep_per_session {
+      *count += 1;
+      retained.insert(reference.checkpoint_id);
+    } else {
+      expired.entry(reference.checkpoint_id).or_default();
+    }
+  }
+
+  let dry_run = dry_run.unwrap_or(true);
+  let root = PathBuf::from(&repo.root_path);
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs() as i64)
+    .unwrap_or(0);
+  let (result, cleared_messages) = tauri::async_runtime::spawn_blocking(move || {
+    let checkpoints = list_checkpoints(&root).map_err(|err| err.to_string())?;
+    let mut deleted_checkpoints = Vec::new();
+    let mut kept_checkpoints = 0;
+    for checkpoint in checkpoints {
+      let prunable = checkpoint.id.starts_with("session-")
+        && !retained.contains(&checkpoint.id)
+        && (expired.contains_key(&checkpoint.id)
+          || now - checkpoint.created > CHECKPOINT_PRUNE_GRACE_SECS);
+      if prunable {
+        deleted_checkpoints.push(checkpoint.id);
+      } else {
+        kept_checkpoints += 1;
+      }
+    }
+    let (bytes_before, bytes_after) = if dry_run {
+      (0, 0)
+    } else {
+      delete_checkpoints(&root, &deleted_checkpoints).map_err(|err| err.to_string())?;
+      gc_checkpoint_objects(&root).map_err(|err| err.to_string())?
+    };
+    let cleared_messages: Vec<String> = deleted_checkpoints
+      .iter()
+      .filter_map(|checkpoint_id| expired.get(checkpoint_id))
+      .flatten()
+      .cloned()
+      .collect();
+    Ok::<_, String>((
+      CompactCheckpointsResult {
+        dry_run,
+        deleted_checkpoints,
+        kept_checkpoints,
+        bytes_before,
+        bytes_after,
+        bytes_reclaimed: bytes_before.saturating_sub(bytes_after),
+      },
+      cleared_messages,
+    ))
+  })
+  .await
+  .map_err(|err| err.to_string())??;
+  if !result.dry_run {
+    sessions::clear_session_message_checkpoints(db.pool(), &cleared_messages)
+      .await
+      .map_err(|err| err.to_string())?;
+    sessions::delete_turn_checkpoints(db.pool(), &result.deleted_checkpoints)
+      .await
+      .map_err(|err| err.to_string())?;
+  }
+  Ok(result)
+}
+
 #[allow(non_snake_case)]
 #[tauri::command]
 async fn updatePermissionMode(
@@ -1256,6 +2214,14 @@ async fn updatePermissionMode(
     .await
 }
 
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn getSidecarStats(
+  sidecar: tauri::State<'_, SidecarManager>,
+) -> Result<SidecarStats, String> {
+  Ok(sidecar.stats().await)
+}
+
 #[allow(non_snake_case)]
 #[tauri::command]
 async fn respondAskUserQuestion(
@@ -1365,6 +2331,75 @@ async fn readWorkspaceFile(
     .map_err(|err| err.to_string())?
 }
 
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn getFileBlame(
+  db: tauri::State<'_, Database>,
+  paths: tauri::State<'_, AppPaths>,
+  workspace_id: String,
+  path: String,
+) -> Result<Vec<FileBlameLine>, String> {
+  let workspace_record = workspace::get_workspace(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let root = resolve_workspace_root(&paths, Path::new(&workspace_record.path))?;
+  let turns = sessions::list_workspace_turn_windows(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let lines = tauri::async_runtime::spawn_blocking(move || git::blame_file(&root, Path::new(&path)))
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| err.to_string())?;
+  // Uncommitted lines have no commit time to match; they are attributed to the
+  // workspace's latest turn, which is the most likely to have written them.
+  let latest_turn = turns.last().map(session_turn_link);
+  Ok(
+    lines
+      .into_iter()
+      .map(|line| {
+        let session_turn = if line.committed {
+          match_session_turn(&turns, line.author_time)
+        } else {
+          latest_turn.clone()
+        };
+        FileBlameLine { line, session_turn }
+      })
+      .collect(),
+  )
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn getFileHistory(
+  db: tauri::State<'_, Database>,
+  paths: tauri::State<'_, AppPaths>,
+  workspace_id: String,
+  path: String,
+) -> Result<Vec<FileHistoryItem>, String> {
+  let workspace_record = workspace::get_workspace(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let root = resolve_workspace_root(&paths, Path::new(&workspace_record.path))?;
+  let turns = sessions::list_workspace_turn_windows(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let entries = tauri::async_runtime::spawn_blocking(move || {
+    git::file_history(&root, Path::new(&path), MAX_FILE_HISTORY_ENTRIES)
+  })
+  .await
+  .map_err(|err| err.to_string())?
+  .map_err(|err| err.to_string())?;
+  Ok(
+    entries
+      .into_iter()
+      .map(|entry| {
+        let session_turn = match_session_turn(&turns, entry.author_time);
+        FileHistoryItem { entry, session_turn }
+      })
+      .collect(),
+  )
+}
+
 #[allow(non_snake_case)]
 #[tauri::command]
 async fn getWorkspaceNotes(
@@ -1448,6 +2483,104 @@ async fn getBranchSyncStatus(
     .map_err(|err| err.to_string())
 }
 
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn listStashes(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  all_branches: Option<bool>,
+) -> Result<Vec<git::StashEntry>, String> {
+  let workspace_record = workspace::get_workspace(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let workspace_path = PathBuf::from(&workspace_record.path);
+  let entries = tauri::async_runtime::spawn_blocking(move || git::list_stashes(&workspace_path))
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| err.to_string())?;
+  if all_branches.unwrap_or(false) {
+    return Ok(entries);
+  }
+  Ok(
+    entries
+      .into_iter()
+      .filter(|entry| entry.branch.as_deref() == Some(workspace_record.branch.as_str()))
+      .collect(),
+  )
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn stashPush(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  message: Option<String>,
+  include_untracked: Option<bool>,
+) -> Result<Option<git::StashEntry>, String> {
+  let workspace_record = workspace::get_workspace(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let workspace_path = PathBuf::from(&workspace_record.path);
+  let include_untracked = include_untracked.unwrap_or(false);
+  tauri::async_runtime::spawn_blocking(move || {
+    git::stash_push(&workspace_path, message.as_deref(), include_untracked)
+  })
+  .await
+  .map_err(|err| err.to_string())?
+  .map_err(|err| err.to_string())
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn stashApply(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  stash_id: String,
+) -> Result<(), String> {
+  let workspace_record = workspace::get_workspace(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let workspace_path = PathBuf::from(&workspace_record.path);
+  tauri::async_runtime::spawn_blocking(move || git::stash_apply(&workspace_path, &stash_id, false))
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| err.to_string())
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn stashPop(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  stash_id: String,
+) -> Result<(), String> {
+  let workspace_record = workspace::get_workspace(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let workspace_path = PathBuf::from(&workspace_record.path);
+  tauri::async_runtime::spawn_blocking(move || git::stash_apply(&workspace_path, &stash_id, true))
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| err.to_string())
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn stashDrop(
+  db: tauri::State<'_, Database>,
+  workspace_id: String,
+  stash_id: String,
+) -> Result<(), String> {
+  let workspace_record = workspace::get_workspace(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let workspace_path = PathBuf::from(&workspace_record.path);
+  tauri::async_runtime::spawn_blocking(move || git::stash_drop(&workspace_path, &stash_id))
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| err.to_string())
+}
+
 #[allow(non_snake_case)]
 #[tauri::command]
 async fn getWorkspaceDiff(
@@ -1482,13 +2615,14 @@ async fn getGithubAuthStatus() -> Result<GithubAuthStatus, String> {
 async fn getSpotlightStatus(
   spotlight: tauri::State<'_, SpotlightManager>,
   workspace_id: String,
-) -> Result<bool, String> {
-  Ok(spotlight.is_active(&workspace_id))
+) -> Result<SpotlightStatus, String> {
+  Ok(spotlight.status(&workspace_id))
 }
 
 #[allow(non_snake_case)]
 #[tauri::command]
 async fn enableSpotlight(
+  app: tauri::AppHandle,
   db: tauri::State<'_, Database>,
   spotlight: tauri::State<'_, SpotlightManager>,
   workspace_id: String,
@@ -1501,16 +2635,189 @@ async fn enableSpotlight(
     .map_err(|err| err.to_string())?;
   let workspace_path = PathBuf::from(&workspace_record.path);
   let repo_root = PathBuf::from(&repo.root_path);
-  spotlight.enable(&workspace_id, workspace_path, repo_root)
+  let debounce_ms = settings::get_spotlight_debounce_ms(db.pool())
+    .await
+    .map_err(|err| err.to_string())?;
+  let record = spotlight.enable(
+    app,
+    &workspace_id,
+    workspace_path,
+    repo_root,
+    Duration::from_millis(debounce_ms),
+  )?;
+  spotlight::insert_spotlight_instance(db.pool(), &record)
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// Resume spotlight after a `spotlight-conflict`. `keep` is `"workspace"` to
+/// overwrite the repo-root edits or `"root"` to copy them into the workspace.
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn resolveSpotlightConflict(
+  spotlight: tauri::State<'_, SpotlightManager>,
+  workspace_id: String,
+  keep: String,
+) -> Result<(), String> {
+  let keep_root = match keep.as_str() {
+    "root" => true,
+    "workspace" => false,
+    _ => return Err(format!("Unknown spotlight side: {keep}")),
+  };
+  spotlight.resolve_conflict(&workspace_id, keep_root)
 }
 
 #[allow(non_snake_case)]
 #[tauri::command]
 async fn disableSpotlight(
+  db: tauri::State<'_, Database>,
   spotlight: tauri::State<'_, SpotlightManager>,
   workspace_id: String,
 ) -> Result<(), String> {
-  spotlight.disable(&workspace_id)
+  spotlight.disable(&workspace_id)?;
+  spotlight::delete_spotlight_instance(db.pool(), &workspace_id)
+    .await
+    .map_err(|err| err.to_string())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpotlightRecoveryItem {
+  workspace_id: String,
+  workspace_path: Option<String>,
+  repo_root: String,
+  rollback_checkpoint_id: String,
+  sync_checkpoint_id: String,
+  created: Option<i64>,
+  resumable: bool,
+}
+
+/// Spotlight instances left behind by a crash: persisted rows that are not
+/// running, plus `spotlight-rollback-*` refs with no row at all.
+async fn find_spotlight_recovery(
+  db: &Database,
+  spotlight: &SpotlightManager,
+) -> Result<Vec<SpotlightRecoveryItem>, String> {
+  let records = spotlight::list_spotlight_instances(db.pool())
+    .await
+    .map_err(|err| err.to_string())?;
+  let repo_roots: Vec<String> = repos::list_repos(db.pool())
+    .await
+    .map_err(|err| err.to_string())?
+    .into_iter()
+    .map(|repo| repo.root_path)
+    .collect();
+
+  let mut items: Vec<SpotlightRecoveryItem> = Vec::new();
+  for record in records {
+    if spotlight.status(&record.workspace_id).active {
+      continue;
+    }
+    let repo_root = PathBuf::from(&record.repo_root);
+    let created = get_checkpoint(&repo_root, &record.rollback_checkpoint_id)
+      .ok()
+      .map(|info| info.created);
+    let resumable = created.is_some() && Path::new(&record.workspace_path).is_dir();
+    items.push(SpotlightRecoveryItem {
+      workspace_id: record.workspace_id,
+      workspace_path: Some(record.workspace_path),
+      repo_root: record.repo_root,
+      rollback_checkpoint_id: record.rollback_checkpoint_id,
+      sync_checkpoint_id: record.sync_checkpoint_id,
+      created,
+      resumable,
+    });
+  }
+
+  for root in repo_roots {
+    let checkpoints = match list_checkpoints(Path::new(&root)) {
+      Ok(checkpoints) => checkpoints,
+      Err(_) => continue,
+    };
+    for checkpoint in checkpoints {
+      let Some(workspace_id) = spotlight::rollback_checkpoint_workspace(&checkpoint.id) else {
+        continue;
+      };
+      if spotlight.status(workspace_id).active
+        || items
+          .iter()
+          .any(|item| item.rollback_checkpoint_id == checkpoint.id)
+      {
+        continue;
+      }
+      items.push(SpotlightRecoveryItem {
+        workspace_id: workspace_id.to_string(),
+        workspace_path: None,
+        repo_root: root.clone(),
+        rollback_checkpoint_id: checkpoint.id.clone(),
+        sync_checkpoint_id: format!("spotlight-sync-{workspace_id}"),
+        created: Some(checkpoint.created),
+        resumable: false,
+      });
+    }
+  }
+  Ok(items)
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn listSpotlightRecovery(
+  db: tauri::State<'_, Database>,
+  spotlight: tauri::State<'_, SpotlightManager>,
+) -> Result<Vec<SpotlightRecoveryItem>, String> {
+  find_spotlight_recovery(db.inner(), spotlight.inner()).await
+}
+
+/// Resolve a leftover spotlight. `action` is `"resume"` to restart syncing on
+/// top of the current repo root or `"rollback"` to restore the root.
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn recoverSpotlight(
+  app: tauri::AppHandle,
+  db: tauri::State<'_, Database>,
+  spotlight: tauri::State<'_, SpotlightManager>,
+  rollback_checkpoint_id: String,
+  action: String,
+) -> Result<(), String> {
+  let item = find_spotlight_recovery(db.inner(), spotlight.inner())
+    .await?
+    .into_iter()
+    .find(|item| item.rollback_checkpoint_id == rollback_checkpoint_id)
+    .ok_or_else(|| format!("No leftover spotlight for {rollback_checkpoint_id}"))?;
+
+  match action.as_str() {
+    "resume" => {
+      let workspace_path = match (&item.workspace_path, item.resumable) {
+        (Some(workspace_path), true) => workspace_path.clone(),
+        _ => return Err("This spotlight can only be rolled back".to_string()),
+      };
+      let record = SpotlightRecord {
+        workspace_id: item.workspace_id,
+        repo_root: item.repo_root,
+        workspace_path,
+        rollback_checkpoint_id: item.rollback_checkpoint_id,
+        sync_checkpoint_id: item.sync_checkpoint_id,
+      };
+      let debounce_ms = settings::get_spotlight_debounce_ms(db.pool())
+        .await
+        .map_err(|err| err.to_string())?;
+      spotlight.resume(app, &record, Duration::from_millis(debounce_ms))
+    }
+    "rollback" => {
+      let repo_root = PathBuf::from(&item.repo_root);
+      let rollback_id = item.rollback_checkpoint_id.clone();
+      let sync_id = item.sync_checkpoint_id.clone();
+      tauri::async_runtime::spawn_blocking(move || {
+        spotlight::roll_back(&repo_root, &rollback_id, &sync_id)
+      })
+      .await
+      .map_err(|err| err.to_string())??;
+      spotlight::delete_spotlight_instance(db.pool(), &item.workspace_id)
+        .await
+        .map_err(|err| err.to_string())
+    }
+    _ => Err(format!("Unknown spotlight recovery action: {action}")),
+  }
 }
 
 #[allow(non_snake_case)]
@@ -1537,6 +2844,131 @@ async fn listRepoBranches(
   Ok(branches)
 }
 
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn listBranchesDetailed(
+  db: tauri::State<'_, Database>,
+  repo_id: String,
+) -> Result<Vec<BranchListItem>, String> {
+  let repo = repos::get_repo_by_id(db.pool(), &repo_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let owners: HashMap<String, String> =
+    workspace::list_active_workspace_branches(db.pool(), &repo_id)
+      .await
+      .map_err(|err| err.to_string())?
+      .into_iter()
+      .map(|(id, branch)| (branch, id))
+      .collect();
+  let root = PathBuf::from(&repo.root_path);
+  let default_branch = repo.default_branch.clone();
+  let mut details = tauri::async_runtime::spawn_blocking(move || {
+    git::list_branch_details(&root, &default_branch)
+  })
+  .await
+  .map_err(|err| err.to_string())?
+  .map_err(|err| err.to_string())?;
+  details.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(
+    details
+      .into_iter()
+      .map(|details| BranchListItem {
+        workspace_id: owners.get(&details.name).cloned(),
+        details,
+      })
+      .collect(),
+  )
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn deleteBranch(
+  db: tauri::State<'_, Database>,
+  repo_id: String,
+  branch: String,
+  force: Option<bool>,
+) -> Result<(), String> {
+  let repo = repos::get_repo_by_id(db.pool(), &repo_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  let branch = branch.trim().to_string();
+  if branch.is_empty() {
+    return Err("Branch name is required".to_string());
+  }
+  if branch == repo.default_branch {
+    return Err("Refusing to delete the default branch".to_string());
+  }
+  if let Some(existing_id) =
+    workspace::find_active_workspace_for_branch(db.pool(), &repo_id, &branch)
+      .await
+      .map_err(|err| err.to_string())?
+  {
+    return Err(format!(
+      "Branch {branch} is used by workspace {existing_id}"
+    ));
+  }
+  let root = PathBuf::from(&repo.root_path);
+  let force = force.unwrap_or(false);
+  tauri::async_runtime::spawn_blocking(move || git::delete_branch(&root, &branch, force))
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| err.to_string())
+}
+
+#[allow(non_snake_case)]
+#[tauri::command]
+async fn pruneMergedBranches(
+  db: tauri::State<'_, Database>,
+  repo_id: String,
+  dry_run: Option<bool>,
+) -> Result<PruneMergedBranchesResult, String> {
+  let repo = repos::get_repo_by_id(db.pool(), &repo_id)
+    .await
+    .map_err(|err| err.to_string())?;
+  // Archived workspaces keep their branch so they can be unarchived later.
+  let owned: HashSet<String> = workspace::list_workspace_branches(db.pool(), &repo_id)
+    .await
+    .map_err(|err| err.to_string())?
+    .into_iter()
+    .map(|(_, branch)| branch)
+    .collect();
+  let dry_run = dry_run.unwrap_or(true);
+  let root = PathBuf::from(&repo.root_path);
+  let default_branch = repo.default_branch.clone();
+  tauri::async_runtime::spawn_blocking(move || -> Result<PruneMergedBranchesResult, String> {
+    let merged = git::list_merged_branches(&root, &default_branch).map_err(|err| err.to_string())?;
+    let worktrees = git::list_worktree_branches(&root).map_err(|err| err.to_string())?;
+    let candidates = git::prunable_branches(merged, &default_branch, &owned, &worktrees);
+    if dry_run {
+      return Ok(PruneMergedBranchesResult {
+        dry_run,
+        branches: candidates,
+        failed: Vec::new(),
+      });
+    }
+    let mut branches = Vec::new();
+    let mut failed = Vec::new();
+    for branch in candidates {
+      // `-d` still refuses branches git does not see as merged into the root
+      // checkout's HEAD or upstream; those are reported instead of forced.
+      match git::delete_branch(&root, &branch, false) {
+        Ok(()) => branches.push(branch),
+        Err(err) => failed.push(BranchPruneFailure {
+          branch,
+          error: err.to_string(),
+        }),
+      }
+    }
+    Ok(PruneMergedBranchesResult {
+      dry_run,
+      branches,
+      failed,
+    })
+  })
+  .await
+  .map_err(|err| err.to_string())?
+}
+
 #[allow(non_snake_case)]
 #[tauri::command]
 async fn setWorkspaceTargetBranch(
@@ -2798,6 +4230,32 @@ fn format_pr_comments(
 
 const MAX_WORKSPACE_FILES: usize = 2000;
 const MAX_FILE_PREVIEW_BYTES: usize = 200_000;
+const MAX_FILE_HISTORY_ENTRIES: usize = 200;
+// Agents usually commit a little after the turn's last streamed update lands.
+const TURN_MATCH_SLACK_SECS: i64 = 120;
+
+/// Link a commit timestamp to the latest chat turn that was active at that time.
+fn match_session_turn(
+  turns: &[sessions::SessionTurnWindow],
+  timestamp: i64,
+) -> Option<SessionTurnLink> {
+  turns
+    .iter()
+    .rev()
+    .find(|turn| {
+      turn.started_at <= timestamp && timestamp <= turn.finished_at + TURN_MATCH_SLACK_SECS
+    })
+    .map(session_turn_link)
+}
+
+fn session_turn_link(turn: &sessions::SessionTurnWindow) -> SessionTurnLink {
+  SessionTurnLink {
+    session_id: turn.session_id.clone(),
+    session_title: turn.session_title.clone(),
+    turn_id: turn.turn_id,
+    message_id: turn.message_id.clone(),
+  }
+}
 
 fn resolve_workspace_root(paths: &AppPaths, workspace_path: &Path) -> Result<PathBuf, String> {
   let workspace_root = paths
@@ -3153,6 +4611,20 @@ fn main() {
       app.manage(TerminalManager::default());
       app.manage(sidecar_manager);
       app.manage(SpotlightManager::default());
+      app.manage(MessageQueue::default());
+      let app_handle = app.handle().clone();
+      app.listen_any("session-status", move |event| {
+        let Ok(payload) = serde_json::from_str::<SessionStatusPayload>(event.payload()) else {
+          return;
+        };
+        if payload.status != "idle" || payload.reason.as_deref() != Some("finished") {
+          return;
+        }
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+          dispatch_next_queued_message(&app_handle, &payload.session_id).await;
+        });
+      });
       Ok(())
     })
     .plugin(tauri_plugin_dialog::init())
@@ -3178,26 +4650,47 @@ fn main() {
       listSessions,
       createWorkspace,
       createSession,
+      renameSession,
       updateSessionModel,
       archiveWorkspace,
       deleteSession,
       unarchiveWorkspace,
       listSessionMessages,
+      listSessionToolCalls,
+      getUsageReport,
+      searchSessions,
+      exportSession,
+      importSession,
       listSessionAttachments,
       createAttachment,
       deleteAttachment,
       pinWorkspace,
       markWorkspaceUnread,
       sendSessionMessage,
+      reorderQueuedMessages,
+      cancelQueuedMessage,
       cancelSession,
       resetSessionToTurn,
+      redoSessionToTurn,
+      forkSession,
+      listCheckpoints,
+      createNamedCheckpoint,
+      diffCheckpoint,
+      previewCheckpointRestore,
+      restoreCheckpoint,
+      exportCheckpoint,
+      importCheckpoint,
+      compactCheckpoints,
       updatePermissionMode,
+      getSidecarStats,
       respondAskUserQuestion,
       respondExitPlanMode,
       setWorkspaceSparseCheckout,
       setWorkspaceLinkedWorkspaces,
       listWorkspaceFiles,
       readWorkspaceFile,
+      getFileBlame,
+      getFileHistory,
       getWorkspaceNotes,
       setWorkspaceNotes,
       getWorkspaceTodos,
@@ -3205,11 +4698,22 @@ fn main() {
       getWorkspaceGitStatus,
       getBranchSyncStatus,
       getWorkspaceDiff,
+      listStashes,
+      stashPush,
+      stashApply,
+      stashPop,
+      stashDrop,
       getGithubAuthStatus,
       getSpotlightStatus,
       enableSpotlight,
       disableSpotlight,
+      listSpotlightRecovery,
+      recoverSpotlight,
+      resolveSpotlightConflict,
       listRepoBranches,
+      listBranchesDetailed,
+      deleteBranch,
+      pruneMergedBranches,
       setWorkspaceTargetBranch,
       createPullRequest,
       mergePullRequest,
@@ -3224,6 +4728,19 @@ fn main() {
       closeTerminal,
       openPathIn
     ])
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(|app_handle, event| {
+      if let tauri::RunEvent::Exit = event {
+        // Roll spotlight repo roots back before the process ends, however the app
+        // is quit, so no workspace content is left synced into them.
+        let rolled_back = app_handle.state::<SpotlightManager>().disable_all();
+        let db = app_handle.state::<Database>().inner().clone();
+        tauri::async_runtime::block_on(async move {
+          for workspace_id in rolled_back {
+            let _ = spotlight::delete_spotlight_instance(db.pool(), &workspace_id).await;
+          }
+        });
+      }
+    });
 }
@@ -0,0 +1,198 @@
+use crate::db::DbError;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+const DEFAULT_SEARCH_LIMIT: i64 = 50;
+const MAX_SEARCH_LIMIT: i64 = 200;
+/// Snippet markers; control characters never show up in indexed text.
+const HIGHLIGHT_START: char = '\u{2}';
+const HIGHLIGHT_END: char = '\u{3}';
+
+/// Optional narrowing for `searchSessions`. `role` only matches messages.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchFilters {
+  pub workspace_id: Option<String>,
+  pub repo_id: Option<String>,
+  pub session_id: Option<String>,
+  pub role: Option<String>,
+  pub include_tool_calls: Option<bool>,
+  pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetSegment {
+  pub text: String,
+  pub highlighted: bool,
+}
+
+/// A message or tool call matching a search, with a highlighted excerpt.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchResult {
+  pub kind: String,
+  pub source_id: String,
+  pub session_id: String,
+  pub session_title: Option<String>,
+  pub workspace_id: String,
+  pub repo_id: String,
+  pub turn_id: i64,
+  pub role: Option<String>,
+  pub snippet: Vec<SnippetSegment>,
+  pub rank: f64,
+}
+
+#[derive(sqlx::FromRow)]
+struct SearchHitRow {
+  kind: String,
+  source_id: String,
+  session_id: String,
+  session_title: Option<String>,
+  workspace_id: String,
+  repo_id: String,
+  turn_id: i64,
+  role: Option<String>,
+  snippet: String,
+  rank: f64,
+}
+
+/// Turn free text into an FTS5 query: every word must match, the last one as a
+/// prefix so results show up while typing. Operators in the input are ignored.
+pub fn build_match_query(input: &str) -> Option<String> {
+  let terms: Vec<String> = input
+    .split_whitespace()
+    .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+    .collect();
+  if terms.is_empty() {
+    return None;
+  }
+  Some(format!("{}*", terms.join(" ")))
+}
+
+/// Split an FTS5 snippet into plain and highlighted runs.
+pub fn parse_snippet(snippet: &str) -> Vec<SnippetSegment> {
+  let mut segments = Vec::new();
+  let mut current = String::new();
+  let mut highlighted = false;
+  for ch in snippet.chars() {
+    if ch == HIGHLIGHT_START || ch == HIGHLIGHT_END {
+      if !current.is_empty() {
+        segments.push(SnippetSegment {
+          text: std::mem::take(&mut current),
+          highlighted,
+        });
+      }
+      highlighted = ch == HIGHLIGHT_START;
+    } else {
+      current.push(ch);
+    }
+  }
+  if !current.is_empty() {
+    segments.push(SnippetSegment {
+      text: current,
+      highlighted,
+    });
+  }
+  segments
+}
+
+pub async fn search_sessions(
+  pool: &SqlitePool,
+  query: &str,
+  filters: &SessionSearchFilters,
+) -> Result<Vec<SessionSearchResult>, DbError> {
+  let Some(match_query) = build_match_query(query) else {
+    return Ok(Vec::new());
+  };
+  let limit = filters
+    .limit
+    .unwrap_or(DEFAULT_SEARCH_LIMIT)
+    .clamp(1, MAX_SEARCH_LIMIT);
+  let include_tool_calls = filters.include_tool_calls.unwrap_or(true);
+  let rows = sqlx::query_as::<_, SearchHitRow>(
+    "SELECT hit.kind, hit.source_id, hit.session_id, s.title AS session_title,
+            s.workspace_id, w.repo_id, hit.turn_id, hit.role, hit.snippet, hit.rank
+     FROM (
+       SELECT 'message' AS kind, m.id AS source_id, m.session_id, m.turn_id, m.role,
+              snippet(session_messages_fts, 0, char(2), char(3), '…', 24) AS snippet,
+              bm25(session_messages_fts) AS rank
+       FROM session_messages_fts
+       JOIN session_messages m ON m.search_rowid = session_messages_fts.rowid
+       WHERE session_messages_fts MATCH ? AND m.sent_at IS NOT NULL
+       UNION ALL
+       SELECT 'tool_call', t.id, t.session_id, t.turn_id, NULL,
+              snippet(session_tool_calls_fts, -1, char(2), char(3), '…', 24),
+              bm25(session_tool_calls_fts)
+       FROM session_tool_calls_fts
+       JOIN session_tool_calls t ON t.search_rowid = session_tool_calls_fts.rowid
+       WHERE ? AND session_tool_calls_fts MATCH ?
+     ) hit
+     JOIN sessions s ON s.id = hit.session_id
+     JOIN workspaces w ON w.id = s.workspace_id
+     WHERE (? IS NULL OR s.workspace_id = ?)
+       AND (? IS NULL OR w.repo_id = ?)
+       AND (? IS NULL OR hit.session_id = ?)
+       AND (? IS NULL OR hit.role = ?)
+     ORDER BY hit.rank ASC
+     LIMIT ?",
+  )
+  .bind(&match_query)
+  .bind(include_tool_calls)
+  .bind(&match_query)
+  .bind(&filters.workspace_id)
+  .bind(&filters.workspace_id)
+  .bind(&filters.repo_id)
+  .bind(&filters.repo_id)
+  .bind(&filters.session_id)
+  .bind(&filters.session_id)
+  .bind(&filters.role)
+  .bind(&filters.role)
+  .bind(limit)
+  .fetch_all(pool)
+  .await?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|row| SessionSearchResult {
+        kind: row.kind,
+        source_id: row.source_id,
+        session_id: row.session_id,
+        session_title: row.session_title,
+        workspace_id: row.workspace_id,
+        repo_id: row.repo_id,
+        turn_id: row.turn_id,
+        role: row.role,
+        snippet: parse_snippet(&row.snippet),
+        rank: row.rank,
+      })
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{build_match_query, parse_snippet, SnippetSegment};
+
+  #[test]
+  fn quotes_search_terms() {
+    assert_eq!(build_match_query("   "), None);
+    assert_eq!(
+      build_match_query("fix \"auth\" OR*"),
+      Some("\"fix\" \"\"\"auth\"\"\" \"OR*\"*".to_string())
+    );
+  }
+
+  #[test]
+  fn splits_highlighted_snippets() {
+    assert_eq!(
+      parse_snippet("…the \u{2}sidecar\u{3} crashed"),
+      vec![
+        SnippetSegment { text: "…the ".to_string(), highlighted: false },
+        SnippetSegment { text: "sidecar".to_string(), highlighted: true },
+        SnippetSegment { text: " crashed".to_string(), highlighted: false },
+      ]
+    );
+  }
+}
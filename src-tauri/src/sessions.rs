@@ -3,7 +3,7 @@ use serde::Serialize;
 use sqlx::SqlitePool;
 
 /// Session record stored in SQLite.
-#[derive(Debug, Serialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionRecord {
   pub id: String,
@@ -17,6 +17,12 @@ pub struct SessionRecord {
   pub codex_session_id: Option<String>,
   pub context_token_count: Option<i64>,
   pub is_compacted: bool,
+  pub sidecar_restart_count: i64,
+  pub forked_from_session_id: Option<String>,
+  pub forked_from_turn_id: Option<i64>,
+  /// Claude message uuid the next query resumes at; cleared once the fork has
+  /// its own agent session.
+  pub resume_session_at: Option<String>,
 }
 
 /// Data required to insert a new session record.
@@ -40,7 +46,7 @@ pub struct NewSessionMessageDraft {
 }
 
 /// Session message record stored in SQLite.
-#[derive(Debug, Serialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionMessageRecord {
   pub id: String,
@@ -52,6 +58,8 @@ pub struct SessionMessageRecord {
   pub cancelled_at: Option<String>,
   pub metadata_json: Option<String>,
   pub checkpoint_id: Option<String>,
+  /// Position in the session's queue while the message waits to be sent.
+  pub queue_position: Option<i64>,
 }
 
 /// Data required to insert a new session message record.
@@ -65,6 +73,51 @@ pub struct NewSessionMessage {
   pub checkpoint_id: Option<String>,
 }
 
+/// Data required to queue a user message while its session is busy.
+pub struct NewQueuedSessionMessage {
+  pub id: String,
+  pub session_id: String,
+  pub content: String,
+  pub metadata_json: Option<String>,
+}
+
+/// Data required to branch a session after one of its turns.
+pub struct NewSessionFork {
+  pub source_session_id: String,
+  pub workspace_id: String,
+  pub turn_id: i64,
+  pub title: Option<String>,
+  pub claude_session_id: Option<String>,
+  pub resume_session_at: Option<String>,
+}
+
+/// Time window covered by one chat turn, in Unix seconds.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SessionTurnWindow {
+  pub session_id: String,
+  pub session_title: Option<String>,
+  pub turn_id: i64,
+  pub message_id: Option<String>,
+  pub started_at: i64,
+  pub finished_at: i64,
+}
+
+/// Checkpoint reference held by a session message, newest turn first per session.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MessageCheckpointRef {
+  pub session_id: String,
+  pub turn_id: i64,
+  pub message_id: String,
+  pub checkpoint_id: String,
+}
+
+/// Checkpoint taken after an agent turn, newest turn first per session.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TurnCheckpointRef {
+  pub session_id: String,
+  pub checkpoint_id: String,
+}
+
 #[derive(Debug, sqlx::FromRow)]
 pub struct SessionMessageCheckpoint {
   pub checkpoint_id: Option<String>,
@@ -74,7 +127,9 @@ pub struct SessionMessageCheckpoint {
 pub async fn list_sessions(pool: &SqlitePool) -> Result<Vec<SessionRecord>, DbError> {
   let rows = sqlx::query_as::<_, SessionRecord>(
     "SELECT id, workspace_id, title, agent_type, model, status, unread_count,
-            claude_session_id, codex_session_id, context_token_count, is_compacted
+            claude_session_id, codex_session_id, context_token_count, is_compacted,
+            sidecar_restart_count, forked_from_session_id, forked_from_turn_id,
+            resume_session_at
      FROM sessions
      ORDER BY created_at DESC",
   )
@@ -106,7 +161,9 @@ pub async fn insert_session(pool: &SqlitePool, new_session: NewSession) -> Resul
 pub async fn get_session(pool: &SqlitePool, session_id: &str) -> Result<SessionRecord, DbError> {
   let row = sqlx::query_as::<_, SessionRecord>(
     "SELECT id, workspace_id, title, agent_type, model, status, unread_count,
-            claude_session_id, codex_session_id, context_token_count, is_compacted
+            claude_session_id, codex_session_id, context_token_count, is_compacted,
+            sidecar_restart_count, forked_from_session_id, forked_from_turn_id,
+            resume_session_at
      FROM sessions
      WHERE id = ?",
   )
@@ -147,6 +204,49 @@ pub async fn set_session_status(
   Ok(())
 }
 
+/// Give a session a title chosen by the user; auto-titles never replace it.
+pub async fn rename_session(
+  pool: &SqlitePool,
+  session_id: &str,
+  title: &str,
+) -> Result<SessionRecord, DbError> {
+  let result = sqlx::query(
+    "UPDATE sessions
+     SET title = ?, title_source = 'user', updated_at = CURRENT_TIMESTAMP
+     WHERE id = ?",
+  )
+  .bind(title)
+  .bind(session_id)
+  .execute(pool)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(DbError::NotFound(format!("Session not found: {session_id}")));
+  }
+  get_session(pool, session_id).await
+}
+
+/// Store a generated title unless the session was renamed or titled already.
+/// Returns the updated session when the title was applied.
+pub async fn set_generated_session_title(
+  pool: &SqlitePool,
+  session_id: &str,
+  title: &str,
+) -> Result<Option<SessionRecord>, DbError> {
+  let result = sqlx::query(
+    "UPDATE sessions
+     SET title = ?, title_source = 'generated', updated_at = CURRENT_TIMESTAMP
+     WHERE id = ? AND title_source = 'default'",
+  )
+  .bind(title)
+  .bind(session_id)
+  .execute(pool)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Ok(None);
+  }
+  get_session(pool, session_id).await.map(Some)
+}
+
 pub async fn set_session_model(
   pool: &SqlitePool,
   session_id: &str,
@@ -187,6 +287,24 @@ pub async fn set_session_codex_id(
   Ok(())
 }
 
+/// Record that the session's sidecar process was restarted; returns the new count.
+pub async fn increment_session_restart_count(
+  pool: &SqlitePool,
+  session_id: &str,
+) -> Result<i64, DbError> {
+  let count = sqlx::query_scalar::<_, i64>(
+    "UPDATE sessions
+     SET sidecar_restart_count = sidecar_restart_count + 1, updated_at = CURRENT_TIMESTAMP
+     WHERE id = ?
+     RETURNING sidecar_restart_count",
+  )
+  .bind(session_id)
+  .fetch_optional(pool)
+  .await?;
+  count.ok_or_else(|| DbError::NotFound(format!("Session not found: {session_id}")))
+}
+
+/// A fork's resume point only applies to the agent session it was taken from.
 pub async fn set_session_claude_id(
   pool: &SqlitePool,
   session_id: &str,
@@ -194,10 +312,52 @@ pub async fn set_session_claude_id(
 ) -> Result<(), DbError> {
   let result = sqlx::query(
     "UPDATE sessions
-     SET claude_session_id = ?, updated_at = CURRENT_TIMESTAMP
+     SET resume_session_at = CASE
+           WHEN claude_session_id IS ? THEN resume_session_at
+           ELSE NULL
+         END,
+         claude_session_id = ?,
+         updated_at = CURRENT_TIMESTAMP
      WHERE id = ?",
   )
   .bind(claude_session_id)
+  .bind(claude_session_id)
+  .bind(session_id)
+  .execute(pool)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(DbError::NotFound(format!("Session not found: {session_id}")));
+  }
+  Ok(())
+}
+
+pub async fn set_session_context_token_count(
+  pool: &SqlitePool,
+  session_id: &str,
+  context_token_count: i64,
+) -> Result<(), DbError> {
+  let result = sqlx::query(
+    "UPDATE sessions
+     SET context_token_count = ?, updated_at = CURRENT_TIMESTAMP
+     WHERE id = ?",
+  )
+  .bind(context_token_count)
+  .bind(session_id)
+  .execute(pool)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(DbError::NotFound(format!("Session not found: {session_id}")));
+  }
+  Ok(())
+}
+
+/// Remember that the agent compacted the session's context at least once.
+pub async fn set_session_compacted(pool: &SqlitePool, session_id: &str) -> Result<(), DbError> {
+  let result = sqlx::query(
+    "UPDATE sessions
+     SET is_compacted = 1, updated_at = CURRENT_TIMESTAMP
+     WHERE id = ?",
+  )
   .bind(session_id)
   .execute(pool)
   .await?;
@@ -212,10 +372,30 @@ pub async fn list_session_messages(
   session_id: &str,
 ) -> Result<Vec<SessionMessageRecord>, DbError> {
   let rows = sqlx::query_as::<_, SessionMessageRecord>(
-    "SELECT id, session_id, turn_id, role, content, sent_at, cancelled_at, metadata_json, checkpoint_id
+    "SELECT id, session_id, turn_id, role, content, sent_at, cancelled_at, metadata_json, checkpoint_id,
+            queue_position
      FROM session_messages
      WHERE session_id = ?
-     ORDER BY created_at ASC",
+     ORDER BY sent_at IS NULL, queue_position ASC, sequence ASC",
+  )
+  .bind(session_id)
+  .fetch_all(pool)
+  .await?;
+  Ok(rows)
+}
+
+/// Messages waiting for the session to become idle (`sent_at IS NULL`), in
+/// dispatch order.
+pub async fn list_queued_session_messages(
+  pool: &SqlitePool,
+  session_id: &str,
+) -> Result<Vec<SessionMessageRecord>, DbError> {
+  let rows = sqlx::query_as::<_, SessionMessageRecord>(
+    "SELECT id, session_id, turn_id, role, content, sent_at, cancelled_at, metadata_json, checkpoint_id,
+            queue_position
+     FROM session_messages
+     WHERE session_id = ? AND sent_at IS NULL
+     ORDER BY queue_position ASC, created_at ASC",
   )
   .bind(session_id)
   .fetch_all(pool)
@@ -223,6 +403,139 @@ pub async fn list_session_messages(
   Ok(rows)
 }
 
+/// Queue a user message at the end of the session's queue. Queued messages
+/// use turn 0 until they are dispatched.
+pub async fn insert_queued_session_message(
+  pool: &SqlitePool,
+  message: NewQueuedSessionMessage,
+) -> Result<SessionMessageRecord, DbError> {
+  sqlx::query(
+    "INSERT INTO session_messages
+      (id, session_id, turn_id, role, content, sent_at, metadata_json, queue_position)
+     VALUES (?, ?, 0, 'user', ?, NULL, ?,
+       (SELECT COALESCE(MAX(queue_position), 0) + 1
+        FROM session_messages
+        WHERE session_id = ? AND sent_at IS NULL))",
+  )
+  .bind(&message.id)
+  .bind(&message.session_id)
+  .bind(&message.content)
+  .bind(&message.metadata_json)
+  .bind(&message.session_id)
+  .execute(pool)
+  .await?;
+
+  get_session_message(pool, &message.id).await
+}
+
+/// Take a queued message out of the queue and make it the given turn. The row
+/// takes the session's next `sequence` so it sorts after the previous turn.
+pub async fn mark_session_message_sent(
+  pool: &SqlitePool,
+  message_id: &str,
+  turn_id: i64,
+  checkpoint_id: Option<&str>,
+) -> Result<SessionMessageRecord, DbError> {
+  let result = sqlx::query(
+    "UPDATE session_messages
+     SET turn_id = ?,
+         checkpoint_id = ?,
+         sent_at = CURRENT_TIMESTAMP,
+         created_at = CURRENT_TIMESTAMP,
+         updated_at = CURRENT_TIMESTAMP,
+         queue_position = NULL,
+         sequence = (
+           SELECT COALESCE(MAX(other.sequence), 0) + 1
+           FROM session_messages AS other
+           WHERE other.session_id = session_messages.session_id
+         )
+     WHERE id = ? AND sent_at IS NULL",
+  )
+  .bind(turn_id)
+  .bind(checkpoint_id)
+  .bind(message_id)
+  .execute(pool)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(DbError::NotFound(format!(
+      "Queued message not found: {message_id}"
+    )));
+  }
+
+  get_session_message(pool, message_id).await
+}
+
+/// Rewrite queue positions to follow `message_ids`. Queued messages missing from
+/// the list keep their relative order after the listed ones.
+pub async fn reorder_queued_session_messages(
+  pool: &SqlitePool,
+  session_id: &str,
+  message_ids: &[String],
+) -> Result<(), DbError> {
+  let queued: Vec<String> = sqlx::query_scalar(
+    "SELECT id FROM session_messages
+     WHERE session_id = ? AND sent_at IS NULL
+     ORDER BY queue_position ASC, created_at ASC",
+  )
+  .bind(session_id)
+  .fetch_all(pool)
+  .await?;
+  if let Some(unknown) = message_ids.iter().find(|id| !queued.contains(id)) {
+    return Err(DbError::NotFound(format!("Queued message not found: {unknown}")));
+  }
+  let ordered = message_ids
+    .iter()
+    .chain(queued.iter().filter(|id| !message_ids.contains(id)));
+
+  let mut tx = pool.begin().await?;
+  for (index, message_id) in ordered.enumerate() {
+    sqlx::query(
+      "UPDATE session_messages
+       SET queue_position = ?, updated_at = CURRENT_TIMESTAMP
+       WHERE id = ? AND sent_at IS NULL",
+    )
+    .bind(index as i64 + 1)
+    .bind(message_id)
+    .execute(&mut *tx)
+    .await?;
+  }
+  tx.commit().await?;
+  Ok(())
+}
+
+/// Drop a message that is still waiting in the queue, with its attachments.
+pub async fn delete_queued_session_message(
+  pool: &SqlitePool,
+  session_id: &str,
+  message_id: &str,
+) -> Result<(), DbError> {
+  let mut tx = pool.begin().await?;
+  sqlx::query(
+    "DELETE FROM attachments
+     WHERE session_message_id IN (
+       SELECT id FROM session_messages WHERE id = ? AND session_id = ? AND sent_at IS NULL
+     )",
+  )
+  .bind(message_id)
+  .bind(session_id)
+  .execute(&mut *tx)
+  .await?;
+  let result = sqlx::query(
+    "DELETE FROM session_messages WHERE id = ? AND session_id = ? AND sent_at IS NULL",
+  )
+  .bind(message_id)
+  .bind(session_id)
+  .execute(&mut *tx)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(DbError::NotFound(format!(
+      "Queued message not found: {message_id}"
+    )));
+  }
+  tx.commit().await?;
+  Ok(())
+}
+
 #[allow(dead_code)]
 pub async fn insert_session_message_with_next_turn(
   pool: &SqlitePool,
@@ -300,12 +613,32 @@ pub async fn update_session_message_content(
   Ok(())
 }
 
-#[allow(dead_code)]
+pub async fn set_session_message_checkpoint(
+  pool: &SqlitePool,
+  message_id: &str,
+  checkpoint_id: &str,
+) -> Result<(), DbError> {
+  let result = sqlx::query(
+    "UPDATE session_messages
+     SET checkpoint_id = ?, updated_at = CURRENT_TIMESTAMP
+     WHERE id = ?",
+  )
+  .bind(checkpoint_id)
+  .bind(message_id)
+  .execute(pool)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(DbError::NotFound(format!(
+      "Session message not found: {message_id}"
+    )));
+  }
+  Ok(())
+}
+
 pub async fn set_session_message_cancelled(
   pool: &SqlitePool,
   message_id: &str,
 ) -> Result<(), DbError> {
-  // TODO: Wire this into cancel flow once we track the active assistant message id.
   let result = sqlx::query(
     "UPDATE session_messages
      SET cancelled_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
@@ -322,6 +655,77 @@ pub async fn set_session_message_cancelled(
   Ok(())
 }
 
+/// Keep the partial reply of a cancelled turn and flag it `cancelled` in its
+/// metadata.
+/// Re-index a message's final text for search. Streaming updates skip the index,
+/// so call this once the message's content is complete.
+pub async fn index_session_message(pool: &SqlitePool, message_id: &str) -> Result<(), DbError> {
+  let mut tx = pool.begin().await?;
+  sqlx::query(
+    "DELETE FROM session_messages_fts
+     WHERE rowid = (SELECT search_rowid FROM session_messages WHERE id = ?)",
+  )
+  .bind(message_id)
+  .execute(&mut *tx)
+  .await?;
+  sqlx::query(
+    "INSERT INTO session_messages_fts (rowid, content)
+     SELECT search_rowid, content FROM session_messages
+     WHERE id = ? AND sent_at IS NOT NULL",
+  )
+  .bind(message_id)
+  .execute(&mut *tx)
+  .await?;
+  tx.commit().await?;
+  Ok(())
+}
+
+pub async fn save_cancelled_assistant_message(
+  pool: &SqlitePool,
+  message_id: &str,
+  content: &str,
+) -> Result<(), DbError> {
+  let result = sqlx::query(
+    "UPDATE session_messages
+     SET content = CASE WHEN ? = '' THEN content ELSE ? END,
+         metadata_json = json_set(COALESCE(metadata_json, '{}'), '$.cancelled', json('true')),
+         cancelled_at = CURRENT_TIMESTAMP,
+         updated_at = CURRENT_TIMESTAMP
+     WHERE id = ?",
+  )
+  .bind(content)
+  .bind(content)
+  .bind(message_id)
+  .execute(pool)
+  .await?;
+  if result.rows_affected() == 0 {
+    return Err(DbError::NotFound(format!(
+      "Session message not found: {message_id}"
+    )));
+  }
+  index_session_message(pool, message_id).await
+}
+
+/// Messages of one turn in transcript order.
+pub async fn list_turn_messages(
+  pool: &SqlitePool,
+  session_id: &str,
+  turn_id: i64,
+) -> Result<Vec<SessionMessageRecord>, DbError> {
+  let rows = sqlx::query_as::<_, SessionMessageRecord>(
+    "SELECT id, session_id, turn_id, role, content, sent_at, cancelled_at, metadata_json, checkpoint_id,
+            queue_position
+     FROM session_messages
+     WHERE session_id = ? AND turn_id = ? AND sent_at IS NOT NULL
+     ORDER BY sequence ASC",
+  )
+  .bind(session_id)
+  .bind(turn_id)
+  .fetch_all(pool)
+  .await?;
+  Ok(rows)
+}
+
 pub async fn generate_message_id(pool: &SqlitePool) -> Result<String, DbError> {
   generate_random_id(pool).await
 }
@@ -331,7 +735,8 @@ async fn get_session_message(
   message_id: &str,
 ) -> Result<SessionMessageRecord, DbError> {
   let row = sqlx::query_as::<_, SessionMessageRecord>(
-    "SELECT id, session_id, turn_id, role, content, sent_at, cancelled_at, metadata_json, checkpoint_id
+    "SELECT id, session_id, turn_id, role, content, sent_at, cancelled_at, metadata_json, checkpoint_id,
+            queue_position
      FROM session_messages
      WHERE id = ?",
   )
@@ -384,6 +789,143 @@ pub async fn get_session_message_checkpoint(
   Ok(row)
 }
 
+/// Checkpoint taken after the agent finished the given turn, i.e. the workspace
+/// state to move to when stepping forward to that turn.
+pub async fn get_turn_assistant_checkpoint(
+  pool: &SqlitePool,
+  session_id: &str,
+  turn_id: i64,
+) -> Result<Option<String>, DbError> {
+  let row: Option<(String,)> = sqlx::query_as(
+    "SELECT checkpoint_id
+     FROM session_turn_checkpoints
+     WHERE session_id = ? AND turn_id = ?",
+  )
+  .bind(session_id)
+  .bind(turn_id)
+  .fetch_optional(pool)
+  .await?;
+  Ok(row.map(|(checkpoint_id,)| checkpoint_id))
+}
+
+/// Record the checkpoint taken after `turn_id`. A new turn replaces the forward
+/// history left behind by an earlier reset, so later turns are dropped.
+pub async fn record_turn_checkpoint(
+  pool: &SqlitePool,
+  session_id: &str,
+  turn_id: i64,
+  checkpoint_id: &str,
+) -> Result<(), DbError> {
+  let mut tx = pool.begin().await?;
+  sqlx::query("DELETE FROM session_turn_checkpoints WHERE session_id = ? AND turn_id > ?")
+    .bind(session_id)
+    .bind(turn_id)
+    .execute(&mut *tx)
+    .await?;
+  sqlx::query(
+    "INSERT INTO session_turn_checkpoints (session_id, turn_id, checkpoint_id)
+     VALUES (?, ?, ?)
+     ON CONFLICT(session_id, turn_id) DO UPDATE SET
+       checkpoint_id = excluded.checkpoint_id,
+       created_at = CURRENT_TIMESTAMP",
+  )
+  .bind(session_id)
+  .bind(turn_id)
+  .bind(checkpoint_id)
+  .execute(&mut *tx)
+  .await?;
+  tx.commit().await?;
+  Ok(())
+}
+
+/// Count sessions of a repository, across all its workspaces, that are running.
+pub async fn count_running_repo_sessions(pool: &SqlitePool, repo_id: &str) -> Result<i64, DbError> {
+  let (count,): (i64,) = sqlx::query_as(
+    "SELECT COUNT(*)
+     FROM sessions s
+     JOIN workspaces w ON w.id = s.workspace_id
+     WHERE w.repo_id = ? AND s.status = 'running'",
+  )
+  .bind(repo_id)
+  .fetch_one(pool)
+  .await?;
+  Ok(count)
+}
+
+/// List turn checkpoints of every session in a repository, newest turn first per session.
+pub async fn list_repo_turn_checkpoints(
+  pool: &SqlitePool,
+  repo_id: &str,
+) -> Result<Vec<TurnCheckpointRef>, DbError> {
+  let rows = sqlx::query_as::<_, TurnCheckpointRef>(
+    "SELECT t.session_id, t.checkpoint_id
+     FROM session_turn_checkpoints t
+     JOIN sessions s ON s.id = t.session_id
+     JOIN workspaces w ON w.id = s.workspace_id
+     WHERE w.repo_id = ?
+     ORDER BY t.session_id, t.turn_id DESC",
+  )
+  .bind(repo_id)
+  .fetch_all(pool)
+  .await?;
+  Ok(rows)
+}
+
+/// Forget turn checkpoints whose snapshots were pruned.
+pub async fn delete_turn_checkpoints(
+  pool: &SqlitePool,
+  checkpoint_ids: &[String],
+) -> Result<(), DbError> {
+  let mut tx = pool.begin().await?;
+  for checkpoint_id in checkpoint_ids {
+    sqlx::query("DELETE FROM session_turn_checkpoints WHERE checkpoint_id = ?")
+      .bind(checkpoint_id)
+      .execute(&mut *tx)
+      .await?;
+  }
+  tx.commit().await?;
+  Ok(())
+}
+
+/// List checkpoint references held by messages of every session in a repository.
+pub async fn list_repo_message_checkpoints(
+  pool: &SqlitePool,
+  repo_id: &str,
+) -> Result<Vec<MessageCheckpointRef>, DbError> {
+  let rows = sqlx::query_as::<_, MessageCheckpointRef>(
+    "SELECT m.session_id, m.turn_id, m.id AS message_id, m.checkpoint_id
+     FROM session_messages m
+     JOIN sessions s ON s.id = m.session_id
+     JOIN workspaces w ON w.id = s.workspace_id
+     WHERE w.repo_id = ? AND m.checkpoint_id IS NOT NULL AND m.checkpoint_id != ''
+     ORDER BY m.session_id, m.turn_id DESC, m.created_at DESC",
+  )
+  .bind(repo_id)
+  .fetch_all(pool)
+  .await?;
+  Ok(rows)
+}
+
+/// Drop checkpoint references from messages whose snapshots were pruned.
+pub async fn clear_session_message_checkpoints(
+  pool: &SqlitePool,
+  message_ids: &[String],
+) -> Result<(), DbError> {
+  let mut tx = pool.begin().await?;
+  for message_id in message_ids {
+    sqlx::query(
+      "UPDATE session_messages
+       SET checkpoint_id = NULL, updated_at = CURRENT_TIMESTAMP
+       WHERE id = ?",
+    )
+    .bind(message_id)
+    .execute(&mut *tx)
+    .await?;
+  }
+  tx.commit().await?;
+  Ok(())
+}
+
 pub async fn delete_session_messages_from_turn(
   pool: &SqlitePool,
   session_id: &str,
@@ -448,13 +990,146 @@ pub async fn reset_session_to_turn(
   Ok(())
 }
 
+/// Create a session holding copies of the source's messages, attachments and tool
+/// calls up to and including `turn_id`. Copied messages keep their checkpoints.
+pub async fn fork_session(pool: &SqlitePool, fork: NewSessionFork) -> Result<SessionRecord, DbError> {
+  let source = get_session(pool, &fork.source_session_id).await?;
+  let id = generate_random_id(pool).await?;
+  let mut tx = pool.begin().await?;
+  sqlx::query(
+    "INSERT INTO sessions
+      (id, workspace_id, title, agent_type, model, status, claude_session_id,
+       forked_from_session_id, forked_from_turn_id, resume_session_at)
+     VALUES (?, ?, ?, ?, ?, 'idle', ?, ?, ?, ?)",
+  )
+  .bind(&id)
+  .bind(&fork.workspace_id)
+  .bind(&fork.title)
+  .bind(&source.agent_type)
+  .bind(&source.model)
+  .bind(&fork.claude_session_id)
+  .bind(&source.id)
+  .bind(fork.turn_id)
+  .bind(&fork.resume_session_at)
+  .execute(&mut *tx)
+  .await?;
+
+  let message_ids: Vec<String> = sqlx::query_scalar(
+    "SELECT id FROM session_messages
+     WHERE session_id = ? AND turn_id <= ? AND sent_at IS NOT NULL
+     ORDER BY sequence ASC",
+  )
+  .bind(&source.id)
+  .bind(fork.turn_id)
+  .fetch_all(&mut *tx)
+  .await?;
+  for message_id in message_ids {
+    let new_message_id: String = sqlx::query_scalar("SELECT lower(hex(randomblob(16)))")
+      .fetch_one(&mut *tx)
+      .await?;
+    sqlx::query(
+      "INSERT INTO session_messages
+        (id, session_id, turn_id, role, content, sent_at, cancelled_at, metadata_json,
+         checkpoint_id, created_at)
+       SELECT ?, ?, turn_id, role, content, sent_at, cancelled_at, metadata_json,
+              checkpoint_id, created_at
+       FROM session_messages
+       WHERE id = ?",
+    )
+    .bind(&new_message_id)
+    .bind(&id)
+    .bind(&message_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+      "INSERT INTO attachments
+        (id, session_id, session_message_id, type, title, path, mime_type, is_draft)
+       SELECT lower(hex(randomblob(16))), ?, ?, type, title, path, mime_type, 0
+       FROM attachments
+       WHERE session_message_id = ? AND is_draft = 0",
+    )
+    .bind(&id)
+    .bind(&new_message_id)
+    .bind(&message_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+      "INSERT INTO session_tool_calls
+        (id, session_id, turn_id, message_id, call_id, tool_name, input_json, output, status,
+         started_at_ms, finished_at_ms)
+       SELECT lower(hex(randomblob(16))), ?, turn_id, ?, call_id, tool_name, input_json, output,
+              status, started_at_ms, finished_at_ms
+       FROM session_tool_calls
+       WHERE message_id = ?",
+    )
+    .bind(&id)
+    .bind(&new_message_id)
+    .bind(&message_id)
+    .execute(&mut *tx)
+    .await?;
+  }
+  sqlx::query(
+    "INSERT INTO session_turn_checkpoints (session_id, turn_id, checkpoint_id)
+     SELECT ?, turn_id, checkpoint_id
+     FROM session_turn_checkpoints
+     WHERE session_id = ? AND turn_id <= ?",
+  )
+  .bind(&id)
+  .bind(&source.id)
+  .bind(fork.turn_id)
+  .execute(&mut *tx)
+  .await?;
+  // Calls the agent made before its reply was stored have no message yet.
+  sqlx::query(
+    "INSERT INTO session_tool_calls
+      (id, session_id, turn_id, message_id, call_id, tool_name, input_json, output, status,
+       started_at_ms, finished_at_ms)
+     SELECT lower(hex(randomblob(16))), ?, turn_id, NULL, call_id, tool_name, input_json, output,
+            status, started_at_ms, finished_at_ms
+     FROM session_tool_calls
+     WHERE session_id = ? AND turn_id <= ? AND message_id IS NULL",
+  )
+  .bind(&id)
+  .bind(&source.id)
+  .bind(fork.turn_id)
+  .execute(&mut *tx)
+  .await?;
+  tx.commit().await?;
+
+  get_session(pool, &id).await
+}
+
+/// Claude's uuid for the last assistant message of a turn, recorded in the
+/// message metadata as `agentMessageId`.
+pub async fn get_turn_agent_message_id(
+  pool: &SqlitePool,
+  session_id: &str,
+  turn_id: i64,
+) -> Result<Option<String>, DbError> {
+  let id: Option<Option<String>> = sqlx::query_scalar(
+    "SELECT json_extract(metadata_json, '$.agentMessageId')
+     FROM session_messages
+     WHERE session_id = ? AND turn_id = ? AND role = 'assistant'
+       AND json_valid(metadata_json)
+     ORDER BY sequence DESC
+     LIMIT 1",
+  )
+  .bind(session_id)
+  .bind(turn_id)
+  .fetch_optional(pool)
+  .await?;
+  Ok(id.flatten())
+}
+
 pub async fn list_workspace_sessions(
   pool: &SqlitePool,
   workspace_id: &str,
 ) -> Result<Vec<SessionRecord>, DbError> {
   let rows = sqlx::query_as::<_, SessionRecord>(
     "SELECT id, workspace_id, title, agent_type, model, status, unread_count,
-            claude_session_id, codex_session_id, context_token_count, is_compacted
+            claude_session_id, codex_session_id, context_token_count, is_compacted,
+            sidecar_restart_count, forked_from_session_id, forked_from_turn_id,
+            resume_session_at
      FROM sessions
      WHERE workspace_id = ?
      ORDER BY created_at DESC",
@@ -465,6 +1140,29 @@ pub async fn list_workspace_sessions(
   Ok(rows)
 }
 
+pub async fn list_workspace_turn_windows(
+  pool: &SqlitePool,
+  workspace_id: &str,
+) -> Result<Vec<SessionTurnWindow>, DbError> {
+  let rows = sqlx::query_as::<_, SessionTurnWindow>(
+    "SELECT m.session_id,
+            s.title AS session_title,
+            m.turn_id,
+            MIN(CASE WHEN m.role = 'user' THEN m.id END) AS message_id,
+            CAST(strftime('%s', MIN(m.created_at)) AS INTEGER) AS started_at,
+            CAST(strftime('%s', MAX(m.updated_at)) AS INTEGER) AS finished_at
+     FROM session_messages m
+     JOIN sessions s ON s.id = m.session_id
+     WHERE s.workspace_id = ? AND m.turn_id > 0
+     GROUP BY m.session_id, m.turn_id
+     ORDER BY started_at ASC",
+  )
+  .bind(workspace_id)
+  .fetch_all(pool)
+  .await?;
+  Ok(rows)
+}
+
 pub async fn clear_session_resume(
   pool: &SqlitePool,
   session_id: &str,
@@ -16,6 +16,19 @@ pub const KEY_ENV_VARS: &str = "env_vars";
 pub const KEY_WORKSPACES_ROOT: &str = "workspaces_root";
 /// Settings key for spotlight feature enablement.
 pub const KEY_SPOTLIGHT_ENABLED: &str = "spotlight_enabled";
+/// Settings key for how many of a session's newest turns keep their checkpoints (0 keeps all).
+pub const KEY_CHECKPOINT_KEEP_PER_SESSION: &str = "checkpoint_keep_per_session";
+/// Settings key for how long spotlight waits for file changes to settle (ms).
+pub const KEY_SPOTLIGHT_DEBOUNCE_MS: &str = "spotlight_debounce_ms";
+/// Settings key for the maximum number of sidecar processes shared by sessions.
+pub const KEY_SIDECAR_MAX_PROCESSES: &str = "sidecar_max_processes";
+/// Settings key for how long an idle session or sidecar process is kept (0 keeps them).
+pub const KEY_SIDECAR_IDLE_TIMEOUT_SECS: &str = "sidecar_idle_timeout_secs";
+/// Settings key for titling new Claude chats from their first turn (off unless
+/// "true"). Codex chats are never retitled.
+pub const KEY_SESSION_AUTO_TITLE: &str = "session_auto_title";
+/// Settings key for the Claude model that writes chat titles.
+pub const KEY_SESSION_TITLE_MODEL: &str = "session_title_model";
 
 const DEFAULT_SETTINGS: &[(&str, &str)] = &[
   (KEY_DEFAULT_MODEL, "gpt-5-codex"),
@@ -25,6 +38,12 @@ const DEFAULT_SETTINGS: &[(&str, &str)] = &[
   (KEY_ENV_VARS, ""),
   (KEY_WORKSPACES_ROOT, ""),
   (KEY_SPOTLIGHT_ENABLED, "false"),
+  (KEY_CHECKPOINT_KEEP_PER_SESSION, "100"),
+  (KEY_SPOTLIGHT_DEBOUNCE_MS, "500"),
+  (KEY_SIDECAR_MAX_PROCESSES, "4"),
+  (KEY_SIDECAR_IDLE_TIMEOUT_SECS, "600"),
+  (KEY_SESSION_AUTO_TITLE, "false"),
+  (KEY_SESSION_TITLE_MODEL, "claude-haiku-4-5"),
 ];
 
 /// Stored settings entry.
@@ -90,3 +109,58 @@ pub async fn get_env_vars(pool: &SqlitePool) -> Result<String, DbError> {
 pub async fn set_env_vars(pool: &SqlitePool, value: &str) -> Result<(), DbError> {
   set_setting(pool, KEY_ENV_VARS, value).await
 }
+
+/// Get how many turns per session keep their checkpoints; 0 means unlimited.
+pub async fn get_checkpoint_keep_per_session(pool: &SqlitePool) -> Result<usize, DbError> {
+  Ok(
+    get_setting(pool, KEY_CHECKPOINT_KEEP_PER_SESSION)
+      .await?
+      .and_then(|value| value.trim().parse::<usize>().ok())
+      .unwrap_or(100),
+  )
+}
+
+/// Get the spotlight debounce interval in milliseconds.
+pub async fn get_spotlight_debounce_ms(pool: &SqlitePool) -> Result<u64, DbError> {
+  Ok(
+    get_setting(pool, KEY_SPOTLIGHT_DEBOUNCE_MS)
+      .await?
+      .and_then(|value| value.trim().parse::<u64>().ok())
+      .unwrap_or(500),
+  )
+}
+
+/// Get the sidecar process limit; always at least one.
+pub async fn get_sidecar_max_processes(pool: &SqlitePool) -> Result<usize, DbError> {
+  Ok(
+    get_setting(pool, KEY_SIDECAR_MAX_PROCESSES)
+      .await?
+      .and_then(|value| value.trim().parse::<usize>().ok())
+      .unwrap_or(4)
+      .max(1),
+  )
+}
+
+/// Get the sidecar idle timeout in seconds; 0 disables idle eviction.
+pub async fn get_sidecar_idle_timeout_secs(pool: &SqlitePool) -> Result<u64, DbError> {
+  Ok(
+    get_setting(pool, KEY_SIDECAR_IDLE_TIMEOUT_SECS)
+      .await?
+      .and_then(|value| value.trim().parse::<u64>().ok())
+      .unwrap_or(600),
+  )
+}
+
+/// Get the model used to title new chats, or `None` when auto-titles are off.
+pub async fn get_session_title_model(pool: &SqlitePool) -> Result<Option<String>, DbError> {
+  if get_setting(pool, KEY_SESSION_AUTO_TITLE).await?.as_deref() != Some("true") {
+    return Ok(None);
+  }
+  Ok(Some(
+    get_setting(pool, KEY_SESSION_TITLE_MODEL)
+      .await?
+      .map(|value| value.trim().to_string())
+      .filter(|value| !value.is_empty())
+      .unwrap_or_else(|| "claude-haiku-4-5".to_string()),
+  ))
+}
@@ -1,17 +1,22 @@
+use crate::checkpoints::{create_agent_checkpoint, CheckpointOutcome};
 use crate::db::Database;
 use crate::path_utils;
 use crate::sessions;
+use crate::settings;
+use crate::titles;
+use crate::tool_calls;
+use crate::usage;
 use crate::workspace;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Emitter, Manager};
 use tauri::path::BaseDirectory;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
@@ -24,6 +29,18 @@ type PendingResponse = oneshot::Sender<Result<Value, String>>;
 const SOCKET_PATH_TIMEOUT_SECS: u64 = 30;
 const FRONTEND_RESPONSE_TIMEOUT_SECS: u64 = 120;
 const MAX_STORED_DIFF_BYTES: usize = 200_000;
+const SIDECAR_REQUEST_TIMEOUT_SECS: u64 = 5;
+/// `contextUsage` runs a `/context` query through the agent CLI.
+const CONTEXT_USAGE_TIMEOUT_SECS: u64 = 60;
+/// `generateTitle` runs a one-turn query against the title model.
+const GENERATE_TITLE_TIMEOUT_SECS: u64 = 60;
+const IDLE_SWEEP_INTERVAL_SECS: u64 = 30;
+/// Attempts to bring a crashed sidecar back before its sessions are failed.
+const MAX_RESPAWN_ATTEMPTS: u32 = 5;
+/// A session that crashes its sidecar this often within the window is failed
+/// instead of restarted again, so a crashing turn cannot loop forever.
+const MAX_SESSION_RESTARTS: u32 = 3;
+const SESSION_RESTART_WINDOW_SECS: u64 = 300;
 
 fn truncate_utf8(value: &str, max_bytes: usize) -> String {
   if value.len() <= max_bytes {
@@ -40,10 +57,20 @@ fn truncate_utf8(value: &str, max_bytes: usize) -> String {
   value[..end].to_string()
 }
 
+type SessionMap = Arc<Mutex<HashMap<String, Arc<SidecarSession>>>>;
+type ProcessPool = Arc<Mutex<Vec<Arc<SidecarProcess>>>>;
+
 #[derive(Clone)]
 pub struct SidecarManager {
-  sessions: Arc<Mutex<HashMap<String, Arc<SidecarSession>>>>,
+  sessions: SessionMap,
+  processes: ProcessPool,
   pending_frontend: Arc<Mutex<HashMap<String, PendingFrontendRequest>>>,
+  next_process_id: Arc<AtomicU64>,
+  sweeper_started: Arc<AtomicBool>,
+  shutting_down: Arc<AtomicBool>,
+  restart_count: Arc<AtomicU64>,
+  /// Recent restarts per session: (count, window start).
+  recent_restarts: Arc<Mutex<HashMap<String, (u32, Instant)>>>,
   app_handle: AppHandle,
   db: Database,
 }
@@ -52,21 +79,115 @@ struct PendingFrontendRequest {
   responder: oneshot::Sender<Value>,
 }
 
-struct SidecarSession {
-  session_id: String,
+/// One Node sidecar process and its socket. Several sessions share a process;
+/// the sidecar keeps them apart by the session `id` carried on every message.
+struct SidecarProcess {
+  process_id: u64,
+  pid: u32,
   writer: Arc<Mutex<SidecarWriter>>,
   pending: Arc<Mutex<HashMap<String, PendingResponse>>>,
-  streaming: Arc<Mutex<StreamingState>>,
+  next_request_id: AtomicU64,
+  session_ids: Mutex<HashSet<String>>,
+  last_active: AtomicU64,
   child: Arc<Mutex<Option<Child>>>,
   closing: Arc<AtomicBool>,
   reader_task: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
+struct SidecarSession {
+  session_id: String,
+  process: Arc<SidecarProcess>,
+  streaming: Arc<Mutex<StreamingState>>,
+  /// Held while `/context` resumes the Claude session after a turn; the next
+  /// query waits for it so the two never resume the same session at once.
+  context_refresh: Arc<Mutex<()>>,
+  /// Held while a finished turn's diff and checkpoint are captured; the next
+  /// query waits for it so the checkpoint never sees the next turn's edits.
+  finalizing: Arc<Mutex<()>>,
+  last_active: AtomicU64,
+}
+
+/// Snapshot of the sidecar pool for `getSidecarStats`.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarStats {
+  pub max_processes: usize,
+  pub idle_timeout_secs: u64,
+  pub session_count: usize,
+  /// Sidecar processes restarted after crashing since the app started.
+  pub restart_count: u64,
+  /// Sum of the processes' resident memory that could be measured.
+  pub memory_bytes: u64,
+  pub processes: Vec<SidecarProcessStats>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarProcessStats {
+  pub process_id: u64,
+  pub pid: u32,
+  pub session_ids: Vec<String>,
+  pub idle_secs: u64,
+  pub memory_bytes: Option<u64>,
+  pub heap_used_bytes: Option<u64>,
+}
+
 #[derive(Default)]
 struct StreamingState {
   current_turn_id: Option<i64>,
   assistant_message_id: Option<String>,
   assistant_content: String,
+  /// The query for the current turn, kept so it can be re-sent after a restart.
+  in_flight: Option<InFlightQuery>,
+  /// Set once the agent reported anything for the current turn; from then on it
+  /// may have run tools, so re-sending the prompt is no longer safe.
+  received_output: bool,
+  /// Turn stopped by `cancel`; the sidecar's abort error for it is expected.
+  cancelled_turn_id: Option<i64>,
+  /// uuid of the turn's latest Claude assistant message, a `resumeSessionAt` target.
+  agent_message_id: Option<String>,
+  /// Context size reported with the turn's latest Claude assistant message.
+  last_context_tokens: Option<i64>,
+}
+
+/// What a turn had produced when it was cancelled.
+pub struct CancelledTurn {
+  pub turn_id: i64,
+  pub assistant_message_id: Option<String>,
+  pub assistant_content: String,
+}
+
+#[derive(Clone)]
+struct InFlightQuery {
+  agent_type: String,
+  prompt: String,
+  options: Value,
+}
+
+/// What becomes of a session's running turn when its sidecar died.
+enum InterruptedTurn {
+  /// No turn was running.
+  None,
+  /// Nothing was reported yet, so the query can be sent again.
+  Resend(InFlightQuery, i64),
+  /// The agent may already have run tools; the turn has to end.
+  End(i64),
+}
+
+impl StreamingState {
+  /// Decide what to do with the running turn after a sidecar restart. A turn
+  /// that is ended is cleared here; one to re-send stays current.
+  fn take_interrupted_turn(&mut self) -> InterruptedTurn {
+    let (Some(turn_id), Some(in_flight)) = (self.current_turn_id, self.in_flight.clone()) else {
+      return InterruptedTurn::None;
+    };
+    if self.received_output {
+      self.current_turn_id = None;
+      self.in_flight = None;
+      return InterruptedTurn::End(turn_id);
+    }
+    InterruptedTurn::Resend(in_flight, turn_id)
+  }
 }
 
 #[derive(Debug, Deserialize)]
@@ -168,6 +289,7 @@ struct UiMessagePayload {
   role: String,
   content: String,
   metadata: Option<Value>,
+  checkpoint_id: Option<String>,
   streaming: bool,
 }
 
@@ -183,6 +305,24 @@ struct SessionErrorEvent {
 struct SessionStatusEvent {
   session_id: String,
   status: String,
+  /// Why the session went idle: `finished`, `cancelled`, `error` or `closed`.
+  reason: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SessionContextUsageEvent {
+  session_id: String,
+  context_token_count: i64,
+  is_compacted: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SidecarRestartedEvent {
+  session_id: String,
+  restart_count: i64,
+  reissued: bool,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -210,7 +350,13 @@ impl SidecarManager {
   pub fn new(app_handle: AppHandle, db: Database) -> Self {
     Self {
       sessions: Arc::new(Mutex::new(HashMap::new())),
+      processes: Arc::new(Mutex::new(Vec::new())),
       pending_frontend: Arc::new(Mutex::new(HashMap::new())),
+      next_process_id: Arc::new(AtomicU64::new(1)),
+      sweeper_started: Arc::new(AtomicBool::new(false)),
+      shutting_down: Arc::new(AtomicBool::new(false)),
+      restart_count: Arc::new(AtomicU64::new(0)),
+      recent_restarts: Arc::new(Mutex::new(HashMap::new())),
       app_handle,
       db,
     }
@@ -228,12 +374,24 @@ impl SidecarManager {
       return Err(format!("Unknown agent type: {agent_type}"));
     }
     let session = self.ensure_session(session_id).await?;
+    drop(session.finalizing.lock().await);
+    drop(session.context_refresh.lock().await);
     {
       let mut state = session.streaming.lock().await;
       state.current_turn_id = Some(turn_id);
       state.assistant_message_id = None;
       state.assistant_content.clear();
+      state.cancelled_turn_id = None;
+      state.agent_message_id = None;
+      state.last_context_tokens = None;
+      state.in_flight = Some(InFlightQuery {
+        agent_type: agent_type.to_string(),
+        prompt: prompt.to_string(),
+        options: options.clone(),
+      });
+      state.received_output = false;
     }
+    session.touch();
     let payload = json!({
       "jsonrpc": "2.0",
       "method": "query",
@@ -245,10 +403,16 @@ impl SidecarManager {
         "options": options,
       }
     });
-    session.send_raw(payload).await
+    session.process.send_raw(payload).await
   }
 
-  pub async fn cancel(&self, session_id: &str, agent_type: &str) -> Result<(), String> {
+  /// Ask the agent to stop and drop the streaming state of the running turn so
+  /// late output is ignored. Returns the partial turn, if one was running.
+  pub async fn cancel(
+    &self,
+    session_id: &str,
+    agent_type: &str,
+  ) -> Result<Option<CancelledTurn>, String> {
     let session = self.ensure_session(session_id).await?;
     let payload = json!({
       "jsonrpc": "2.0",
@@ -259,7 +423,19 @@ impl SidecarManager {
         "agentType": agent_type,
       }
     });
-    session.send_raw(payload).await
+    session.process.send_raw(payload).await?;
+
+    let mut state = session.streaming.lock().await;
+    state.in_flight = None;
+    let Some(turn_id) = state.current_turn_id.take() else {
+      return Ok(None);
+    };
+    state.cancelled_turn_id = Some(turn_id);
+    Ok(Some(CancelledTurn {
+      turn_id,
+      assistant_message_id: state.assistant_message_id.take(),
+      assistant_content: std::mem::take(&mut state.assistant_content),
+    }))
   }
 
   pub async fn update_permission_mode(
@@ -282,7 +458,7 @@ impl SidecarManager {
         "permissionMode": mode,
       }
     });
-    session.send_raw(payload).await
+    session.process.send_raw(payload).await
   }
 
   pub async fn resolve_frontend_request(
@@ -303,27 +479,66 @@ impl SidecarManager {
       .map_err(|_| "Failed to deliver frontend response".to_string())
   }
 
+  /// Detach a session from its process and drop its sidecar-side state. The
+  /// process stays up for the other sessions it hosts.
   pub async fn close_session(&self, session_id: &str) {
     let session = {
       let mut guard = self.sessions.lock().await;
       guard.remove(session_id)
     };
+    self.recent_restarts.lock().await.remove(session_id);
     if let Some(session) = session {
-      session.shutdown().await;
+      session.process.detach(session_id).await;
     }
   }
 
   pub async fn shutdown_all(&self) {
-    let sessions = {
-      let guard = self.sessions.lock().await;
-      guard.values().cloned().collect::<Vec<_>>()
+    self.shutting_down.store(true, Ordering::SeqCst);
+    let processes = {
+      let mut guard = self.processes.lock().await;
+      std::mem::take(&mut *guard)
     };
-    for session in sessions {
-      session.shutdown().await;
+    for process in processes {
+      process.shutdown().await;
+    }
+  }
+
+  pub async fn stats(&self) -> SidecarStats {
+    let max_processes = settings::get_sidecar_max_processes(self.db.pool())
+      .await
+      .unwrap_or(1);
+    let idle_timeout_secs = settings::get_sidecar_idle_timeout_secs(self.db.pool())
+      .await
+      .unwrap_or(0);
+    let processes = self.processes.lock().await.clone();
+    let now = unix_now();
+    let mut stats = Vec::with_capacity(processes.len());
+    for process in processes {
+      let mut session_ids: Vec<String> = process.session_ids.lock().await.iter().cloned().collect();
+      session_ids.sort();
+      let usage = process.request("stats", json!({})).await.ok();
+      let read = |key: &str| usage.as_ref().and_then(|value| value.get(key)).and_then(Value::as_u64);
+      stats.push(SidecarProcessStats {
+        process_id: process.process_id,
+        pid: process.pid,
+        session_ids,
+        idle_secs: now.saturating_sub(process.last_active.load(Ordering::Relaxed)),
+        memory_bytes: read("rss"),
+        heap_used_bytes: read("heapUsed"),
+      });
+    }
+    SidecarStats {
+      max_processes,
+      idle_timeout_secs,
+      session_count: self.sessions.lock().await.len(),
+      restart_count: self.restart_count.load(Ordering::Relaxed),
+      memory_bytes: stats.iter().filter_map(|process| process.memory_bytes).sum(),
+      processes: stats,
     }
   }
 
   async fn ensure_session(&self, session_id: &str) -> Result<Arc<SidecarSession>, String> {
+    self.ensure_sweeper();
     let existing = {
       let guard = self.sessions.lock().await;
       guard.get(session_id).cloned()
@@ -331,42 +546,132 @@ impl SidecarManager {
     if let Some(session) = existing {
       return Ok(session);
     }
-    let session = self.spawn_session(session_id).await?;
+    let process = self.acquire_process(session_id).await?;
+    let session = Arc::new(SidecarSession {
+      session_id: session_id.to_string(),
+      process: process.clone(),
+      streaming: Arc::new(Mutex::new(StreamingState::default())),
+      context_refresh: Arc::new(Mutex::new(())),
+      finalizing: Arc::new(Mutex::new(())),
+      last_active: AtomicU64::new(unix_now()),
+    });
     let mut guard = self.sessions.lock().await;
+    if let Some(existing) = guard.get(session_id).cloned() {
+      // Lost a race with another caller; keep the session that won.
+      drop(guard);
+      if existing.process.process_id != process.process_id {
+        process.session_ids.lock().await.remove(session_id);
+      }
+      return Ok(existing);
+    }
     guard.insert(session_id.to_string(), session.clone());
     Ok(session)
   }
 
-  async fn spawn_session(&self, session_id: &str) -> Result<Arc<SidecarSession>, String> {
-    // NOTE: One Node sidecar process + socket reader per session for isolation.
-    // Per session: one child process, one socket connection, and stdio FDs (~4-6 total).
-    // Memory is dominated by the Node runtime + SDK (rough order: tens of MB per session).
-    // No pooling/limit yet; keep session counts bounded (TODO: pool/max sessions if needed).
+  /// Pick a process for a new session and attach it there. Sessions spread over
+  /// new processes until the limit is reached, then share the least loaded one.
+  /// Spawning happens outside the `processes` lock so a slow start does not stall
+  /// other sessions.
+  async fn acquire_process(&self, session_id: &str) -> Result<Arc<SidecarProcess>, String> {
+    let max_processes = settings::get_sidecar_max_processes(self.db.pool())
+      .await
+      .map_err(|err| err.to_string())?;
+    {
+      let mut processes = self.processes.lock().await;
+      processes.retain(|process| !process.closing.load(Ordering::SeqCst));
+      if let Some((count, process)) = least_loaded_process(&processes).await {
+        if count == 0 || processes.len() >= max_processes {
+          drop(processes);
+          return Ok(attach_process(process, session_id).await);
+        }
+      }
+    }
+
+    let spawned = self.spawn_process().await?;
+    let mut processes = self.processes.lock().await;
+    processes.retain(|process| !process.closing.load(Ordering::SeqCst));
+    if processes.len() >= max_processes {
+      // Other sessions filled the pool while this one was starting.
+      if let Some((_, process)) = least_loaded_process(&processes).await {
+        drop(processes);
+        spawned.shutdown().await;
+        return Ok(attach_process(process, session_id).await);
+      }
+    }
+    processes.push(spawned.clone());
+    drop(processes);
+    Ok(attach_process(spawned, session_id).await)
+  }
+
+  async fn spawn_process(&self) -> Result<Arc<SidecarProcess>, String> {
+    // Per process: one Node runtime + SDK (tens of MB), one socket connection and
+    // its stdio FDs. `sidecar_max_processes` bounds how many exist at once.
     let (child, socket_path) = spawn_sidecar_process(&self.app_handle)?;
     let (reader, writer) = connect_socket(&socket_path).await?;
-    let session = Arc::new(SidecarSession {
-      session_id: session_id.to_string(),
+    let process = Arc::new(SidecarProcess {
+      process_id: self.next_process_id.fetch_add(1, Ordering::SeqCst),
+      pid: child.id(),
       writer: Arc::new(Mutex::new(writer)),
       pending: Arc::new(Mutex::new(HashMap::new())),
-      streaming: Arc::new(Mutex::new(StreamingState::default())),
+      next_request_id: AtomicU64::new(1),
+      session_ids: Mutex::new(HashSet::new()),
+      last_active: AtomicU64::new(unix_now()),
       child: Arc::new(Mutex::new(Some(child))),
       closing: Arc::new(AtomicBool::new(false)),
       reader_task: Arc::new(Mutex::new(None)),
     });
-    let reader_handle = self.spawn_reader(session.clone(), reader);
-    *session.reader_task.lock().await = Some(reader_handle);
-    Ok(session)
+    let reader_handle = self.spawn_reader(process.clone(), reader);
+    *process.reader_task.lock().await = Some(reader_handle);
+    Ok(process)
+  }
+
+  fn ensure_sweeper(&self) {
+    if self.sweeper_started.swap(true, Ordering::SeqCst) {
+      return;
+    }
+    let manager = self.clone();
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(Duration::from_secs(IDLE_SWEEP_INTERVAL_SECS));
+      loop {
+        interval.tick().await;
+        manager.evict_idle().await;
+      }
+    });
+  }
+
+  /// Detach sessions with no activity for the idle timeout, then stop processes
+  /// that have had no sessions for as long.
+  async fn evict_idle(&self) {
+    let idle_timeout_secs = match settings::get_sidecar_idle_timeout_secs(self.db.pool()).await {
+      Ok(0) | Err(_) => return,
+      Ok(value) => value,
+    };
+    let now = unix_now();
+    let sessions = self.sessions.lock().await.values().cloned().collect::<Vec<_>>();
+    for session_id in idle_sessions(&sessions, now, idle_timeout_secs).await {
+      self.close_session(&session_id).await;
+    }
+
+    let idle_processes = {
+      let mut processes = self.processes.lock().await;
+      take_idle_processes(&mut processes, now, idle_timeout_secs).await
+    };
+    for process in idle_processes {
+      process.shutdown().await;
+    }
   }
 
   fn spawn_reader(
     &self,
-    session: Arc<SidecarSession>,
+    process: Arc<SidecarProcess>,
     reader: Box<dyn tokio::io::AsyncRead + Send + Unpin>,
   ) -> JoinHandle<()> {
     let app_handle = self.app_handle.clone();
     let db = self.db.clone();
     let pending_frontend = self.pending_frontend.clone();
     let sessions_map = self.sessions.clone();
+    let processes = self.processes.clone();
+    let manager = self.clone();
     tokio::spawn(async move {
       let mut lines = tokio::io::BufReader::new(reader).lines();
       loop {
@@ -388,8 +693,10 @@ impl SidecarManager {
             continue;
           }
         };
+        process.touch();
         if let Err(err) = handle_payload(
-          &session,
+          &process,
+          &sessions_map,
           &payload,
           &app_handle,
           &db,
@@ -400,32 +707,277 @@ impl SidecarManager {
           eprintln!("[sidecar] payload handling error: {err}");
         }
       }
-      let closing = session.closing.load(Ordering::SeqCst);
-      sessions_map.lock().await.remove(&session.session_id);
+      let closing = process.closing.load(Ordering::SeqCst);
+      processes
+        .lock()
+        .await
+        .retain(|item| item.process_id != process.process_id);
+      process.pending.lock().await.clear();
+      let session_ids = process.session_ids.lock().await.drain().collect::<Vec<_>>();
+      let mut orphaned = Vec::new();
+      {
+        let mut map = sessions_map.lock().await;
+        for session_id in &session_ids {
+          if map
+            .get(session_id)
+            .is_some_and(|session| session.process.process_id == process.process_id)
+          {
+            orphaned.extend(map.remove(session_id));
+          }
+        }
+      }
       if closing {
-        let _ = sessions::set_session_status(db.pool(), &session.session_id, "idle").await;
-        let _ = app_handle.emit(
-          "session-status",
-          SessionStatusEvent {
-            session_id: session.session_id.clone(),
-            status: "idle".to_string(),
-          },
-        );
+        for session in orphaned {
+          mark_session_idle(&db, &app_handle, &session.session_id, "closed").await;
+        }
       } else {
-        let _ = sessions::set_session_status(db.pool(), &session.session_id, "error").await;
-        let _ = app_handle.emit(
-          "session-error",
-          SessionErrorEvent {
-            session_id: session.session_id.clone(),
-            error: "Sidecar disconnected".to_string(),
-          },
-        );
+        eprintln!("[sidecar] process {} exited unexpectedly", process.pid);
+        manager.restart_sessions(orphaned).await;
       }
     })
   }
+
+  /// Bring sessions of a crashed process back on a fresh process, retrying with
+  /// exponential backoff. Sessions that cannot be restarted are marked `error`.
+  async fn restart_sessions(&self, orphaned: Vec<Arc<SidecarSession>>) {
+    if orphaned.is_empty() {
+      return;
+    }
+    let mut restartable = Vec::new();
+    for session in orphaned {
+      if self.note_restart(&session.session_id).await {
+        restartable.push(session);
+      } else {
+        self
+          .fail_session(&session.session_id, "Sidecar keeps crashing; restart limit reached")
+          .await;
+      }
+    }
+    if restartable.is_empty() {
+      return;
+    }
+
+    let mut process = None;
+    for attempt in 0..MAX_RESPAWN_ATTEMPTS {
+      tokio::time::sleep(Duration::from_secs(1 << attempt)).await;
+      if self.shutting_down.load(Ordering::SeqCst) {
+        return;
+      }
+      match self.spawn_process().await {
+        Ok(spawned) => {
+          process = Some(spawned);
+          break;
+        }
+        Err(err) => eprintln!("[sidecar] restart attempt {} failed: {err}", attempt + 1),
+      }
+    }
+    let Some(process) = process else {
+      for session in restartable {
+        self.fail_session(&session.session_id, "Sidecar disconnected").await;
+      }
+      return;
+    };
+    self.processes.lock().await.push(process.clone());
+    self.restart_count.fetch_add(1, Ordering::Relaxed);
+
+    for old in restartable {
+      let session_id = old.session_id.clone();
+      let session = Arc::new(SidecarSession {
+        session_id: session_id.clone(),
+        process: process.clone(),
+        streaming: old.streaming.clone(),
+        context_refresh: old.context_refresh.clone(),
+        finalizing: old.finalizing.clone(),
+        last_active: AtomicU64::new(unix_now()),
+      });
+      {
+        let mut map = self.sessions.lock().await;
+        // The user already started over on another process.
+        if map.contains_key(&session_id) {
+          continue;
+        }
+        process.session_ids.lock().await.insert(session_id.clone());
+        map.insert(session_id.clone(), session.clone());
+      }
+      let restart_count = sessions::increment_session_restart_count(self.db.pool(), &session_id)
+        .await
+        .unwrap_or(0);
+      let reissued = self.resume_in_flight_turn(&session).await;
+      let _ = self.app_handle.emit(
+        "session-sidecar-restarted",
+        SidecarRestartedEvent {
+          session_id,
+          restart_count,
+          reissued,
+        },
+      );
+    }
+  }
+
+  /// Re-send the turn that was running when the sidecar died, resuming the agent
+  /// session from its stored token. Only done if the agent had not reported any
+  /// output yet; otherwise the turn is ended with a system message. Returns
+  /// whether the turn was re-sent.
+  async fn resume_in_flight_turn(&self, session: &SidecarSession) -> bool {
+    let interrupted = session.streaming.lock().await.take_interrupted_turn();
+    let (in_flight, turn_id) = match interrupted {
+      InterruptedTurn::None => return false,
+      InterruptedTurn::End(turn_id) => {
+        self.interrupt_turn(&session.session_id, turn_id).await;
+        return false;
+      }
+      InterruptedTurn::Resend(in_flight, turn_id) => (in_flight, turn_id),
+    };
+
+    let mut options = in_flight.options.clone();
+    if let Ok(record) = sessions::get_session(self.db.pool(), &session.session_id).await {
+      let resume = match in_flight.agent_type.as_str() {
+        "claude" => record.claude_session_id,
+        "codex" => record.codex_session_id,
+        _ => None,
+      };
+      if let (Some(resume), Some(object)) = (resume, options.as_object_mut()) {
+        object.insert("resume".to_string(), Value::String(resume));
+      }
+    }
+    let payload = json!({
+      "jsonrpc": "2.0",
+      "method": "query",
+      "params": {
+        "type": "query",
+        "id": session.session_id,
+        "agentType": in_flight.agent_type,
+        "prompt": in_flight.prompt,
+        "options": options,
+      }
+    });
+    match session.process.send_raw(payload).await {
+      Ok(()) => true,
+      Err(err) => {
+        eprintln!("[sidecar] failed to re-send turn {turn_id}: {err}");
+        {
+          let mut state = session.streaming.lock().await;
+          state.current_turn_id = None;
+          state.in_flight = None;
+        }
+        self.interrupt_turn(&session.session_id, turn_id).await;
+        false
+      }
+    }
+  }
+
+  async fn interrupt_turn(&self, session_id: &str, turn_id: i64) {
+    let _ =
+      tool_calls::finish_running_tool_calls(self.db.pool(), session_id, turn_id, "interrupted").await;
+    let error = "Sidecar restarted during this turn; it may be incomplete. Send a message to continue.";
+    if let Ok(message_id) = sessions::generate_message_id(self.db.pool()).await {
+      let _ = sessions::insert_session_message(
+        self.db.pool(),
+        sessions::NewSessionMessage {
+          id: message_id,
+          session_id: session_id.to_string(),
+          turn_id,
+          role: "system".to_string(),
+          content: error.to_string(),
+          metadata_json: Some(json!({ "kind": "sidecar_restart" }).to_string()),
+          checkpoint_id: None,
+        },
+      )
+      .await;
+    }
+    let _ = self.app_handle.emit(
+      "session-error",
+      SessionErrorEvent {
+        session_id: session_id.to_string(),
+        error: error.to_string(),
+      },
+    );
+    mark_session_idle(&self.db, &self.app_handle, session_id, "error").await;
+  }
+
+  async fn fail_session(&self, session_id: &str, error: &str) {
+    let _ = sessions::set_session_status(self.db.pool(), session_id, "error").await;
+    let _ = self.app_handle.emit(
+      "session-error",
+      SessionErrorEvent {
+        session_id: session_id.to_string(),
+        error: error.to_string(),
+      },
+    );
+  }
+
+  /// Count a restart for the session; false once it exceeded the limit.
+  async fn note_restart(&self, session_id: &str) -> bool {
+    let mut recent = self.recent_restarts.lock().await;
+    count_restart(&mut recent, session_id, Instant::now())
+  }
 }
 
 impl SidecarSession {
+  fn touch(&self) {
+    self.last_active.store(unix_now(), Ordering::Relaxed);
+  }
+}
+
+impl SidecarProcess {
+  fn touch(&self) {
+    self.last_active.store(unix_now(), Ordering::Relaxed);
+  }
+
+  async fn detach(&self, session_id: &str) {
+    self.session_ids.lock().await.remove(session_id);
+    self.touch();
+    let payload = json!({
+      "jsonrpc": "2.0",
+      "method": "closeSession",
+      "params": {
+        "type": "close_session",
+        "id": session_id,
+      }
+    });
+    if let Err(err) = self.send_raw(payload).await {
+      eprintln!("[sidecar] failed to close session {session_id}: {err}");
+    }
+  }
+
+  async fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+    self
+      .request_with_timeout(method, params, Duration::from_secs(SIDECAR_REQUEST_TIMEOUT_SECS))
+      .await
+  }
+
+  async fn request_with_timeout(
+    &self,
+    method: &str,
+    params: Value,
+    limit: Duration,
+  ) -> Result<Value, String> {
+    let key = format!(
+      "supertree-{}",
+      self.next_request_id.fetch_add(1, Ordering::SeqCst)
+    );
+    let (tx, rx) = oneshot::channel();
+    self.pending.lock().await.insert(key.clone(), tx);
+    let payload = json!({
+      "jsonrpc": "2.0",
+      "id": key,
+      "method": method,
+      "params": params,
+    });
+    if let Err(err) = self.send_raw(payload).await {
+      self.pending.lock().await.remove(&key);
+      return Err(err);
+    }
+    match timeout(limit, rx).await {
+      Ok(Ok(result)) => result,
+      Ok(Err(_)) => Err("Sidecar closed before responding".to_string()),
+      Err(_) => {
+        self.pending.lock().await.remove(&key);
+        Err(format!("Sidecar request {method} timed out"))
+      }
+    }
+  }
+
   async fn send_raw(&self, payload: Value) -> Result<(), String> {
     let line = serde_json::to_string(&payload).map_err(|err| err.to_string())?;
     let mut writer = self.writer.lock().await;
@@ -489,7 +1041,8 @@ impl SidecarSession {
 }
 
 async fn handle_payload(
-  session: &SidecarSession,
+  process: &Arc<SidecarProcess>,
+  sessions_map: &SessionMap,
   payload: &Value,
   app_handle: &AppHandle,
   db: &Database,
@@ -499,23 +1052,47 @@ async fn handle_payload(
     if payload.get("id").is_some() {
       let request: JsonRpcRequest = serde_json::from_value(payload.clone())
         .map_err(|err| err.to_string())?;
-      return handle_request(session, request, app_handle, db, pending_frontend).await;
+      // Requests can wait minutes on the user; don't block other sessions' traffic.
+      let process = process.clone();
+      let app_handle = app_handle.clone();
+      let db = db.clone();
+      let pending_frontend = pending_frontend.clone();
+      tokio::spawn(async move {
+        if let Err(err) =
+          handle_request(&process, request, &app_handle, &db, &pending_frontend).await
+        {
+          eprintln!("[sidecar] request handling error: {err}");
+        }
+      });
+      return Ok(());
     }
     let notification: JsonRpcNotification = serde_json::from_value(payload.clone())
       .map_err(|err| err.to_string())?;
-    return handle_notification(session, notification, app_handle, db).await;
+    let session_id = notification
+      .params
+      .as_ref()
+      .and_then(|params| params.get("id"))
+      .and_then(Value::as_str)
+      .unwrap_or_default();
+    let session = sessions_map.lock().await.get(session_id).cloned();
+    let Some(session) = session.filter(|session| session.process.process_id == process.process_id)
+    else {
+      return Err(format!("Notification for unknown session: {session_id}"));
+    };
+    session.touch();
+    return handle_notification(&session, notification, app_handle, db).await;
   }
   if payload.get("id").is_some() {
     let response: JsonRpcResponse = serde_json::from_value(payload.clone())
       .map_err(|err| err.to_string())?;
-    return handle_response(session, response).await;
+    return handle_response(process, response).await;
   }
   Ok(())
 }
 
-async fn handle_response(session: &SidecarSession, response: JsonRpcResponse) -> Result<(), String> {
+async fn handle_response(process: &SidecarProcess, response: JsonRpcResponse) -> Result<(), String> {
   let key = id_to_key(&response.id);
-  let mut pending = session.pending.lock().await;
+  let mut pending = process.pending.lock().await;
   let Some(sender) = pending.remove(&key) else {
     return Ok(());
   };
@@ -560,7 +1137,7 @@ async fn handle_notification(
 }
 
 async fn handle_request(
-  session: &SidecarSession,
+  process: &SidecarProcess,
   request: JsonRpcRequest,
   app_handle: &AppHandle,
   db: &Database,
@@ -581,7 +1158,7 @@ async fn handle_request(
         },
       )
       .await?;
-      session.send_response(request.id, response).await?;
+      process.send_response(request.id, response).await?;
     }
     "exitPlanMode" => {
       let params = request.params.unwrap_or(Value::Null);
@@ -596,17 +1173,17 @@ async fn handle_request(
         },
       )
       .await?;
-      session.send_response(request.id, response).await?;
+      process.send_response(request.id, response).await?;
     }
     "getDiff" => {
       let params = request.params.unwrap_or(Value::Null);
       let payload: GetDiffPayload = serde_json::from_value(params)
         .map_err(|err| err.to_string())?;
       let response = get_diff_response(db, &payload).await?;
-      session.send_response(request.id, response).await?;
+      process.send_response(request.id, response).await?;
     }
     _ => {
-      session
+      process
         .send_error(request.id, "Unknown request method")
         .await?;
     }
@@ -651,6 +1228,81 @@ async fn wait_for_frontend<T: Serialize + Clone>(
   Ok(response)
 }
 
+async fn least_loaded_process(
+  processes: &[Arc<SidecarProcess>],
+) -> Option<(usize, Arc<SidecarProcess>)> {
+  let mut least_loaded: Option<(usize, Arc<SidecarProcess>)> = None;
+  for process in processes {
+    let count = process.session_ids.lock().await.len();
+    if least_loaded.as_ref().is_none_or(|(best, _)| count < *best) {
+      least_loaded = Some((count, process.clone()));
+    }
+  }
+  least_loaded
+}
+
+/// Sessions untouched for `idle_timeout_secs` that are not running a turn.
+async fn idle_sessions(
+  sessions: &[Arc<SidecarSession>],
+  now: u64,
+  idle_timeout_secs: u64,
+) -> Vec<String> {
+  let mut idle = Vec::new();
+  for session in sessions {
+    if now.saturating_sub(session.last_active.load(Ordering::Relaxed)) < idle_timeout_secs {
+      continue;
+    }
+    if session.streaming.lock().await.current_turn_id.is_some() {
+      continue;
+    }
+    idle.push(session.session_id.clone());
+  }
+  idle
+}
+
+/// Remove the processes that have had no sessions for `idle_timeout_secs` from
+/// the pool and return them.
+async fn take_idle_processes(
+  processes: &mut Vec<Arc<SidecarProcess>>,
+  now: u64,
+  idle_timeout_secs: u64,
+) -> Vec<Arc<SidecarProcess>> {
+  let mut idle = Vec::new();
+  let mut kept = Vec::new();
+  for process in processes.drain(..) {
+    let unused = process.session_ids.lock().await.is_empty();
+    let idle_secs = now.saturating_sub(process.last_active.load(Ordering::Relaxed));
+    if unused && idle_secs >= idle_timeout_secs {
+      idle.push(process);
+    } else {
+      kept.push(process);
+    }
+  }
+  *processes = kept;
+  idle
+}
+
+/// Count a restart of `session_id` at `now`; false once it restarted more than
+/// `MAX_SESSION_RESTARTS` times within `SESSION_RESTART_WINDOW_SECS`.
+fn count_restart(
+  recent: &mut HashMap<String, (u32, Instant)>,
+  session_id: &str,
+  now: Instant,
+) -> bool {
+  let entry = recent.entry(session_id.to_string()).or_insert((0, now));
+  if now.saturating_duration_since(entry.1) >= Duration::from_secs(SESSION_RESTART_WINDOW_SECS) {
+    *entry = (0, now);
+  }
+  entry.0 += 1;
+  entry.0 <= MAX_SESSION_RESTARTS
+}
+
+async fn attach_process(process: Arc<SidecarProcess>, session_id: &str) -> Arc<SidecarProcess> {
+  process.session_ids.lock().await.insert(session_id.to_string());
+  process.touch();
+  process
+}
+
 async fn handle_sidecar_message(
   session: &SidecarSession,
   payload: SidecarMessagePayload,
@@ -671,6 +1323,34 @@ async fn handle_sidecar_message(
   let Some(turn_id) = stream_state.current_turn_id else {
     return Ok(());
   };
+  stream_state.received_output = true;
+  if payload.agent_type == "claude"
+    && payload.data.get("type").and_then(Value::as_str) == Some("assistant")
+  {
+    if let Some(uuid) = payload.data.get("uuid").and_then(Value::as_str) {
+      stream_state.agent_message_id = Some(uuid.to_string());
+    }
+  }
+  record_tool_calls(db, &payload, turn_id, stream_state.assistant_message_id.as_deref()).await;
+  if let Some(tokens) = usage::parse_message_context_tokens(&payload.agent_type, &payload.data) {
+    stream_state.last_context_tokens = Some(tokens);
+  }
+  if usage::is_compaction_message(&payload.data) {
+    let _ = sessions::set_session_compacted(db.pool(), &payload.id).await;
+  }
+  if let Some(turn_usage) = usage::parse_turn_usage(&payload.agent_type, &payload.data) {
+    let message_context_tokens = stream_state.last_context_tokens;
+    record_turn_usage(
+      session,
+      &payload,
+      turn_id,
+      turn_usage,
+      message_context_tokens,
+      app_handle,
+      db,
+    )
+    .await;
+  }
 
   let content = if let Some(full) = payload.text.clone() {
     full
@@ -683,9 +1363,11 @@ async fn handle_sidecar_message(
   if payload.text.is_some() {
     stream_state.assistant_content = content.clone();
   }
+  let is_final = payload.is_final.unwrap_or(false);
   if content.trim().is_empty() {
-    if payload.is_final.unwrap_or(false) {
-      mark_session_idle(db, app_handle, &payload.id).await;
+    // Tool-only turns still get their checkpoint.
+    if is_final {
+      finish_turn(session, &mut stream_state, &payload, turn_id, None, app_handle, db).await;
     }
     return Ok(());
   }
@@ -695,39 +1377,13 @@ async fn handle_sidecar_message(
     "raw": payload.data,
     "toolSummary": payload.tool_summary,
   });
-  if payload.is_final.unwrap_or(false) {
-    if let Some(diff_stat) = get_workspace_diff_stat(db, &payload.id).await {
-      if let Some(object) = metadata.as_object_mut() {
-        object.insert("diffStat".to_string(), Value::String(diff_stat));
-      }
-    }
-    if payload.agent_type == "claude" {
-      let diff_payload = GetDiffPayload {
-        session_id: payload.id.clone(),
-        file: None,
-        stat: Some(false),
-      };
-      match get_diff_response(db, &diff_payload).await {
-        Ok(value) => {
-          if let Some(diff) = value.get("diff").and_then(|diff| diff.as_str()) {
-            let trimmed = diff.trim();
-            if !trimmed.is_empty() {
-              let mut stored = trimmed.to_string();
-              if stored.len() > MAX_STORED_DIFF_BYTES {
-                stored = truncate_utf8(trimmed, MAX_STORED_DIFF_BYTES);
-                stored.push_str("\n...[truncated]");
-              }
-              if let Some(object) = metadata.as_object_mut() {
-                object.insert("diff".to_string(), Value::String(stored));       
-              }
-            }
-          }
-        }
-        Err(err) => {
-          eprintln!("[sidecar] diff capture error: {err}");
-        }
-      }
-    }
+  if let (Some(agent_message_id), Some(object)) =
+    (stream_state.agent_message_id.as_ref(), metadata.as_object_mut())
+  {
+    object.insert(
+      "agentMessageId".to_string(),
+      Value::String(agent_message_id.clone()),
+    );
   }
   let metadata_str = metadata.to_string();
 
@@ -750,6 +1406,7 @@ async fn handle_sidecar_message(
         .await
       .map_err(|err| err.to_string())?;
       stream_state.assistant_message_id = Some(new_id.clone());
+      let _ = tool_calls::set_turn_tool_calls_message(db.pool(), &payload.id, turn_id, &new_id).await;
       (new_id, true)
     }
   };
@@ -760,7 +1417,8 @@ async fn handle_sidecar_message(
       .map_err(|err| err.to_string())?;
   }
 
-  let streaming = !payload.is_final.unwrap_or(false);
+  // A final message keeps streaming until `finish_turn` has attached the diff
+  // and checkpoint.
   let _ = app_handle.emit(
     "session-message",
     SessionMessageEvent {
@@ -769,30 +1427,278 @@ async fn handle_sidecar_message(
         id: message_id.clone(),
         role: "assistant".to_string(),
         content: content.clone(),
-        metadata: Some(metadata),
-        streaming,
+        metadata: Some(metadata.clone()),
+        checkpoint_id: None,
+        streaming: true,
       },
     },
   );
 
-  if payload.is_final.unwrap_or(false) {
-    mark_session_idle(db, app_handle, &payload.id).await;
-    stream_state.current_turn_id = None;
+  if is_final {
+    let message = FinalMessage {
+      id: message_id,
+      content,
+      metadata,
+    };
+    finish_turn(session, &mut stream_state, &payload, turn_id, Some(message), app_handle, db).await;
   }
 
   Ok(())
 }
 
+/// Assistant message of a finished turn, completed by `finish_turn`.
+struct FinalMessage {
+  id: String,
+  content: String,
+  metadata: Value,
+}
+
+/// End the current turn and capture its diff and assistant checkpoint in a task,
+/// so a large workspace does not stall the reader shared by the process's other
+/// sessions. The message row is updated and the session marked idle once the
+/// task is done; the session's next query waits for it in `send_query`.
+async fn finish_turn(
+  session: &SidecarSession,
+  stream_state: &mut StreamingState,
+  payload: &SidecarMessagePayload,
+  turn_id: i64,
+  message: Option<FinalMessage>,
+  app_handle: &AppHandle,
+  db: &Database,
+) {
+  stream_state.current_turn_id = None;
+  stream_state.in_flight = None;
+  let finalizing = session.finalizing.clone().lock_owned().await;
+  let process = session.process.clone();
+  let session_id = payload.id.clone();
+  let agent_type = payload.agent_type.clone();
+  let app_handle = app_handle.clone();
+  let db = db.clone();
+  tokio::spawn(async move {
+    let _finalizing = finalizing;
+    let checkpoint_id = create_assistant_checkpoint(&db, &session_id, turn_id, &agent_type).await;
+    if let Some(mut message) = message {
+      capture_turn_diff(&db, &session_id, &agent_type, &mut message.metadata).await;
+      let metadata_str = message.metadata.to_string();
+      if let Err(err) = sessions::update_session_message_content(
+        db.pool(),
+        &message.id,
+        &message.content,
+        Some(&metadata_str),
+      )
+      .await
+      {
+        eprintln!("[sidecar] failed to store final message for {session_id}: {err}");
+      }
+      if let Some(checkpoint_id) = checkpoint_id.as_deref() {
+        if let Err(err) =
+          sessions::set_session_message_checkpoint(db.pool(), &message.id, checkpoint_id).await
+        {
+          eprintln!("[sidecar] failed to link checkpoint for {session_id}: {err}");
+        }
+      }
+      let _ = sessions::index_session_message(db.pool(), &message.id).await;
+      let _ = app_handle.emit(
+        "session-message",
+        SessionMessageEvent {
+          session_id: session_id.clone(),
+          message: UiMessagePayload {
+            id: message.id.clone(),
+            role: "assistant".to_string(),
+            content: message.content.clone(),
+            metadata: Some(message.metadata),
+            checkpoint_id,
+            streaming: false,
+          },
+        },
+      );
+      if turn_id == 1 && agent_type == "claude" {
+        spawn_session_title(process, &session_id, &message.content, &app_handle, &db);
+      }
+    }
+    mark_session_idle(&db, &app_handle, &session_id, "finished").await;
+  });
+}
+
+/// Add the workspace diff stat, and for Claude the full diff, to a final
+/// message's metadata.
+async fn capture_turn_diff(db: &Database, session_id: &str, agent_type: &str, metadata: &mut Value) {
+  if let Some(diff_stat) = get_workspace_diff_stat(db, session_id).await {
+    if let Some(object) = metadata.as_object_mut() {
+      object.insert("diffStat".to_string(), Value::String(diff_stat));
+    }
+  }
+  if agent_type != "claude" {
+    return;
+  }
+  let diff_payload = GetDiffPayload {
+    session_id: session_id.to_string(),
+    file: None,
+    stat: Some(false),
+  };
+  match get_diff_response(db, &diff_payload).await {
+    Ok(value) => {
+      if let Some(diff) = value.get("diff").and_then(|diff| diff.as_str()) {
+        let trimmed = diff.trim();
+        if !trimmed.is_empty() {
+          let mut stored = trimmed.to_string();
+          if stored.len() > MAX_STORED_DIFF_BYTES {
+            stored = truncate_utf8(trimmed, MAX_STORED_DIFF_BYTES);
+            stored.push_str("\n...[truncated]");
+          }
+          if let Some(object) = metadata.as_object_mut() {
+            object.insert("diff".to_string(), Value::String(stored));
+          }
+        }
+      }
+    }
+    Err(err) => {
+      eprintln!("[sidecar] diff capture error: {err}");
+    }
+  }
+}
+
+/// Name a new Claude chat from its first exchange using the configured title
+/// model. Titles the user set in the meantime are left alone.
+fn spawn_session_title(
+  process: Arc<SidecarProcess>,
+  session_id: &str,
+  assistant_content: &str,
+  app_handle: &AppHandle,
+  db: &Database,
+) {
+  let session_id = session_id.to_string();
+  let assistant_content = assistant_content.to_string();
+  let app_handle = app_handle.clone();
+  let db = db.clone();
+  tokio::spawn(async move {
+    let Ok(Some(model)) = settings::get_session_title_model(db.pool()).await else {
+      return;
+    };
+    let user_message = sessions::list_turn_messages(db.pool(), &session_id, 1)
+      .await
+      .ok()
+      .and_then(|messages| messages.into_iter().find(|message| message.role == "user"))
+      .map(|message| message.content)
+      .unwrap_or_default();
+    let params = json!({
+      "type": "generate_title",
+      "id": session_id,
+      "agentType": "claude",
+      "options": {
+        "prompt": titles::build_title_prompt(&user_message, &assistant_content),
+        "model": model,
+      },
+    });
+    let result = match process
+      .request_with_timeout(
+        "generateTitle",
+        params,
+        Duration::from_secs(GENERATE_TITLE_TIMEOUT_SECS),
+      )
+      .await
+    {
+      Ok(result) => result,
+      Err(err) => {
+        eprintln!("[sidecar] generateTitle failed for {session_id}: {err}");
+        return;
+      }
+    };
+    let Some(title) = result
+      .get("title")
+      .and_then(Value::as_str)
+      .and_then(titles::clean_generated_title)
+    else {
+      return;
+    };
+    match sessions::set_generated_session_title(db.pool(), &session_id, &title).await {
+      Ok(Some(updated)) => {
+        let _ = app_handle.emit("session-updated", updated);
+      }
+      Ok(None) => {}
+      Err(err) => eprintln!("[sidecar] failed to store title for {session_id}: {err}"),
+    }
+  });
+}
+
+/// Snapshot the workspace after the agent finishes a turn so the session can be
+/// moved forward again after a reset. Failures are logged and never block the turn.
+async fn create_assistant_checkpoint(
+  db: &Database,
+  session_id: &str,
+  turn_id: i64,
+  agent_type: &str,
+) -> Option<String> {
+  let session = sessions::get_session(db.pool(), session_id).await.ok()?;
+  let workspace_record = workspace::get_workspace(db.pool(), &session.workspace_id)
+    .await
+    .ok()?;
+  let workspace_path = PathBuf::from(workspace_record.path);
+  let checkpoint_id = format!("session-{session_id}-turn-{turn_id}-assistant");
+  let agent_type = agent_type.to_string();
+  let result = {
+    let checkpoint_id = checkpoint_id.clone();
+    tokio::task::spawn_blocking(move || {
+      create_agent_checkpoint(&workspace_path, &checkpoint_id, &agent_type)
+    })
+    .await
+  };
+  match result {
+    Ok(Ok(CheckpointOutcome::Created)) => {
+      if let Err(err) =
+        sessions::record_turn_checkpoint(db.pool(), session_id, turn_id, &checkpoint_id).await
+      {
+        eprintln!("[checkpoint] failed to record turn checkpoint for session {session_id} turn {turn_id}: {err}");
+      }
+      Some(checkpoint_id)
+    }
+    Ok(Ok(CheckpointOutcome::Skipped { reason })) => {
+      eprintln!("[checkpoint] assistant checkpoint skipped for session {session_id} turn {turn_id}: {reason}");
+      None
+    }
+    Ok(Err(err)) => {
+      eprintln!("[checkpoint] assistant checkpoint failed for session {session_id} turn {turn_id}: {err}");
+      None
+    }
+    Err(err) => {
+      eprintln!("[checkpoint] assistant checkpoint task failed for session {session_id} turn {turn_id}: {err}");
+      None
+    }
+  }
+}
+
 async fn handle_sidecar_error(
   session: &SidecarSession,
   payload: SidecarErrorPayload,
   app_handle: &AppHandle,
   db: &Database,
 ) -> Result<(), String> {
-  let turn_id = {
-    let state = session.streaming.lock().await;
-    state.current_turn_id.unwrap_or(-1)
+  let (turn_id, partial_message_id) = {
+    let mut state = session.streaming.lock().await;
+    state.in_flight = None;
+    let partial_message_id = state.assistant_message_id.clone();
+    // The turn is over either way; leaving it set keeps the session from being
+    // evicted and lets a later cancel mark a finished turn as cancelled.
+    let turn_id = match (state.current_turn_id.take(), state.cancelled_turn_id) {
+      (Some(turn_id), _) => turn_id,
+      // Abort replies to `cancel`; the turn is already recorded as cancelled.
+      (None, Some(_)) => {
+        drop(state);
+        mark_session_idle(db, app_handle, &payload.id, "cancelled").await;
+        return Ok(());
+      }
+      (None, None) => -1,
+    };
+    (turn_id, partial_message_id)
   };
+  if turn_id >= 0 {
+    let _ =
+      tool_calls::finish_running_tool_calls(db.pool(), &payload.id, turn_id, "error").await;
+    // The reply stops here, so index what streamed of it.
+    if let Some(message_id) = partial_message_id.as_deref() {
+      let _ = sessions::index_session_message(db.pool(), message_id).await;
+    }
+  }
   let message_id = sessions::generate_message_id(db.pool())
     .await
     .map_err(|err| err.to_string())?;
@@ -824,17 +1730,133 @@ async fn handle_sidecar_error(
     },
   );
 
-  mark_session_idle(db, app_handle, &payload.id).await;
+  mark_session_idle(db, app_handle, &payload.id, "error").await;
   Ok(())
 }
 
-async fn mark_session_idle(db: &Database, app_handle: &AppHandle, session_id: &str) {
+async fn record_tool_calls(
+  db: &Database,
+  payload: &SidecarMessagePayload,
+  turn_id: i64,
+  message_id: Option<&str>,
+) {
+  for update in tool_calls::parse_tool_call_updates(&payload.agent_type, &payload.data) {
+    if let Err(err) =
+      tool_calls::upsert_tool_call(db.pool(), &payload.id, turn_id, message_id, &update).await
+    {
+      eprintln!("[sidecar] failed to record tool call {}: {err}", update.call_id);
+    }
+  }
+}
+
+/// Store the turn's token usage and refresh the session's context size, asking
+/// Claude for its `/context` numbers and falling back to the last message's usage,
+/// then the turn's tokens.
+async fn record_turn_usage(
+  session: &SidecarSession,
+  payload: &SidecarMessagePayload,
+  turn_id: i64,
+  mut turn_usage: usage::TurnUsage,
+  message_context_tokens: Option<i64>,
+  app_handle: &AppHandle,
+  db: &Database,
+) {
+  let Ok(record) = sessions::get_session(db.pool(), &payload.id).await else {
+    return;
+  };
+  let Ok(workspace_record) = workspace::get_workspace(db.pool(), &record.workspace_id).await else {
+    return;
+  };
+  if turn_usage.model.is_none() {
+    turn_usage.model = record.model.clone();
+  }
+  if let Err(err) = usage::record_turn_usage(
+    db.pool(),
+    usage::NewTurnUsage {
+      session_id: &record.id,
+      turn_id,
+      workspace_id: &workspace_record.id,
+      repo_id: &workspace_record.repo_id,
+      agent_type: &payload.agent_type,
+      usage: &turn_usage,
+    },
+  )
+  .await
+  {
+    eprintln!("[sidecar] failed to record usage for turn {turn_id}: {err}");
+  }
+
+  let claude_session_id = payload
+    .agent_session_id
+    .clone()
+    .or(record.claude_session_id.clone())
+    .filter(|_| payload.agent_type == "claude");
+  let process = session.process.clone();
+  let app_handle = app_handle.clone();
+  let db = db.clone();
+  let session_id = record.id.clone();
+  let fallback = message_context_tokens.unwrap_or_else(|| turn_usage.context_tokens());
+  // Taken before the turn is marked idle, so a queued message dispatched right
+  // after waits in `send_query` until `/context` is done with the session.
+  let refresh = session.context_refresh.clone().lock_owned().await;
+  tokio::spawn(async move {
+    let _refresh = refresh;
+    let mut context_tokens = None;
+    if let Some(claude_session_id) = claude_session_id {
+      let params = json!({
+        "type": "context_usage",
+        "id": session_id,
+        "agentType": "claude",
+        "options": { "cwd": workspace_record.path, "claudeSessionId": claude_session_id },
+      });
+      match process
+        .request_with_timeout(
+          "contextUsage",
+          params,
+          Duration::from_secs(CONTEXT_USAGE_TIMEOUT_SECS),
+        )
+        .await
+      {
+        Ok(result) => {
+          let mut text = String::new();
+          usage::collect_text(result.get("contextUsageData").unwrap_or(&result), &mut text);
+          context_tokens = usage::parse_context_tokens(&text);
+        }
+        Err(err) => eprintln!("[sidecar] contextUsage failed for {session_id}: {err}"),
+      }
+    }
+    let context_tokens = context_tokens.unwrap_or(fallback);
+    if sessions::set_session_context_token_count(db.pool(), &session_id, context_tokens)
+      .await
+      .is_err()
+    {
+      return;
+    }
+    let is_compacted = sessions::get_session(db.pool(), &session_id)
+      .await
+      .map(|record| record.is_compacted)
+      .unwrap_or(false);
+    let _ = app_handle.emit(
+      "session-context-usage",
+      SessionContextUsageEvent {
+        session_id,
+        context_token_count: context_tokens,
+        is_compacted,
+      },
+    );
+  });
+}
+
+/// Only a `finished` turn lets the next queued message go out; after a cancel or
+/// an error the queue waits for the user.
+async fn mark_session_idle(db: &Database, app_handle: &AppHandle, session_id: &str, reason: &str) {
   let _ = sessions::set_session_status(db.pool(), session_id, "idle").await;
   let _ = app_handle.emit(
     "session-status",
     SessionStatusEvent {
       session_id: session_id.to_string(),
       status: "idle".to_string(),
+      reason: reason.to_string(),
     },
   );
 }
@@ -907,6 +1929,13 @@ async fn get_workspace_diff_stat(db: &Database, session_id: &str) -> Option<Stri
   }
 }
 
+fn unix_now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs())
+    .unwrap_or(0)
+}
+
 fn id_to_key(id: &Value) -> String {
   match id {
     Value::String(value) => value.clone(),
@@ -1056,3 +2085,151 @@ async fn connect_socket(
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    count_restart, idle_sessions, least_loaded_process, take_idle_processes, InFlightQuery,
+    InterruptedTurn, SidecarProcess, SidecarSession, StreamingState, MAX_SESSION_RESTARTS,
+    SESSION_RESTART_WINDOW_SECS,
+  };
+  use serde_json::json;
+  use std::collections::HashMap;
+  use std::sync::atomic::{AtomicBool, AtomicU64};
+  use std::sync::Arc;
+  use std::time::{Duration, Instant};
+  use tokio::sync::Mutex;
+
+  /// Pooled process with no child or socket behind it.
+  fn test_process(process_id: u64, session_ids: &[&str], last_active: u64) -> Arc<SidecarProcess> {
+    Arc::new(SidecarProcess {
+      process_id,
+      pid: 0,
+      writer: Arc::new(Mutex::new(Box::new(tokio::io::sink()))),
+      pending: Arc::new(Mutex::new(HashMap::new())),
+      next_request_id: AtomicU64::new(1),
+      session_ids: Mutex::new(session_ids.iter().map(|id| id.to_string()).collect()),
+      last_active: AtomicU64::new(last_active),
+      child: Arc::new(Mutex::new(None)),
+      closing: Arc::new(AtomicBool::new(false)),
+      reader_task: Arc::new(Mutex::new(None)),
+    })
+  }
+
+  fn test_session(
+    session_id: &str,
+    process: &Arc<SidecarProcess>,
+    last_active: u64,
+    current_turn_id: Option<i64>,
+  ) -> Arc<SidecarSession> {
+    Arc::new(SidecarSession {
+      session_id: session_id.to_string(),
+      process: process.clone(),
+      streaming: Arc::new(Mutex::new(StreamingState {
+        current_turn_id,
+        ..StreamingState::default()
+      })),
+      context_refresh: Arc::new(Mutex::new(())),
+      finalizing: Arc::new(Mutex::new(())),
+      last_active: AtomicU64::new(last_active),
+    })
+  }
+
+  #[tokio::test]
+  async fn picks_the_process_with_fewest_sessions() {
+    assert!(least_loaded_process(&[]).await.is_none());
+    let processes = vec![
+      test_process(1, &["a", "b"], 0),
+      test_process(2, &["c"], 0),
+      test_process(3, &["d"], 0),
+    ];
+    let (count, process) = least_loaded_process(&processes).await.unwrap();
+    assert_eq!(count, 1);
+    // Ties go to the earliest process in the pool.
+    assert_eq!(process.process_id, 2);
+  }
+
+  #[tokio::test]
+  async fn evicts_only_idle_sessions_outside_a_turn() {
+    let process = test_process(1, &["idle", "busy", "recent"], 0);
+    let sessions = vec![
+      test_session("idle", &process, 100, None),
+      test_session("busy", &process, 100, Some(3)),
+      test_session("recent", &process, 950, None),
+    ];
+    assert_eq!(idle_sessions(&sessions, 1000, 600).await, vec!["idle".to_string()]);
+  }
+
+  #[tokio::test]
+  async fn stops_processes_without_sessions_once_idle() {
+    let mut processes = vec![
+      test_process(1, &[], 100),
+      test_process(2, &["a"], 100),
+      test_process(3, &[], 950),
+    ];
+    let stopped = take_idle_processes(&mut processes, 1000, 600).await;
+    assert_eq!(stopped.iter().map(|process| process.process_id).collect::<Vec<_>>(), vec![1]);
+    assert_eq!(processes.iter().map(|process| process.process_id).collect::<Vec<_>>(), vec![2, 3]);
+  }
+
+  #[test]
+  fn limits_restarts_within_the_window() {
+    let mut recent = HashMap::new();
+    let start = Instant::now();
+    for _ in 0..MAX_SESSION_RESTARTS {
+      assert!(count_restart(&mut recent, "s1", start));
+    }
+    assert!(!count_restart(&mut recent, "s1", start + Duration::from_secs(1)));
+    // Other sessions keep their own count.
+    assert!(count_restart(&mut recent, "s2", start));
+    // The count starts over once the window has passed.
+    let later = start + Duration::from_secs(SESSION_RESTART_WINDOW_SECS);
+    assert!(count_restart(&mut recent, "s1", later));
+    assert_eq!(recent["s1"].0, 1);
+  }
+
+  fn running_turn(turn_id: i64, received_output: bool) -> StreamingState {
+    StreamingState {
+      current_turn_id: Some(turn_id),
+      in_flight: Some(InFlightQuery {
+        agent_type: "claude".to_string(),
+        prompt: "hi".to_string(),
+        options: json!({ "turnId": turn_id }),
+      }),
+      received_output,
+      ..StreamingState::default()
+    }
+  }
+
+  #[test]
+  fn resends_a_turn_that_produced_no_output() {
+    let mut state = running_turn(2, false);
+    let InterruptedTurn::Resend(query, turn_id) = state.take_interrupted_turn() else {
+      panic!("expected the turn to be re-sent");
+    };
+    assert_eq!((query.prompt.as_str(), turn_id), ("hi", 2));
+    // The turn stays current so the resumed query streams into it.
+    assert_eq!(state.current_turn_id, Some(2));
+    assert!(state.in_flight.is_some());
+  }
+
+  #[test]
+  fn ends_a_turn_once_output_was_received() {
+    let mut state = running_turn(2, true);
+    assert!(matches!(state.take_interrupted_turn(), InterruptedTurn::End(2)));
+    assert_eq!(state.current_turn_id, None);
+    assert!(state.in_flight.is_none());
+  }
+
+  #[test]
+  fn ignores_sessions_without_a_running_turn() {
+    let mut state = StreamingState::default();
+    assert!(matches!(state.take_interrupted_turn(), InterruptedTurn::None));
+    // A turn that was not sent through `send_query` has nothing to re-send.
+    let mut state = StreamingState {
+      current_turn_id: Some(1),
+      ..StreamingState::default()
+    };
+    assert!(matches!(state.take_interrupted_turn(), InterruptedTurn::None));
+  }
+}
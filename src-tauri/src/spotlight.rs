@@ -1,28 +1,131 @@
-use notify::{recommended_watcher, Event, RecursiveMode, Watcher};
-use std::collections::HashMap;
+use notify::{recommended_watcher, Event, EventKind, RecursiveMode, Watcher};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::{
   atomic::{AtomicBool, Ordering},
   mpsc, Arc, Mutex,
 };
 use std::thread::{self, JoinHandle};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
 
-use crate::checkpoints::{create_checkpoint, delete_checkpoint, restore_checkpoint, CheckpointOutcome};
+use crate::checkpoints::{
+  changed_paths, create_checkpoint, create_checkpoint_excluding, current_worktree_tree,
+  delete_checkpoint, hash_worktree_paths, ignored_paths, restore_checkpoint,
+  restore_checkpoint_excluding, tree_blob_ids, CheckpointOutcome,
+};
+use crate::db::DbError;
+use crate::git::{global_excludes_file, read_spotlight_ignore};
+
+/// How often a full checkpoint sync runs after incremental copies, to pick up
+/// anything the watcher missed.
+const FULL_SYNC_INTERVAL: Duration = Duration::from_secs(120);
+/// Past this many pending paths a full sync is cheaper than copying one by one.
+const MAX_INCREMENTAL_PATHS: usize = 500;
+/// Lower bound for the configured debounce so the worker never spins.
+const MIN_DEBOUNCE: Duration = Duration::from_millis(50);
 
 pub struct SpotlightManager {
   instances: Arc<Mutex<HashMap<String, SpotlightInstance>>>,
 }
 
+/// Snapshot of a spotlight instance for the UI. Times are Unix seconds.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotlightStatus {
+  pub active: bool,
+  pub paused: bool,
+  pub active_since: Option<u64>,
+  pub last_sync_at: Option<u64>,
+  pub last_sync_duration_ms: Option<u64>,
+  /// `"incremental"` or `"full"`.
+  pub last_sync_mode: Option<String>,
+  pub files_synced: usize,
+  pub sync_count: u64,
+  pub last_error: Option<String>,
+  pub last_skip_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpotlightSyncEvent {
+  workspace_id: String,
+  outcome: String,
+  status: SpotlightStatus,
+}
+
+/// Persisted spotlight instance, used to recover the repo root after a crash.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotlightRecord {
+  pub workspace_id: String,
+  pub repo_root: String,
+  pub workspace_path: String,
+  pub rollback_checkpoint_id: String,
+  pub sync_checkpoint_id: String,
+}
+
 struct SpotlightInstance {
+  workspace_path: PathBuf,
   repo_root: PathBuf,
   rollback_checkpoint_id: String,
   sync_checkpoint_id: String,
   stop_flag: Arc<AtomicBool>,
-  trigger_tx: mpsc::Sender<()>,
+  trigger_tx: mpsc::Sender<SyncTrigger>,
+  guard: Arc<Mutex<SyncGuard>>,
+  status: Arc<Mutex<SpotlightStatus>>,
+  excludes_file: PathBuf,
   join: Option<JoinHandle<()>>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SyncTrigger {
+  /// Workspace paths reported by the watcher; empty means the watcher lost track.
+  Workspace(Vec<PathBuf>),
+  /// Rebuild the repo root from a full checkpoint of the workspace.
+  Full,
+  /// Repo root paths reported by the watcher; empty means the watcher lost track.
+  Root(Vec<PathBuf>),
+}
+
+/// Root-side state shared by the sync worker and conflict resolution.
+#[derive(Default)]
+struct SyncGuard {
+  /// Set when the repo root was edited outside spotlight; syncing waits for the user.
+  paused: bool,
+  /// Tree of the repo root right after the last full sync.
+  root_tree: Option<String>,
+  /// Blob ids (`None` when deleted) of root paths written by incremental syncs
+  /// since `root_tree` was recorded.
+  root_paths: HashMap<String, Option<String>>,
+}
+
+struct SyncContext {
+  app_handle: AppHandle,
+  workspace_id: String,
+  workspace_path: PathBuf,
+  repo_root: PathBuf,
+  sync_checkpoint_id: String,
+  /// Quiet period to wait for after a change before syncing.
+  debounce: Duration,
+  /// Ignore file layering `spotlightIgnore` over the user's global excludes; both
+  /// sync modes and root conflict checks use it alongside `.gitignore`.
+  excludes_file: PathBuf,
+  guard: Arc<Mutex<SyncGuard>>,
+  status: Arc<Mutex<SpotlightStatus>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpotlightConflictEvent {
+  workspace_id: String,
+  changed_paths: Vec<String>,
+}
+
 impl Default for SpotlightManager {
   fn default() -> Self {
     Self {
@@ -32,29 +135,75 @@ impl Default for SpotlightManager {
 }
 
 impl SpotlightManager {
-  pub fn is_active(&self, workspace_id: &str) -> bool {
-    self
-      .instances
-      .lock()
-      .map(|map| map.contains_key(workspace_id))
-      .unwrap_or(false)
+  pub fn status(&self, workspace_id: &str) -> SpotlightStatus {
+    let (guard, status) = {
+      let Ok(map) = self.instances.lock() else {
+        return SpotlightStatus::default();
+      };
+      let Some(instance) = map.get(workspace_id) else {
+        return SpotlightStatus::default();
+      };
+      (instance.guard.clone(), instance.status.clone())
+    };
+    let mut snapshot = status.lock().map(|value| value.clone()).unwrap_or_default();
+    snapshot.paused = guard.lock().map(|value| value.paused).unwrap_or(false);
+    snapshot
   }
 
   pub fn enable(
     &self,
+    app_handle: AppHandle,
     workspace_id: &str,
     workspace_path: PathBuf,
     repo_root: PathBuf,
+    debounce: Duration,
+  ) -> Result<SpotlightRecord, String> {
+    self.start(app_handle, workspace_id, workspace_path, repo_root, debounce, None)
+  }
+
+  /// Restart a spotlight left behind by a crash, keeping its original rollback
+  /// checkpoint (the repo root currently holds synced workspace content).
+  pub fn resume(
+    &self,
+    app_handle: AppHandle,
+    record: &SpotlightRecord,
+    debounce: Duration,
   ) -> Result<(), String> {
+    self
+      .start(
+        app_handle,
+        &record.workspace_id,
+        PathBuf::from(&record.workspace_path),
+        PathBuf::from(&record.repo_root),
+        debounce,
+        Some(record.rollback_checkpoint_id.clone()),
+      )
+      .map(|_| ())
+  }
+
+  fn start(
+    &self,
+    app_handle: AppHandle,
+    workspace_id: &str,
+    workspace_path: PathBuf,
+    repo_root: PathBuf,
+    debounce: Duration,
+    existing_rollback: Option<String>,
+  ) -> Result<SpotlightRecord, String> {
     if workspace_path == repo_root {
       return Err("Spotlight requires a separate worktree path".to_string());
     }
+    // Same as checkpoint patterns: a broken `supertree.json` is logged, not fatal.
+    let patterns = read_spotlight_ignore(&workspace_path).unwrap_or_else(|err| {
+      eprintln!("[spotlight] ignoring spotlight ignore patterns: {err}");
+      Vec::new()
+    });
     let mut map = self
       .instances
       .lock()
       .map_err(|_| "Spotlight state locked".to_string())?;
-    if map.contains_key(workspace_id) {
-      return Ok(());
+    if let Some(instance) = map.get(workspace_id) {
+      return Ok(instance.record(workspace_id));
     }
     if map.values().any(|item| item.repo_root == repo_root) {
       return Err("Spotlight already active for this repository".to_string());
@@ -64,30 +213,56 @@ impl SpotlightManager {
       .duration_since(UNIX_EPOCH)
       .map_err(|err| format!("Failed to read time: {err}"))?
       .as_secs();
-    let rollback_checkpoint_id = format!("spotlight-rollback-{}-{}", workspace_id, stamp);
-    match create_checkpoint(&repo_root, &rollback_checkpoint_id) {
-      Ok(CheckpointOutcome::Created) => {}
-      Ok(CheckpointOutcome::Skipped { reason }) => {
-        return Err(format!("Spotlight cannot start: {reason}"));
-      }
-      Err(err) => {
-        return Err(format!("Spotlight failed to create rollback checkpoint: {err}"));
+    let created_rollback = existing_rollback.is_none();
+    let rollback_checkpoint_id = match existing_rollback {
+      Some(rollback_checkpoint_id) => rollback_checkpoint_id,
+      None => {
+        let rollback_checkpoint_id = format!("spotlight-rollback-{}-{}", workspace_id, stamp);
+        match create_checkpoint(&repo_root, &rollback_checkpoint_id) {
+          Ok(CheckpointOutcome::Created) => {}
+          Ok(CheckpointOutcome::Skipped { reason }) => {
+            return Err(format!("Spotlight cannot start: {reason}"));
+          }
+          Err(err) => {
+            return Err(format!("Spotlight failed to create rollback checkpoint: {err}"));
+          }
+        }
+        rollback_checkpoint_id
       }
-    }
+    };
 
+    let excludes_file = std::env::temp_dir().join(format!("supertree-spotlight-{workspace_id}.exclude"));
+    write_excludes_file(&excludes_file, &workspace_path, &patterns)
+      .map_err(|err| format!("Failed to write spotlight ignore file: {err}"))?;
     let sync_checkpoint_id = format!("spotlight-sync-{}", workspace_id);
-    let (trigger_tx, trigger_rx) = mpsc::channel::<()>();
+    let (trigger_tx, trigger_rx) = mpsc::channel::<SyncTrigger>();
     let (init_tx, init_rx) = mpsc::channel::<Result<(), String>>();
     let stop_flag = Arc::new(AtomicBool::new(false));
     let stop_flag_worker = stop_flag.clone();
     let stop_flag_watcher = stop_flag.clone();
-    let workspace_clone = workspace_path.clone();
-    let repo_clone = repo_root.clone();
-    let sync_id_clone = sync_checkpoint_id.clone();
+    let stop_flag_root_watcher = stop_flag.clone();
+    let guard = Arc::new(Mutex::new(SyncGuard::default()));
+    let status = Arc::new(Mutex::new(SpotlightStatus {
+      active: true,
+      active_since: Some(stamp),
+      ..SpotlightStatus::default()
+    }));
+    let context = SyncContext {
+      app_handle,
+      workspace_id: workspace_id.to_string(),
+      workspace_path: workspace_path.clone(),
+      repo_root: repo_root.clone(),
+      sync_checkpoint_id: sync_checkpoint_id.clone(),
+      debounce: debounce.max(MIN_DEBOUNCE),
+      excludes_file: excludes_file.clone(),
+      guard: guard.clone(),
+      status: status.clone(),
+    };
 
     let trigger_tx_worker = trigger_tx.clone();
     let join = thread::spawn(move || {
       let trigger_tx_watcher = trigger_tx_worker.clone();
+      let watched_workspace = context.workspace_path.clone();
       let mut watcher = match recommended_watcher(move |res: Result<Event, notify::Error>| {
         if stop_flag_watcher.load(Ordering::Relaxed) {
           return;
@@ -96,7 +271,16 @@ impl SpotlightManager {
           if should_ignore_event(&event) {
             return;
           }
-          let _ = trigger_tx_watcher.send(());
+          let paths = if event.need_rescan() {
+            Vec::new()
+          } else {
+            event
+              .paths
+              .into_iter()
+              .filter(|path| path.starts_with(&watched_workspace) && !should_ignore_path(path))
+              .collect()
+          };
+          let _ = trigger_tx_watcher.send(SyncTrigger::Workspace(paths));
         }
       }) {
         Ok(watcher) => watcher,
@@ -106,29 +290,137 @@ impl SpotlightManager {
         }
       };
 
-      if let Err(err) = watcher.watch(&workspace_clone, RecursiveMode::Recursive) {
+      if let Err(err) = watcher.watch(&context.workspace_path, RecursiveMode::Recursive) {
         let _ = init_tx.send(Err(format!("Spotlight failed to watch workspace: {err}")));
         return;
       }
 
+      // Second watcher on the repo root to notice edits made outside spotlight.
+      let trigger_tx_root = trigger_tx_worker.clone();
+      let workspace_inside_root = context.workspace_path.clone();
+      let mut root_watcher = match recommended_watcher(move |res: Result<Event, notify::Error>| {
+        if stop_flag_root_watcher.load(Ordering::Relaxed) {
+          return;
+        }
+        if let Ok(event) = res {
+          if should_ignore_event(&event)
+            || event
+              .paths
+              .iter()
+              .all(|path| path.starts_with(&workspace_inside_root))
+          {
+            return;
+          }
+          let paths = if event.need_rescan() {
+            Vec::new()
+          } else {
+            event
+              .paths
+              .into_iter()
+              .filter(|path| {
+                !path.starts_with(&workspace_inside_root) && !should_ignore_path(path)
+              })
+              .collect()
+          };
+          let _ = trigger_tx_root.send(SyncTrigger::Root(paths));
+        }
+      }) {
+        Ok(watcher) => watcher,
+        Err(err) => {
+          let _ = init_tx.send(Err(format!("Spotlight watcher error: {err}")));
+          return;
+        }
+      };
+      if let Err(err) = root_watcher.watch(&context.repo_root, RecursiveMode::Recursive) {
+        let _ = init_tx.send(Err(format!("Spotlight failed to watch repository root: {err}")));
+        return;
+      }
+
       let _ = init_tx.send(Ok(()));
-      let _ = trigger_tx_worker.send(());
 
+      let mut full_pending = true;
+      let mut pending: HashSet<PathBuf> = HashSet::new();
+      let mut root_rescan = false;
+      let mut root_pending: HashSet<PathBuf> = HashSet::new();
+      let mut incremental_since_full = false;
+      let mut last_full = Instant::now();
       loop {
         if stop_flag_worker.load(Ordering::Relaxed) {
           break;
         }
-        match trigger_rx.recv_timeout(Duration::from_millis(500)) {
-          Ok(()) => {
-            while trigger_rx.try_recv().is_ok() {}
-            if stop_flag_worker.load(Ordering::Relaxed) {
-              break;
+        match trigger_rx.recv_timeout(context.debounce) {
+          Ok(trigger) => {
+            // Wait for changes to settle, but not forever under constant writes.
+            let settle_deadline = Instant::now() + context.debounce * 10;
+            let mut triggers = vec![trigger];
+            while Instant::now() < settle_deadline && !stop_flag_worker.load(Ordering::Relaxed) {
+              match trigger_rx.recv_timeout(context.debounce) {
+                Ok(next) => triggers.push(next),
+                Err(_) => break,
+              }
+            }
+            for trigger in triggers {
+              match trigger {
+                SyncTrigger::Workspace(paths) if paths.is_empty() => full_pending = true,
+                SyncTrigger::Workspace(paths) => pending.extend(paths),
+                SyncTrigger::Full => full_pending = true,
+                SyncTrigger::Root(paths) if paths.is_empty() => root_rescan = true,
+                SyncTrigger::Root(paths) => root_pending.extend(paths),
+              }
+            }
+          }
+          Err(mpsc::RecvTimeoutError::Timeout) => {
+            if incremental_since_full && last_full.elapsed() >= FULL_SYNC_INTERVAL {
+              full_pending = true;
             }
-            run_sync(&workspace_clone, &repo_clone, &sync_id_clone);
           }
-          Err(mpsc::RecvTimeoutError::Timeout) => {}
           Err(mpsc::RecvTimeoutError::Disconnected) => break,
         }
+        if stop_flag_worker.load(Ordering::Relaxed) {
+          break;
+        }
+        // Too many paths for an incremental sync: a full sync is cheaper than
+        // asking git about each of them first.
+        if pending.len() > MAX_INCREMENTAL_PATHS {
+          full_pending = true;
+        }
+        if !full_pending {
+          drop_ignored(&context, &mut pending);
+        }
+        if !root_rescan && root_pending.is_empty() && !full_pending && pending.is_empty() {
+          continue;
+        }
+        let incremental = !full_pending && !pending.is_empty();
+        // Hashing the whole root is left to full syncs; otherwise only the root
+        // paths the watcher reported are compared with what we last wrote.
+        let conflict = if root_rescan
+          || full_pending
+          || root_pending.len() > MAX_INCREMENTAL_PATHS
+        {
+          detect_root_conflict(&context)
+        } else {
+          detect_root_path_conflict(&context, &root_pending)
+        };
+        root_rescan = false;
+        root_pending.clear();
+        if conflict {
+          continue;
+        }
+        if incremental {
+          let paths: Vec<PathBuf> = pending.drain().collect();
+          if run_incremental_sync(&context, &paths) {
+            incremental_since_full = true;
+            continue;
+          }
+          full_pending = true;
+        }
+        if full_pending || !pending.is_empty() {
+          run_sync(&context);
+          full_pending = false;
+          pending.clear();
+          incremental_since_full = false;
+          last_full = Instant::now();
+        }
       }
     });
 
@@ -137,23 +429,31 @@ impl SpotlightManager {
       .map_err(|_| "Spotlight failed to initialize watcher".to_string())?;
     if let Err(err) = init_result {
       stop_flag.store(true, Ordering::Relaxed);
-      let _ = trigger_tx.send(());
+      let _ = trigger_tx.send(SyncTrigger::Full);
       let _ = join.join();
-      let _ = delete_checkpoint(&repo_root, &rollback_checkpoint_id);
+      let _ = fs::remove_file(&excludes_file);
+      if created_rollback {
+        let _ = delete_checkpoint(&repo_root, &rollback_checkpoint_id);
+      }
       return Err(err);
     }
 
     let instance = SpotlightInstance {
+      workspace_path,
       repo_root,
       rollback_checkpoint_id,
       sync_checkpoint_id,
       stop_flag,
       trigger_tx,
+      guard,
+      status,
+      excludes_file,
       join: Some(join),
     };
 
+    let record = instance.record(workspace_id);
     map.insert(workspace_id.to_string(), instance);
-    Ok(())
+    Ok(record)
   }
 
   pub fn disable(&self, workspace_id: &str) -> Result<(), String> {
@@ -168,46 +468,557 @@ impl SpotlightManager {
       return Ok(());
     };
     instance.stop_flag.store(true, Ordering::Relaxed);
-    let _ = instance.trigger_tx.send(());
+    let _ = instance.trigger_tx.send(SyncTrigger::Full);
     if let Some(join) = instance.join.take() {
       let _ = join.join();
     }
+    let _ = fs::remove_file(&instance.excludes_file);
 
-    restore_checkpoint(&instance.repo_root, &instance.rollback_checkpoint_id)
-      .map_err(|err| format!("Failed to restore rollback checkpoint: {err}"))?;
+    roll_back(
+      &instance.repo_root,
+      &instance.rollback_checkpoint_id,
+      &instance.sync_checkpoint_id,
+    )
+  }
 
-    let mut cleanup_errors: Vec<String> = Vec::new();
-    if let Err(err) = delete_checkpoint(&instance.repo_root, &instance.rollback_checkpoint_id) {
-      cleanup_errors.push(format!("Rollback checkpoint cleanup failed: {err}"));
-    }
-    if let Err(err) = delete_checkpoint(&instance.repo_root, &instance.sync_checkpoint_id) {
-      cleanup_errors.push(format!("Spotlight checkpoint cleanup failed: {err}"));
+  /// Stop every instance and roll its repo root back. Used on clean shutdown;
+  /// returns the workspace ids that were rolled back successfully.
+  pub fn disable_all(&self) -> Vec<String> {
+    let workspace_ids: Vec<String> = self
+      .instances
+      .lock()
+      .map(|map| map.keys().cloned().collect())
+      .unwrap_or_default();
+    workspace_ids
+      .into_iter()
+      .filter(|workspace_id| match self.disable(workspace_id) {
+        Ok(()) => true,
+        Err(err) => {
+          eprintln!("[spotlight] shutdown rollback failed for {workspace_id}: {err}");
+          false
+        }
+      })
+      .collect()
+  }
+
+  /// Resume a paused spotlight after the repo root was edited externally.
+  /// `keep_root` copies the root's state into the workspace first; otherwise the
+  /// workspace wins and the next sync overwrites the root edits.
+  pub fn resolve_conflict(&self, workspace_id: &str, keep_root: bool) -> Result<(), String> {
+    let (workspace_path, repo_root, excludes_file, trigger_tx, guard) = {
+      let map = self
+        .instances
+        .lock()
+        .map_err(|_| "Spotlight state locked".to_string())?;
+      let instance = map
+        .get(workspace_id)
+        .ok_or_else(|| "Spotlight is not active for this workspace".to_string())?;
+      (
+        instance.workspace_path.clone(),
+        instance.repo_root.clone(),
+        instance.excludes_file.clone(),
+        instance.trigger_tx.clone(),
+        instance.guard.clone(),
+      )
+    };
+    let mut state = guard
+      .lock()
+      .map_err(|_| "Spotlight state locked".to_string())?;
+    if !state.paused {
+      return Err("No spotlight conflict to resolve".to_string());
     }
-    if !cleanup_errors.is_empty() {
-      return Err(cleanup_errors.join(" | "));
+    if keep_root {
+      let root_checkpoint_id = format!("spotlight-root-{workspace_id}");
+      match create_checkpoint_excluding(&repo_root, &root_checkpoint_id, &excludes_file) {
+        Ok(CheckpointOutcome::Created) => {}
+        Ok(CheckpointOutcome::Skipped { reason }) => {
+          return Err(format!("Cannot copy repository root: {reason}"));
+        }
+        Err(err) => return Err(format!("Failed to snapshot repository root: {err}")),
+      }
+      let restored = restore_checkpoint_excluding(&workspace_path, &root_checkpoint_id, &excludes_file)
+        .map_err(|err| format!("Failed to apply repository root to workspace: {err}"));
+      if let Err(err) = delete_checkpoint(&repo_root, &root_checkpoint_id) {
+        eprintln!("[spotlight] root checkpoint cleanup failed: {err}");
+      }
+      restored?;
     }
+    state.root_tree = Some(
+      current_worktree_tree(&repo_root, Some(&excludes_file))
+        .map_err(|err| format!("Failed to read repository root: {err}"))?,
+    );
+    state.root_paths.clear();
+    state.paused = false;
+    drop(state);
+    let _ = trigger_tx.send(SyncTrigger::Full);
     Ok(())
   }
 }
 
-fn run_sync(workspace_path: &Path, repo_root: &Path, checkpoint_id: &str) {
-  match create_checkpoint(workspace_path, checkpoint_id) {
+impl SpotlightInstance {
+  fn record(&self, workspace_id: &str) -> SpotlightRecord {
+    SpotlightRecord {
+      workspace_id: workspace_id.to_string(),
+      repo_root: self.repo_root.to_string_lossy().to_string(),
+      workspace_path: self.workspace_path.to_string_lossy().to_string(),
+      rollback_checkpoint_id: self.rollback_checkpoint_id.clone(),
+      sync_checkpoint_id: self.sync_checkpoint_id.clone(),
+    }
+  }
+}
+
+/// Restore the repo root from its rollback checkpoint and drop spotlight refs.
+pub fn roll_back(
+  repo_root: &Path,
+  rollback_checkpoint_id: &str,
+  sync_checkpoint_id: &str,
+) -> Result<(), String> {
+  restore_checkpoint(repo_root, rollback_checkpoint_id)
+    .map_err(|err| format!("Failed to restore rollback checkpoint: {err}"))?;
+
+  let mut cleanup_errors: Vec<String> = Vec::new();
+  if let Err(err) = delete_checkpoint(repo_root, rollback_checkpoint_id) {
+    cleanup_errors.push(format!("Rollback checkpoint cleanup failed: {err}"));
+  }
+  if let Err(err) = delete_checkpoint(repo_root, sync_checkpoint_id) {
+    cleanup_errors.push(format!("Spotlight checkpoint cleanup failed: {err}"));
+  }
+  if !cleanup_errors.is_empty() {
+    return Err(cleanup_errors.join(" | "));
+  }
+  Ok(())
+}
+
+/// Split `spotlight-rollback-<workspace_id>-<stamp>` into the workspace id.
+pub fn rollback_checkpoint_workspace(checkpoint_id: &str) -> Option<&str> {
+  checkpoint_id
+    .strip_prefix("spotlight-rollback-")
+    .and_then(|rest| rest.rsplit_once('-'))
+    .map(|(workspace_id, _)| workspace_id)
+    .filter(|workspace_id| !workspace_id.is_empty())
+}
+
+pub async fn insert_spotlight_instance(
+  pool: &SqlitePool,
+  record: &SpotlightRecord,
+) -> Result<(), DbError> {
+  sqlx::query(
+    "INSERT INTO spotlight_instances
+       (workspace_id, repo_root, workspace_path, rollback_checkpoint_id, sync_checkpoint_id)
+     VALUES (?, ?, ?, ?, ?)
+     ON CONFLICT(workspace_id) DO UPDATE SET
+       repo_root = excluded.repo_root,
+       workspace_path = excluded.workspace_path,
+       rollback_checkpoint_id = excluded.rollback_checkpoint_id,
+       sync_checkpoint_id = excluded.sync_checkpoint_id",
+  )
+  .bind(&record.workspace_id)
+  .bind(&record.repo_root)
+  .bind(&record.workspace_path)
+  .bind(&record.rollback_checkpoint_id)
+  .bind(&record.sync_checkpoint_id)
+  .execute(pool)
+  .await?;
+  Ok(())
+}
+
+pub async fn delete_spotlight_instance(pool: &SqlitePool, workspace_id: &str) -> Result<(), DbError> {
+  sqlx::query("DELETE FROM spotlight_instances WHERE workspace_id = ?")
+    .bind(workspace_id)
+    .execute(pool)
+    .await?;
+  Ok(())
+}
+
+pub async fn list_spotlight_instances(pool: &SqlitePool) -> Result<Vec<SpotlightRecord>, DbError> {
+  let rows = sqlx::query_as::<_, SpotlightRecord>(
+    "SELECT workspace_id, repo_root, workspace_path, rollback_checkpoint_id, sync_checkpoint_id
+     FROM spotlight_instances
+     ORDER BY created_at ASC",
+  )
+  .fetch_all(pool)
+  .await?;
+  Ok(rows)
+}
+
+/// Returns true when syncing must not proceed: either a conflict is already
+/// pending, or the repo root no longer matches what the last sync wrote.
+fn detect_root_conflict(context: &SyncContext) -> bool {
+  let Ok(mut state) = context.guard.lock() else {
+    return true;
+  };
+  if state.paused {
+    return true;
+  }
+  let Some(expected) = state.root_tree.clone() else {
+    return false;
+  };
+  let current = match current_worktree_tree(&context.repo_root, Some(&context.excludes_file)) {
+    Ok(tree) => tree,
+    Err(err) => {
+      eprintln!("[spotlight] failed to read repository root: {err}");
+      return false;
+    }
+  };
+  if current == expected {
+    return false;
+  }
+  state.paused = true;
+  drop(state);
+  report_sync(context, "conflict", |status| {
+    status.last_skip_reason = Some("Repository root changed outside spotlight".to_string());
+  });
+  let changed_paths = changed_paths(&context.repo_root, &expected, &current).unwrap_or_default();
+  let _ = context.app_handle.emit(
+    "spotlight-conflict",
+    SpotlightConflictEvent {
+      workspace_id: context.workspace_id.clone(),
+      changed_paths,
+    },
+  );
+  true
+}
+
+/// Like `detect_root_conflict`, but only compares the given root paths with the
+/// last full sync and the incremental copies made since.
+fn detect_root_path_conflict(context: &SyncContext, paths: &HashSet<PathBuf>) -> bool {
+  let Ok(mut state) = context.guard.lock() else {
+    return true;
+  };
+  if state.paused {
+    return true;
+  }
+  let changed = changed_root_paths(&context.repo_root, &context.excludes_file, &state, paths);
+  if changed.is_empty() {
+    return false;
+  }
+  state.paused = true;
+  drop(state);
+  report_sync(context, "conflict", |status| {
+    status.last_skip_reason = Some("Repository root changed outside spotlight".to_string());
+  });
+  let _ = context.app_handle.emit(
+    "spotlight-conflict",
+    SpotlightConflictEvent {
+      workspace_id: context.workspace_id.clone(),
+      changed_paths: changed,
+    },
+  );
+  true
+}
+
+/// Root paths among `paths` whose content differs from what spotlight last wrote
+/// there. Ignored paths never count; nothing does before the first full sync.
+fn changed_root_paths(
+  repo_root: &Path,
+  excludes_file: &Path,
+  state: &SyncGuard,
+  paths: &HashSet<PathBuf>,
+) -> Vec<String> {
+  let Some(root_tree) = state.root_tree.as_deref() else {
+    return Vec::new();
+  };
+  let relative: Vec<String> = paths
+    .iter()
+    .filter_map(|path| path.strip_prefix(repo_root).ok())
+    .filter_map(|rel| rel.to_str())
+    .filter(|rel| !rel.is_empty())
+    .map(str::to_string)
+    .collect();
+  let ignored = ignored_paths(repo_root, relative.iter().map(String::as_str), Some(excludes_file))
+    .unwrap_or_default();
+  let relative: Vec<String> = relative
+    .into_iter()
+    .filter(|rel| !ignored.contains(rel))
+    .collect();
+  if relative.is_empty() {
+    return Vec::new();
+  }
+  let (current, mut expected) = match (
+    hash_worktree_paths(repo_root, &relative),
+    tree_blob_ids(repo_root, root_tree, &relative),
+  ) {
+    (Ok(current), Ok(expected)) => (current, expected),
+    (Err(err), _) | (_, Err(err)) => {
+      eprintln!("[spotlight] failed to read repository root: {err}");
+      return Vec::new();
+    }
+  };
+  for (path, blob) in &state.root_paths {
+    match blob {
+      Some(blob) => expected.insert(path.clone(), blob.clone()),
+      None => expected.remove(path),
+    };
+  }
+  relative
+    .into_iter()
+    .filter(|rel| match current.get(rel) {
+      Some(Some(blob)) => expected.get(rel) != Some(blob),
+      Some(None) => {
+        let prefix = format!("{rel}/");
+        expected.contains_key(rel) || expected.keys().any(|path| path.starts_with(&prefix))
+      }
+      // Directories and symlinks; their files are reported on their own.
+      None => false,
+    })
+    .collect()
+}
+
+fn run_sync(context: &SyncContext) {
+  let started = Instant::now();
+  let workspace_path = context.workspace_path.as_path();
+  let repo_root = context.repo_root.as_path();
+  let checkpoint_id = context.sync_checkpoint_id.as_str();
+  match create_checkpoint_excluding(workspace_path, checkpoint_id, &context.excludes_file) {
     Ok(CheckpointOutcome::Created) => {}
     Ok(CheckpointOutcome::Skipped { reason }) => {
-      eprintln!("[spotlight] sync skipped: {reason}");
+      report_sync(context, "skipped", |status| status.last_skip_reason = Some(reason));
       return;
     }
     Err(err) => {
-      eprintln!("[spotlight] sync checkpoint failed: {err}");
+      report_sync(context, "failed", |status| {
+        status.last_error = Some(format!("Sync checkpoint failed: {err}"));
+      });
       return;
     }
   }
-  if let Err(err) = restore_checkpoint(repo_root, checkpoint_id) {
-    eprintln!("[spotlight] restore failed: {err}");
+  let previous_tree = context
+    .guard
+    .lock()
+    .ok()
+    .and_then(|state| state.root_tree.clone())
+    .or_else(|| current_worktree_tree(repo_root, Some(&context.excludes_file)).ok());
+  if let Err(err) = restore_checkpoint_excluding(repo_root, checkpoint_id, &context.excludes_file) {
+    report_sync(context, "failed", |status| {
+      status.last_error = Some(format!("Restore failed: {err}"));
+    });
+    return;
+  }
+  let tree = match current_worktree_tree(repo_root, Some(&context.excludes_file)) {
+    Ok(tree) => tree,
+    Err(err) => {
+      report_sync(context, "failed", |status| {
+        status.last_error = Some(format!("Failed to record repository root: {err}"));
+      });
+      return;
+    }
+  };
+  let files_synced = previous_tree
+    .and_then(|previous| changed_paths(repo_root, &previous, &tree).ok())
+    .map(|paths| paths.len())
+    .unwrap_or(0);
+  if let Ok(mut state) = context.guard.lock() {
+    state.root_tree = Some(tree);
+    state.root_paths.clear();
+  }
+  let duration_ms = started.elapsed().as_millis() as u64;
+  report_sync(context, "synced", |status| {
+    status.last_sync_at = Some(unix_now());
+    status.last_sync_duration_ms = Some(duration_ms);
+    status.last_sync_mode = Some("full".to_string());
+    status.files_synced = files_synced;
+    status.sync_count += 1;
+    status.last_error = None;
+    status.last_skip_reason = None;
+  });
+}
+
+/// Copy only the given workspace paths into the repo root. Deleted paths are
+/// removed, so renames (reported as both paths) fall out naturally. Returns false
+/// when the change needs a full sync instead (new directories, symlinks, errors).
+fn run_incremental_sync(context: &SyncContext, paths: &[PathBuf]) -> bool {
+  let started = Instant::now();
+  let Some(files_synced) =
+    copy_workspace_paths(&context.workspace_path, &context.repo_root, &context.guard, paths)
+  else {
+    return false;
+  };
+  if files_synced == 0 {
+    return true;
+  }
+  let duration_ms = started.elapsed().as_millis() as u64;
+  report_sync(context, "synced", |status| {
+    status.last_sync_at = Some(unix_now());
+    status.last_sync_duration_ms = Some(duration_ms);
+    status.last_sync_mode = Some("incremental".to_string());
+    status.files_synced = files_synced;
+    status.sync_count += 1;
+    status.last_error = None;
+    status.last_skip_reason = None;
+  });
+  true
+}
+
+/// The copying half of `run_incremental_sync`: returns how many root paths
+/// changed, or `None` when a full sync is needed.
+fn copy_workspace_paths(
+  workspace_path: &Path,
+  repo_root: &Path,
+  guard: &Mutex<SyncGuard>,
+  paths: &[PathBuf],
+) -> Option<usize> {
+  let mut relative: Vec<String> = Vec::new();
+  for path in paths {
+    let Ok(rel) = path.strip_prefix(workspace_path) else {
+      continue;
+    };
+    if rel.as_os_str().is_empty() {
+      continue;
+    }
+    let rel = rel.to_str()?;
+    relative.push(rel.to_string());
+  }
+
+  let mut files_synced = 0usize;
+  for rel in &relative {
+    let source = workspace_path.join(rel);
+    let target = repo_root.join(rel);
+    let applied = match fs::symlink_metadata(&source) {
+      // Directories that appear in one event (e.g. moved in) carry contents we
+      // got no events for, and symlinks are left to git.
+      Ok(meta) if !meta.is_file() => return None,
+      Ok(_) => copy_file(&source, &target),
+      Err(err) if err.kind() == io::ErrorKind::NotFound => remove_entry(&target),
+      Err(_) => return None,
+    };
+    match applied {
+      Ok(true) => files_synced += 1,
+      Ok(false) => {}
+      Err(err) => {
+        eprintln!("[spotlight] incremental sync of {rel} failed: {err}");
+        return None;
+      }
+    }
+  }
+
+  if files_synced == 0 {
+    return Some(0);
+  }
+  // Remember what we wrote per path, so root edits can be told apart from our
+  // own copies without hashing the whole root.
+  let hashes = hash_worktree_paths(repo_root, &relative).ok()?;
+  if let Ok(mut state) = guard.lock() {
+    state.root_paths.extend(hashes);
+  }
+  Some(files_synced)
+}
+
+/// Returns whether the target changed.
+fn copy_file(source: &Path, target: &Path) -> io::Result<bool> {
+  if let Ok(meta) = fs::symlink_metadata(target) {
+    if meta.is_dir() {
+      fs::remove_dir_all(target)?;
+    } else if !meta.is_file() {
+      fs::remove_file(target)?;
+    } else if fs::read(source)? == fs::read(target)? {
+      return Ok(false);
+    }
+  }
+  if let Some(parent) = target.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  fs::copy(source, target)?;
+  Ok(true)
+}
+
+/// Returns whether anything was removed.
+fn remove_entry(target: &Path) -> io::Result<bool> {
+  let meta = match fs::symlink_metadata(target) {
+    Ok(meta) => meta,
+    Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+    Err(err) => return Err(err),
+  };
+  if meta.is_dir() {
+    fs::remove_dir_all(target)?;
+  } else {
+    fs::remove_file(target)?;
+  }
+  Ok(true)
+}
+
+/// Update the shared status and emit a `spotlight-sync` event with the result.
+fn report_sync(context: &SyncContext, outcome: &str, update: impl FnOnce(&mut SpotlightStatus)) {
+  let snapshot = {
+    let Ok(mut status) = context.status.lock() else {
+      return;
+    };
+    update(&mut status);
+    let mut snapshot = status.clone();
+    snapshot.paused = context.guard.lock().map(|state| state.paused).unwrap_or(false);
+    snapshot
+  };
+  if let Some(err) = snapshot.last_error.as_deref().filter(|_| outcome == "failed") {
+    eprintln!("[spotlight] {err}");
   }
+  let _ = context.app_handle.emit(
+    "spotlight-sync",
+    SpotlightSyncEvent {
+      workspace_id: context.workspace_id.clone(),
+      outcome: outcome.to_string(),
+      status: snapshot,
+    },
+  );
+}
+
+fn unix_now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs())
+    .unwrap_or(0)
+}
+
+/// Remove pending workspace paths that `.gitignore` or `spotlightIgnore` exclude,
+/// using the same rules a full sync snapshots with. Paths are kept if git cannot
+/// answer, so a failure errs on the side of syncing.
+fn drop_ignored(context: &SyncContext, pending: &mut HashSet<PathBuf>) {
+  let workspace_path = context.workspace_path.as_path();
+  let relative: Vec<String> = pending
+    .iter()
+    .filter_map(|path| path.strip_prefix(workspace_path).ok())
+    .filter_map(|rel| rel.to_str())
+    .filter(|rel| !rel.is_empty())
+    .map(str::to_string)
+    .collect();
+  let Ok(ignored) = ignored_paths(
+    workspace_path,
+    relative.iter().map(String::as_str),
+    Some(&context.excludes_file),
+  ) else {
+    return;
+  };
+  if ignored.is_empty() {
+    return;
+  }
+  pending.retain(|path| {
+    path
+      .strip_prefix(workspace_path)
+      .ok()
+      .and_then(|rel| rel.to_str())
+      .is_none_or(|rel| !ignored.contains(rel))
+  });
+}
+
+/// Write the ignore file spotlight hands to git: the user's global excludes (which
+/// it replaces as `core.excludesFile`) followed by the `spotlightIgnore` patterns.
+fn write_excludes_file(dest: &Path, workspace_path: &Path, patterns: &[String]) -> io::Result<()> {
+  let mut content = global_excludes_file(workspace_path)
+    .and_then(|path| fs::read_to_string(path).ok())
+    .unwrap_or_default();
+  if !content.is_empty() && !content.ends_with('\n') {
+    content.push('\n');
+  }
+  for pattern in patterns {
+    content.push_str(pattern);
+    content.push('\n');
+  }
+  fs::write(dest, content)
 }
 
 fn should_ignore_event(event: &Event) -> bool {
+  // Reads (including our own `git add` during sync) must not retrigger a sync.
+  if matches!(event.kind, EventKind::Access(_)) {
+    return true;
+  }
   if event.paths.is_empty() {
     return false;
   }
@@ -229,3 +1040,173 @@ fn should_ignore_path(path: &Path) -> bool {
   }
   false
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    changed_root_paths, copy_workspace_paths, roll_back, rollback_checkpoint_workspace, SyncGuard,
+  };
+  use crate::checkpoints::{create_checkpoint, current_worktree_tree, get_checkpoint};
+  use std::collections::HashSet;
+  use std::path::{Path, PathBuf};
+  use std::process::Command;
+  use std::sync::Mutex;
+
+  /// Throwaway repository on `main` with `a.txt`, `b.txt` and a `.gitignore` for
+  /// `*.log` committed, removed on drop.
+  struct TempRepo(PathBuf);
+
+  impl TempRepo {
+    fn new(name: &str) -> Self {
+      let path =
+        std::env::temp_dir().join(format!("supertree-spotlight-{name}-{}", std::process::id()));
+      let _ = std::fs::remove_dir_all(&path);
+      std::fs::create_dir_all(&path).unwrap();
+      let repo = TempRepo(path);
+      repo.git(&["init", "-q", "-b", "main"]);
+      repo.write("a.txt", "a\n");
+      repo.write("b.txt", "b\n");
+      repo.write(".gitignore", "*.log\n");
+      repo.git(&["add", "--all"]);
+      repo.git(&["commit", "-q", "-m", "init"]);
+      repo
+    }
+
+    fn path(&self) -> &Path {
+      &self.0
+    }
+
+    fn file(&self, name: &str) -> PathBuf {
+      self.0.join(name)
+    }
+
+    fn write(&self, name: &str, contents: &str) {
+      std::fs::write(self.file(name), contents).unwrap();
+    }
+
+    fn read(&self, name: &str) -> Option<String> {
+      std::fs::read_to_string(self.file(name)).ok()
+    }
+
+    /// Empty excludes file kept inside `.git`, like the one spotlight writes.
+    fn excludes_file(&self) -> PathBuf {
+      let path = self.0.join(".git").join("spotlight.exclude");
+      std::fs::write(&path, "").unwrap();
+      path
+    }
+
+    /// Root state right after a full sync.
+    fn synced_guard(&self, excludes_file: &Path) -> SyncGuard {
+      SyncGuard {
+        root_tree: Some(current_worktree_tree(self.path(), Some(excludes_file)).unwrap()),
+        ..SyncGuard::default()
+      }
+    }
+
+    fn git(&self, args: &[&str]) -> String {
+      let output = Command::new("git")
+        .current_dir(&self.0)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+      assert!(output.status.success(), "git {args:?}: {}", String::from_utf8_lossy(&output.stderr));
+      String::from_utf8(output.stdout).unwrap()
+    }
+  }
+
+  impl Drop for TempRepo {
+    fn drop(&mut self) {
+      let _ = std::fs::remove_dir_all(&self.0);
+    }
+  }
+
+  fn sorted(mut paths: Vec<String>) -> Vec<String> {
+    paths.sort();
+    paths
+  }
+
+  #[test]
+  fn reports_root_paths_edited_outside_spotlight() {
+    let root = TempRepo::new("root-conflict");
+    let excludes_file = root.excludes_file();
+    let state = root.synced_guard(&excludes_file);
+    root.write("a.txt", "edited in the root\n");
+    root.write("debug.log", "ignored\n");
+    std::fs::remove_file(root.file("b.txt")).unwrap();
+    let paths: HashSet<PathBuf> = ["a.txt", "b.txt", ".gitignore", "debug.log"]
+      .iter()
+      .map(|name| root.file(name))
+      .collect();
+    assert_eq!(
+      sorted(changed_root_paths(root.path(), &excludes_file, &state, &paths)),
+      vec!["a.txt".to_string(), "b.txt".to_string()]
+    );
+    // Nothing is compared before the first full sync.
+    let unsynced = SyncGuard::default();
+    assert!(changed_root_paths(root.path(), &excludes_file, &unsynced, &paths).is_empty());
+  }
+
+  #[test]
+  fn incremental_copies_are_not_root_conflicts() {
+    let root = TempRepo::new("incremental-root");
+    let workspace = TempRepo::new("incremental-workspace");
+    let excludes_file = root.excludes_file();
+    let guard = Mutex::new(root.synced_guard(&excludes_file));
+    workspace.write("a.txt", "from the workspace\n");
+    workspace.write("c.txt", "new file\n");
+    std::fs::remove_file(workspace.file("b.txt")).unwrap();
+    let paths: Vec<PathBuf> = ["a.txt", "b.txt", "c.txt"]
+      .iter()
+      .map(|name| workspace.file(name))
+      .collect();
+    assert_eq!(copy_workspace_paths(workspace.path(), root.path(), &guard, &paths), Some(3));
+    assert_eq!(root.read("a.txt").as_deref(), Some("from the workspace\n"));
+    assert_eq!(root.read("b.txt"), None);
+    assert_eq!(root.read("c.txt").as_deref(), Some("new file\n"));
+    // Copying the same paths again changes nothing.
+    assert_eq!(copy_workspace_paths(workspace.path(), root.path(), &guard, &paths), Some(0));
+
+    let root_paths: HashSet<PathBuf> =
+      ["a.txt", "b.txt", "c.txt"].iter().map(|name| root.file(name)).collect();
+    let state = guard.lock().unwrap();
+    assert!(changed_root_paths(root.path(), &excludes_file, &state, &root_paths).is_empty());
+    root.write("c.txt", "edited in the root\n");
+    assert_eq!(
+      changed_root_paths(root.path(), &excludes_file, &state, &root_paths),
+      vec!["c.txt".to_string()]
+    );
+  }
+
+  #[test]
+  fn new_directories_need_a_full_sync() {
+    let root = TempRepo::new("incremental-dir-root");
+    let workspace = TempRepo::new("incremental-dir-workspace");
+    let guard = Mutex::new(SyncGuard::default());
+    std::fs::create_dir_all(workspace.file("moved/in")).unwrap();
+    workspace.write("moved/in/file.txt", "x\n");
+    let paths = vec![workspace.file("moved")];
+    assert_eq!(copy_workspace_paths(workspace.path(), root.path(), &guard, &paths), None);
+    assert_eq!(root.read("moved/in/file.txt"), None);
+  }
+
+  #[test]
+  fn roll_back_restores_the_root_and_drops_spotlight_refs() {
+    let root = TempRepo::new("rollback");
+    root.write("a.txt", "work in progress\n");
+    let rollback_id = "spotlight-rollback-ws-1-1700000000";
+    create_checkpoint(root.path(), rollback_id).unwrap();
+    // What a sync leaves behind in the root.
+    root.write("a.txt", "synced from the workspace\n");
+    root.write("synced.txt", "synced\n");
+    create_checkpoint(root.path(), "spotlight-sync-ws-1").unwrap();
+
+    roll_back(root.path(), rollback_id, "spotlight-sync-ws-1").unwrap();
+    assert_eq!(root.read("a.txt").as_deref(), Some("work in progress\n"));
+    assert_eq!(root.read("synced.txt"), None);
+    assert!(get_checkpoint(root.path(), rollback_id).is_err());
+    assert!(get_checkpoint(root.path(), "spotlight-sync-ws-1").is_err());
+    assert_eq!(rollback_checkpoint_workspace(rollback_id), Some("ws-1"));
+    assert_eq!(rollback_checkpoint_workspace("spotlight-rollback--1"), None);
+  }
+}
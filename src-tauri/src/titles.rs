@@ -0,0 +1,65 @@
+/// Longest generated title kept, in characters.
+const MAX_TITLE_CHARS: usize = 60;
+/// Each side of the first exchange is cut to this many characters in the prompt.
+const MAX_PROMPT_EXCERPT_CHARS: usize = 2_000;
+
+/// Prompt asking the title model to name a chat from its first exchange.
+pub fn build_title_prompt(user_message: &str, assistant_message: &str) -> String {
+  format!(
+    "Write a short title, at most six words, for a coding chat that starts with the \
+     exchange below. Reply with the title only: no quotes, no trailing punctuation.\n\n\
+     <user>\n{}\n</user>\n\n<assistant>\n{}\n</assistant>",
+    excerpt(user_message),
+    excerpt(assistant_message),
+  )
+}
+
+/// Reduce a model reply to a single clean title line, or `None` if nothing usable
+/// is left.
+pub fn clean_generated_title(raw: &str) -> Option<String> {
+  let line = raw.lines().map(str::trim).find(|line| !line.is_empty())?;
+  let line = line
+    .trim_start_matches('#')
+    .trim()
+    .trim_start_matches("Title:")
+    .trim()
+    .trim_matches(|c: char| matches!(c, '"' | '\'' | '`' | '*' | '“' | '”'))
+    .trim_end_matches(['.', ':'])
+    .trim();
+  if line.is_empty() {
+    return None;
+  }
+  let title: String = match line.char_indices().nth(MAX_TITLE_CHARS) {
+    Some((index, _)) => format!("{}…", line[..index].trim_end()),
+    None => line.to_string(),
+  };
+  Some(title)
+}
+
+fn excerpt(text: &str) -> &str {
+  let text = text.trim();
+  match text.char_indices().nth(MAX_PROMPT_EXCERPT_CHARS) {
+    Some((index, _)) => &text[..index],
+    None => text,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::clean_generated_title;
+
+  #[test]
+  fn cleans_generated_titles() {
+    assert_eq!(
+      clean_generated_title("\n\"Fix sidecar restart backoff.\"\nSome explanation"),
+      Some("Fix sidecar restart backoff".to_string())
+    );
+    assert_eq!(
+      clean_generated_title("## Title: **Add FTS search**"),
+      Some("Add FTS search".to_string())
+    );
+    assert_eq!(clean_generated_title("  \n \"\" "), None);
+    let long = clean_generated_title(&"word ".repeat(30)).unwrap();
+    assert!(long.ends_with('…') && long.chars().count() <= 61);
+  }
+}
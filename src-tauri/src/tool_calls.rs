@@ -0,0 +1,387 @@
+use crate::db::DbError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Longest tool output kept per call; command output can be arbitrarily large.
+const MAX_TOOL_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// One tool invocation made by an agent during a turn.
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionToolCallRecord {
+  pub id: String,
+  pub session_id: String,
+  pub turn_id: i64,
+  pub message_id: Option<String>,
+  pub call_id: String,
+  pub tool_name: String,
+  pub input_json: Option<String>,
+  pub output: Option<String>,
+  pub status: String,
+  pub started_at_ms: i64,
+  pub finished_at_ms: Option<i64>,
+  pub duration_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolCallStatus {
+  Running,
+  Completed,
+  Failed,
+}
+
+impl ToolCallStatus {
+  fn as_str(self) -> &'static str {
+    match self {
+      ToolCallStatus::Running => "running",
+      ToolCallStatus::Completed => "completed",
+      ToolCallStatus::Failed => "error",
+    }
+  }
+}
+
+/// A tool call change found in one sidecar stream message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCallUpdate {
+  pub call_id: String,
+  pub tool_name: Option<String>,
+  pub input: Option<Value>,
+  pub output: Option<String>,
+  pub status: ToolCallStatus,
+}
+
+/// Extract tool call starts and results from a raw agent SDK message.
+pub fn parse_tool_call_updates(agent_type: &str, data: &Value) -> Vec<ToolCallUpdate> {
+  match agent_type {
+    "claude" => parse_claude_message(data),
+    "codex" => parse_codex_event(data).into_iter().collect(),
+    _ => Vec::new(),
+  }
+}
+
+/// Claude reports calls as `tool_use` blocks in assistant messages and their
+/// results as `tool_result` blocks in the following user message.
+fn parse_claude_message(data: &Value) -> Vec<ToolCallUpdate> {
+  let kind = data.get("type").and_then(Value::as_str).unwrap_or_default();
+  let Some(blocks) = data
+    .get("message")
+    .and_then(|message| message.get("content"))
+    .and_then(Value::as_array)
+  else {
+    return Vec::new();
+  };
+  blocks
+    .iter()
+    .filter_map(|block| {
+      let block_type = block.get("type").and_then(Value::as_str)?;
+      match (kind, block_type) {
+        ("assistant", "tool_use") => Some(ToolCallUpdate {
+          call_id: block.get("id").and_then(Value::as_str)?.to_string(),
+          tool_name: block.get("name").and_then(Value::as_str).map(str::to_string),
+          input: block.get("input").cloned(),
+          output: None,
+          status: ToolCallStatus::Running,
+        }),
+        ("user", "tool_result") => {
+          let is_error = block.get("is_error").and_then(Value::as_bool).unwrap_or(false);
+          Some(ToolCallUpdate {
+            call_id: block.get("tool_use_id").and_then(Value::as_str)?.to_string(),
+            tool_name: None,
+            input: None,
+            output: block.get("content").map(claude_result_text),
+            status: if is_error {
+              ToolCallStatus::Failed
+            } else {
+              ToolCallStatus::Completed
+            },
+          })
+        }
+        _ => None,
+      }
+    })
+    .collect()
+}
+
+fn claude_result_text(content: &Value) -> String {
+  match content {
+    Value::String(text) => text.clone(),
+    Value::Array(parts) => parts
+      .iter()
+      .filter_map(|part| part.get("text").and_then(Value::as_str))
+      .collect::<Vec<_>>()
+      .join("\n"),
+    other => other.to_string(),
+  }
+}
+
+/// Codex streams each tool as an item that is started, updated and completed.
+fn parse_codex_event(data: &Value) -> Option<ToolCallUpdate> {
+  let event = data.get("type").and_then(Value::as_str)?;
+  if !matches!(event, "item.started" | "item.updated" | "item.completed") {
+    return None;
+  }
+  let item = data.get("item")?;
+  let item_type = item.get("type").and_then(Value::as_str)?;
+  let (tool_name, input, output) = match item_type {
+    "command_execution" => (
+      item_type.to_string(),
+      serde_json::json!({ "command": item.get("command") }),
+      item
+        .get("aggregated_output")
+        .and_then(Value::as_str)
+        .map(str::to_string),
+    ),
+    "file_change" => (
+      item_type.to_string(),
+      serde_json::json!({ "changes": item.get("changes") }),
+      None,
+    ),
+    "mcp_tool_call" => {
+      let server = item.get("server").and_then(Value::as_str).unwrap_or_default();
+      let tool = item.get("tool").and_then(Value::as_str).unwrap_or(item_type);
+      let name = if server.is_empty() {
+        tool.to_string()
+      } else {
+        format!("{server}/{tool}")
+      };
+      let output = item
+        .get("error")
+        .and_then(|error| error.get("message"))
+        .or_else(|| item.get("result"))
+        .map(|value| match value {
+          Value::String(text) => text.clone(),
+          other => other.to_string(),
+        });
+      (
+        name,
+        item.get("arguments").cloned().unwrap_or(Value::Null),
+        output,
+      )
+    }
+    "web_search" => (
+      item_type.to_string(),
+      serde_json::json!({ "query": item.get("query") }),
+      None,
+    ),
+    _ => return None,
+  };
+  let status = match item.get("status").and_then(Value::as_str) {
+    Some("failed") => ToolCallStatus::Failed,
+    Some("in_progress") => ToolCallStatus::Running,
+    _ if event == "item.completed" => ToolCallStatus::Completed,
+    _ => ToolCallStatus::Running,
+  };
+  Some(ToolCallUpdate {
+    call_id: item.get("id").and_then(Value::as_str)?.to_string(),
+    tool_name: Some(tool_name),
+    input: Some(input),
+    output,
+    status,
+  })
+}
+
+/// Insert or advance a tool call of the turn. Finished calls keep their final
+/// status if the agent repeats an earlier update.
+pub async fn upsert_tool_call(
+  pool: &SqlitePool,
+  session_id: &str,
+  turn_id: i64,
+  message_id: Option<&str>,
+  update: &ToolCallUpdate,
+) -> Result<(), DbError> {
+  let now_ms = unix_now_ms();
+  let input_json = update.input.as_ref().map(Value::to_string);
+  let output = update
+    .output
+    .as_deref()
+    .map(|output| truncate_output(output, MAX_TOOL_OUTPUT_BYTES));
+  let finished_at_ms = (update.status != ToolCallStatus::Running).then_some(now_ms);
+  sqlx::query(
+    "INSERT INTO session_tool_calls
+      (id, session_id, turn_id, message_id, call_id, tool_name, input_json, output, status,
+       started_at_ms, finished_at_ms)
+     VALUES (lower(hex(randomblob(16))), ?, ?, ?, ?, COALESCE(?, 'unknown'), ?, ?, ?, ?, ?)
+     ON CONFLICT(session_id, turn_id, call_id) DO UPDATE SET
+       message_id = COALESCE(session_tool_calls.message_id, excluded.message_id),
+       tool_name = CASE WHEN ? IS NULL THEN session_tool_calls.tool_name ELSE excluded.tool_name END,
+       input_json = COALESCE(excluded.input_json, session_tool_calls.input_json),
+       output = COALESCE(excluded.output, session_tool_calls.output),
+       status = CASE WHEN session_tool_calls.finished_at_ms IS NULL
+                     THEN excluded.status ELSE session_tool_calls.status END,
+       finished_at_ms = COALESCE(session_tool_calls.finished_at_ms, excluded.finished_at_ms),
+       updated_at = CURRENT_TIMESTAMP",
+  )
+  .bind(session_id)
+  .bind(turn_id)
+  .bind(message_id)
+  .bind(&update.call_id)
+  .bind(&update.tool_name)
+  .bind(input_json)
+  .bind(output)
+  .bind(update.status.as_str())
+  .bind(now_ms)
+  .bind(finished_at_ms)
+  .bind(&update.tool_name)
+  .execute(pool)
+  .await?;
+  Ok(())
+}
+
+/// Link calls made before the turn's assistant message existed to it.
+pub async fn set_turn_tool_calls_message(
+  pool: &SqlitePool,
+  session_id: &str,
+  turn_id: i64,
+  message_id: &str,
+) -> Result<(), DbError> {
+  sqlx::query(
+    "UPDATE session_tool_calls
+     SET message_id = ?, updated_at = CURRENT_TIMESTAMP
+     WHERE session_id = ? AND turn_id = ? AND message_id IS NULL",
+  )
+  .bind(message_id)
+  .bind(session_id)
+  .bind(turn_id)
+  .execute(pool)
+  .await?;
+  Ok(())
+}
+
+/// Close calls of a turn that never reported a result, e.g. after a cancel.
+pub async fn finish_running_tool_calls(
+  pool: &SqlitePool,
+  session_id: &str,
+  turn_id: i64,
+  status: &str,
+) -> Result<(), DbError> {
+  sqlx::query(
+    "UPDATE session_tool_calls
+     SET status = ?, finished_at_ms = ?, updated_at = CURRENT_TIMESTAMP
+     WHERE session_id = ? AND turn_id = ? AND finished_at_ms IS NULL",
+  )
+  .bind(status)
+  .bind(unix_now_ms())
+  .bind(session_id)
+  .bind(turn_id)
+  .execute(pool)
+  .await?;
+  Ok(())
+}
+
+/// Tool calls of a session in the order they started, optionally for one turn.
+pub async fn list_session_tool_calls(
+  pool: &SqlitePool,
+  session_id: &str,
+  turn_id: Option<i64>,
+) -> Result<Vec<SessionToolCallRecord>, DbError> {
+  let rows = sqlx::query_as::<_, SessionToolCallRecord>(
+    "SELECT id, session_id, turn_id, message_id, call_id, tool_name, input_json, output, status,
+            started_at_ms, finished_at_ms, finished_at_ms - started_at_ms AS duration_ms
+     FROM session_tool_calls
+     WHERE session_id = ? AND (? IS NULL OR turn_id = ?)
+     ORDER BY started_at_ms ASC, rowid ASC",
+  )
+  .bind(session_id)
+  .bind(turn_id)
+  .bind(turn_id)
+  .fetch_all(pool)
+  .await?;
+  Ok(rows)
+}
+
+fn unix_now_ms() -> i64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_millis() as i64)
+    .unwrap_or(0)
+}
+
+fn truncate_output(output: &str, max_bytes: usize) -> String {
+  if output.len() <= max_bytes {
+    return output.to_string();
+  }
+  let mut end = max_bytes;
+  while !output.is_char_boundary(end) {
+    end -= 1;
+  }
+  format!("{}\n...[truncated]", &output[..end])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{parse_tool_call_updates, ToolCallStatus};
+  use serde_json::json;
+
+  #[test]
+  fn parses_claude_tool_use_and_results() {
+    let started = parse_tool_call_updates(
+      "claude",
+      &json!({
+        "type": "assistant",
+        "message": { "content": [
+          { "type": "text", "text": "Running tests" },
+          { "type": "tool_use", "id": "toolu_1", "name": "Bash", "input": { "command": "cargo test" } }
+        ] }
+      }),
+    );
+    assert_eq!(started.len(), 1);
+    assert_eq!(started[0].call_id, "toolu_1");
+    assert_eq!(started[0].tool_name.as_deref(), Some("Bash"));
+    assert_eq!(started[0].input, Some(json!({ "command": "cargo test" })));
+    assert_eq!(started[0].status, ToolCallStatus::Running);
+
+    let finished = parse_tool_call_updates(
+      "claude",
+      &json!({
+        "type": "user",
+        "message": { "content": [
+          { "type": "tool_result", "tool_use_id": "toolu_1", "is_error": true,
+            "content": [{ "type": "text", "text": "1 failed" }] }
+        ] }
+      }),
+    );
+    assert_eq!(finished.len(), 1);
+    assert_eq!(finished[0].call_id, "toolu_1");
+    assert_eq!(finished[0].output.as_deref(), Some("1 failed"));
+    assert_eq!(finished[0].status, ToolCallStatus::Failed);
+
+    let stream = json!({ "type": "stream_event", "event": { "type": "content_block_delta" } });
+    assert!(parse_tool_call_updates("claude", &stream).is_empty());
+  }
+
+  #[test]
+  fn parses_codex_tool_items() {
+    let completed = parse_tool_call_updates(
+      "codex",
+      &json!({
+        "type": "item.completed",
+        "item": { "id": "item_3", "type": "command_execution", "command": "ls",
+                  "aggregated_output": "src\n", "exit_code": 0, "status": "completed" }
+      }),
+    );
+    assert_eq!(completed.len(), 1);
+    assert_eq!(completed[0].tool_name.as_deref(), Some("command_execution"));
+    assert_eq!(completed[0].input, Some(json!({ "command": "ls" })));
+    assert_eq!(completed[0].output.as_deref(), Some("src\n"));
+    assert_eq!(completed[0].status, ToolCallStatus::Completed);
+
+    let mcp = parse_tool_call_updates(
+      "codex",
+      &json!({
+        "type": "item.started",
+        "item": { "id": "item_4", "type": "mcp_tool_call", "server": "github",
+                  "tool": "search", "status": "in_progress" }
+      }),
+    );
+    assert_eq!(mcp[0].tool_name.as_deref(), Some("github/search"));
+    assert_eq!(mcp[0].status, ToolCallStatus::Running);
+
+    let message = json!({
+      "type": "item.completed",
+      "item": { "id": "item_5", "type": "agent_message", "text": "done" }
+    });
+    assert!(parse_tool_call_updates("codex", &message).is_empty());
+  }
+}
@@ -0,0 +1,463 @@
+use crate::attachments;
+use crate::db::DbError;
+use crate::sessions::{self, SessionRecord};
+use crate::tool_calls::{self, SessionToolCallRecord};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Bumped whenever the JSON export changes shape incompatibly.
+pub const SESSION_EXPORT_VERSION: u32 = 1;
+/// Tool inputs longer than this are cut in Markdown exports.
+const MAX_MARKDOWN_TOOL_INPUT_CHARS: usize = 2_000;
+/// Agents an imported session may name.
+const KNOWN_AGENT_TYPES: &[&str] = &["claude", "codex"];
+/// Roles an imported message may have.
+const KNOWN_MESSAGE_ROLES: &[&str] = &["user", "assistant", "system"];
+
+/// Portable copy of a session, written by `exportSession` and read back by
+/// `importSession`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExport {
+  pub version: u32,
+  pub exported_at: String,
+  pub session: ExportedSession,
+  pub messages: Vec<ExportedMessage>,
+  #[serde(default)]
+  pub tool_calls: Vec<SessionToolCallRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedSession {
+  pub id: String,
+  pub title: Option<String>,
+  pub agent_type: String,
+  pub model: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedMessage {
+  pub id: String,
+  pub turn_id: i64,
+  pub role: String,
+  pub content: String,
+  pub sent_at: Option<String>,
+  pub cancelled_at: Option<String>,
+  /// Parsed `metadata_json`: tool summary, `diffStat`, `diff` and raw output.
+  pub metadata: Option<Value>,
+  #[serde(default)]
+  pub attachments: Vec<ExportedAttachment>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedAttachment {
+  #[serde(rename = "type")]
+  pub attachment_type: String,
+  pub title: Option<String>,
+  pub path: Option<String>,
+  pub mime_type: Option<String>,
+}
+
+/// Gather a session's sent messages, their attachments and its tool calls.
+pub async fn build_session_export(
+  pool: &SqlitePool,
+  session_id: &str,
+) -> Result<SessionExport, DbError> {
+  let session = sessions::get_session(pool, session_id).await?;
+  let exported_at: String = sqlx::query_scalar("SELECT strftime('%Y-%m-%dT%H:%M:%SZ', 'now')")
+    .fetch_one(pool)
+    .await?;
+
+  let mut attachments_by_message: HashMap<String, Vec<ExportedAttachment>> = HashMap::new();
+  for attachment in attachments::list_session_attachments(pool, session_id).await? {
+    let Some(message_id) = attachment.session_message_id.filter(|_| !attachment.is_draft) else {
+      continue;
+    };
+    attachments_by_message
+      .entry(message_id)
+      .or_default()
+      .push(ExportedAttachment {
+        attachment_type: attachment.attachment_type,
+        title: attachment.title,
+        path: attachment.path,
+        mime_type: attachment.mime_type,
+      });
+  }
+
+  let messages = sessions::list_session_messages(pool, session_id)
+    .await?
+    .into_iter()
+    .filter(|message| message.sent_at.is_some())
+    .map(|message| ExportedMessage {
+      attachments: attachments_by_message.remove(&message.id).unwrap_or_default(),
+      metadata: message
+        .metadata_json
+        .as_deref()
+        .and_then(|raw| serde_json::from_str(raw).ok()),
+      id: message.id,
+      turn_id: message.turn_id,
+      role: message.role,
+      content: message.content,
+      sent_at: message.sent_at,
+      cancelled_at: message.cancelled_at,
+    })
+    .collect();
+
+  Ok(SessionExport {
+    version: SESSION_EXPORT_VERSION,
+    exported_at,
+    session: ExportedSession {
+      id: session.id,
+      title: session.title,
+      agent_type: session.agent_type,
+      model: session.model,
+    },
+    messages,
+    tool_calls: tool_calls::list_session_tool_calls(pool, session_id, None).await?,
+  })
+}
+
+/// Render an export as a Markdown transcript grouped by turn.
+pub fn render_markdown(export: &SessionExport) -> String {
+  let mut out = String::new();
+  let title = export.session.title.as_deref().unwrap_or("Chat");
+  let _ = writeln!(out, "# {title}\n");
+  let agent = match export.session.model.as_deref() {
+    Some(model) => format!("{} ({model})", export.session.agent_type),
+    None => export.session.agent_type.clone(),
+  };
+  let _ = writeln!(out, "- Agent: {agent}");
+  let _ = writeln!(out, "- Session: `{}`", export.session.id);
+  let _ = writeln!(out, "- Exported: {}", export.exported_at);
+
+  let mut calls_by_turn: HashMap<i64, Vec<&SessionToolCallRecord>> = HashMap::new();
+  for call in &export.tool_calls {
+    calls_by_turn.entry(call.turn_id).or_default().push(call);
+  }
+
+  let mut current_turn = None;
+  for (index, message) in export.messages.iter().enumerate() {
+    if current_turn != Some(message.turn_id) {
+      current_turn = Some(message.turn_id);
+      let _ = writeln!(out, "\n## Turn {}", message.turn_id);
+    }
+    let role = match message.role.as_str() {
+      "user" => "User",
+      "assistant" => "Assistant",
+      "system" => "System",
+      other => other,
+    };
+    let cancelled = if message.cancelled_at.is_some() { " (cancelled)" } else { "" };
+    let _ = writeln!(out, "\n### {role}{cancelled}\n\n{}", message.content.trim_end());
+
+    if !message.attachments.is_empty() {
+      let _ = writeln!(out, "\n**Attachments**\n");
+      for attachment in &message.attachments {
+        let label = attachment.title.as_deref().unwrap_or(&attachment.attachment_type);
+        match attachment.path.as_deref() {
+          Some(path) => {
+            let _ = writeln!(out, "- {label} (`{path}`)");
+          }
+          None => {
+            let _ = writeln!(out, "- {label}");
+          }
+        }
+      }
+    }
+
+    let metadata = message.metadata.as_ref();
+    if let Some(summary) = metadata
+      .and_then(|value| value.get("toolSummary"))
+      .and_then(Value::as_object)
+      .filter(|summary| !summary.is_empty())
+    {
+      let counts: Vec<String> = summary
+        .iter()
+        .map(|(tool, count)| format!("{tool} × {count}"))
+        .collect();
+      let _ = writeln!(out, "\n**Tool summary:** {}", counts.join(", "));
+    }
+
+    let turn_ends = export
+      .messages
+      .get(index + 1)
+      .is_none_or(|next| next.turn_id != message.turn_id);
+    if turn_ends {
+      if let Some(calls) = calls_by_turn.remove(&message.turn_id) {
+        let _ = writeln!(out, "\n**Tool calls**\n");
+        for call in calls {
+          let duration = call
+            .duration_ms
+            .map(|ms| format!(", {ms} ms"))
+            .unwrap_or_default();
+          let _ = writeln!(out, "- `{}` ({}{duration})", call.tool_name, call.status);
+          if let Some(input) = call.input_json.as_deref() {
+            let input = truncate_chars(input, MAX_MARKDOWN_TOOL_INPUT_CHARS);
+            let _ = writeln!(out, "\n{}", indent(&fenced(&input, "json"), "  "));
+          }
+        }
+      }
+    }
+
+    if let Some(diff_stat) = metadata
+      .and_then(|value| value.get("diffStat"))
+      .and_then(Value::as_str)
+    {
+      let _ = writeln!(out, "\n**Changes**\n\n{}", fenced(diff_stat, "text"));
+    }
+    if let Some(diff) = metadata
+      .and_then(|value| value.get("diff"))
+      .and_then(Value::as_str)
+    {
+      let _ = writeln!(out, "\n{}", fenced(diff, "diff"));
+    }
+  }
+  out
+}
+
+/// Recreate an exported session in `workspace_id` with fresh ids. Checkpoints,
+/// agent resume ids and attachment paths are machine-specific and are not
+/// carried over; attachments come back as references without a file.
+pub async fn import_session_export(
+  pool: &SqlitePool,
+  workspace_id: &str,
+  export: SessionExport,
+) -> Result<SessionRecord, DbError> {
+  validate_export(&export)?;
+  let mut tx = pool.begin().await?;
+  let session_id = random_id(&mut tx).await?;
+  sqlx::query(
+    "INSERT INTO sessions (id, workspace_id, title, agent_type, model, status)
+     VALUES (?, ?, ?, ?, ?, 'idle')",
+  )
+  .bind(&session_id)
+  .bind(workspace_id)
+  .bind(&export.session.title)
+  .bind(&export.session.agent_type)
+  .bind(&export.session.model)
+  .execute(&mut *tx)
+  .await?;
+
+  let mut message_ids = HashMap::new();
+  for message in &export.messages {
+    let message_id = random_id(&mut tx).await?;
+    sqlx::query(
+      "INSERT INTO session_messages
+        (id, session_id, turn_id, role, content, sent_at, cancelled_at, metadata_json)
+       VALUES (?, ?, ?, ?, ?, COALESCE(?, CURRENT_TIMESTAMP), ?, ?)",
+    )
+    .bind(&message_id)
+    .bind(&session_id)
+    .bind(message.turn_id)
+    .bind(&message.role)
+    .bind(&message.content)
+    .bind(&message.sent_at)
+    .bind(&message.cancelled_at)
+    .bind(message.metadata.as_ref().map(Value::to_string))
+    .execute(&mut *tx)
+    .await?;
+    for attachment in &message.attachments {
+      sqlx::query(
+        "INSERT INTO attachments
+          (id, session_id, session_message_id, type, title, path, mime_type, is_draft)
+         VALUES (lower(hex(randomblob(16))), ?, ?, ?, ?, ?, ?, 0)",
+      )
+      .bind(&session_id)
+      .bind(&message_id)
+      .bind(&attachment.attachment_type)
+      // Keep the file name as the label once the foreign path is gone.
+      .bind(attachment.title.clone().or_else(|| {
+        attachment
+          .path
+          .as_deref()
+          .and_then(|path| std::path::Path::new(path).file_name())
+          .map(|name| name.to_string_lossy().into_owned())
+      }))
+      .bind(None::<String>)
+      .bind(&attachment.mime_type)
+      .execute(&mut *tx)
+      .await?;
+    }
+    message_ids.insert(message.id.as_str(), message_id);
+  }
+
+  for call in &export.tool_calls {
+    let message_id = call
+      .message_id
+      .as_deref()
+      .and_then(|id| message_ids.get(id));
+    sqlx::query(
+      "INSERT INTO session_tool_calls
+        (id, session_id, turn_id, message_id, call_id, tool_name, input_json, output, status,
+         started_at_ms, finished_at_ms)
+       VALUES (lower(hex(randomblob(16))), ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&session_id)
+    .bind(call.turn_id)
+    .bind(message_id)
+    .bind(&call.call_id)
+    .bind(&call.tool_name)
+    .bind(&call.input_json)
+    .bind(&call.output)
+    .bind(&call.status)
+    .bind(call.started_at_ms)
+    .bind(call.finished_at_ms)
+    .execute(&mut *tx)
+    .await?;
+  }
+  tx.commit().await?;
+
+  sessions::get_session(pool, &session_id).await
+}
+
+/// Reject exports this build cannot represent before anything is written.
+fn validate_export(export: &SessionExport) -> Result<(), DbError> {
+  if export.version != SESSION_EXPORT_VERSION {
+    return Err(DbError::Parse(format!(
+      "Unsupported session export version: {}",
+      export.version
+    )));
+  }
+  if !KNOWN_AGENT_TYPES.contains(&export.session.agent_type.as_str()) {
+    return Err(DbError::Parse(format!(
+      "Unknown agent type in session export: {}",
+      export.session.agent_type
+    )));
+  }
+  if let Some(message) = export
+    .messages
+    .iter()
+    .find(|message| !KNOWN_MESSAGE_ROLES.contains(&message.role.as_str()))
+  {
+    return Err(DbError::Parse(format!(
+      "Unknown message role in session export: {}",
+      message.role
+    )));
+  }
+  Ok(())
+}
+
+async fn random_id(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>) -> Result<String, DbError> {
+  let id: String = sqlx::query_scalar("SELECT lower(hex(randomblob(16)))")
+    .fetch_one(&mut **tx)
+    .await?;
+  Ok(id)
+}
+
+/// Wrap `body` in a code fence longer than any backtick run inside it.
+fn fenced(body: &str, lang: &str) -> String {
+  let mut longest = 0;
+  let mut run = 0;
+  for ch in body.chars() {
+    run = if ch == '`' { run + 1 } else { 0 };
+    longest = longest.max(run);
+  }
+  let fence = "`".repeat(longest.max(2) + 1);
+  format!("{fence}{lang}\n{}\n{fence}", body.trim_end())
+}
+
+fn indent(text: &str, prefix: &str) -> String {
+  text
+    .lines()
+    .map(|line| format!("{prefix}{line}"))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+  match text.char_indices().nth(max_chars) {
+    Some((index, _)) => format!("{}…", &text[..index]),
+    None => text.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    fenced, render_markdown, validate_export, ExportedMessage, ExportedSession, SessionExport,
+  };
+  use crate::tool_calls::SessionToolCallRecord;
+  use serde_json::json;
+
+  fn message(turn_id: i64, role: &str, content: &str) -> ExportedMessage {
+    ExportedMessage {
+      id: format!("{role}-{turn_id}"),
+      turn_id,
+      role: role.to_string(),
+      content: content.to_string(),
+      sent_at: Some("2026-01-01 10:00:00".to_string()),
+      cancelled_at: None,
+      metadata: None,
+      attachments: Vec::new(),
+    }
+  }
+
+  #[test]
+  fn fences_code_containing_backticks() {
+    assert_eq!(fenced("a\n", "text"), "```text\na\n```");
+    assert_eq!(fenced("x ```` y", ""), "`````\nx ```` y\n`````");
+  }
+
+  #[test]
+  fn rejects_unknown_agents_and_roles() {
+    let export = |agent_type: &str, role: &str| SessionExport {
+      version: 1,
+      exported_at: "2026-01-02T00:00:00Z".to_string(),
+      session: ExportedSession {
+        id: "s1".to_string(),
+        title: None,
+        agent_type: agent_type.to_string(),
+        model: None,
+      },
+      messages: vec![message(1, role, "hi")],
+      tool_calls: Vec::new(),
+    };
+    assert!(validate_export(&export("codex", "user")).is_ok());
+    assert!(validate_export(&export("gemini", "user")).is_err());
+    assert!(validate_export(&export("claude", "tool")).is_err());
+  }
+
+  #[test]
+  fn renders_turns_with_tool_calls_and_diffs() {
+    let mut reply = message(1, "assistant", "Listed the files.");
+    reply.metadata = Some(json!({ "diffStat": " a.rs | 2 +-", "diff": "-old\n+new" }));
+    let export = SessionExport {
+      version: 1,
+      exported_at: "2026-01-02T00:00:00Z".to_string(),
+      session: ExportedSession {
+        id: "s1".to_string(),
+        title: Some("Fix build".to_string()),
+        agent_type: "claude".to_string(),
+        model: None,
+      },
+      messages: vec![message(1, "user", "ls please"), reply, message(2, "user", "thanks")],
+      tool_calls: vec![SessionToolCallRecord {
+        id: "t1".to_string(),
+        session_id: "s1".to_string(),
+        turn_id: 1,
+        message_id: None,
+        call_id: "toolu_1".to_string(),
+        tool_name: "Bash".to_string(),
+        input_json: Some("{\"command\":\"ls\"}".to_string()),
+        output: None,
+        status: "completed".to_string(),
+        started_at_ms: 0,
+        finished_at_ms: Some(40),
+        duration_ms: Some(40),
+      }],
+    };
+    let markdown = render_markdown(&export);
+    assert!(markdown.starts_with("# Fix build\n"));
+    let turn_one = markdown.find("## Turn 1").unwrap();
+    let tool_calls = markdown.find("- `Bash` (completed, 40 ms)").unwrap();
+    let turn_two = markdown.find("## Turn 2").unwrap();
+    assert!(turn_one < tool_calls && tool_calls < turn_two);
+    assert!(markdown.contains("```diff\n-old\n+new\n```"));
+  }
+}
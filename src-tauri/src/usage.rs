@@ -0,0 +1,338 @@
+use crate::db::DbError;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
+
+/// Token counts reported by an agent for one finished turn.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TurnUsage {
+  pub model: Option<String>,
+  pub input_tokens: i64,
+  pub output_tokens: i64,
+  pub cache_read_tokens: i64,
+  pub cache_creation_tokens: i64,
+  pub cost_usd: Option<f64>,
+}
+
+impl TurnUsage {
+  /// Tokens the turn held in context, used when neither `/context` nor a
+  /// per-message usage (Codex has none) can report it.
+  pub fn context_tokens(&self) -> i64 {
+    self.input_tokens + self.cache_read_tokens + self.cache_creation_tokens + self.output_tokens
+  }
+}
+
+/// Data required to store the usage of one turn.
+pub struct NewTurnUsage<'a> {
+  pub session_id: &'a str,
+  pub turn_id: i64,
+  pub workspace_id: &'a str,
+  pub repo_id: &'a str,
+  pub agent_type: &'a str,
+  pub usage: &'a TurnUsage,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReportRow {
+  pub key: String,
+  pub label: String,
+  pub turns: i64,
+  pub input_tokens: i64,
+  pub output_tokens: i64,
+  pub cache_read_tokens: i64,
+  pub cache_creation_tokens: i64,
+  pub cost_usd: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReport {
+  pub range: String,
+  pub group_by: String,
+  pub rows: Vec<UsageReportRow>,
+  pub total: UsageReportRow,
+}
+
+/// Usage from a turn's closing message: Claude's `result` message or Codex's
+/// `turn.completed` event. Returns `None` for every other stream message.
+pub fn parse_turn_usage(agent_type: &str, data: &Value) -> Option<TurnUsage> {
+  let kind = data.get("type").and_then(Value::as_str)?;
+  let usage = data.get("usage")?;
+  let count = |key: &str| usage.get(key).and_then(Value::as_i64).unwrap_or(0);
+  match (agent_type, kind) {
+    ("claude", "result") => Some(TurnUsage {
+      model: data.get("modelUsage").and_then(Value::as_object).and_then(primary_model),
+      input_tokens: count("input_tokens"),
+      output_tokens: count("output_tokens"),
+      cache_read_tokens: count("cache_read_input_tokens"),
+      cache_creation_tokens: count("cache_creation_input_tokens"),
+      cost_usd: data.get("total_cost_usd").and_then(Value::as_f64),
+    }),
+    ("codex", "turn.completed") => {
+      // Codex counts cached tokens as part of the input.
+      let cached = count("cached_input_tokens");
+      Some(TurnUsage {
+        model: None,
+        input_tokens: (count("input_tokens") - cached).max(0),
+        output_tokens: count("output_tokens"),
+        cache_read_tokens: cached,
+        cache_creation_tokens: 0,
+        cost_usd: None,
+      })
+    }
+    _ => None,
+  }
+}
+
+/// The model that did most of the turn's work. `modelUsage` is keyed by every
+/// model that served the turn, including small helpers, so take the one with the
+/// largest cost and then the most output tokens.
+fn primary_model(models: &Map<String, Value>) -> Option<String> {
+  let weight = |usage: &Value| {
+    (
+      usage.get("costUSD").and_then(Value::as_f64).unwrap_or(0.0),
+      usage.get("outputTokens").and_then(Value::as_i64).unwrap_or(0),
+    )
+  };
+  models
+    .iter()
+    .max_by(|(_, a), (_, b)| {
+      let (a_cost, a_output) = weight(a);
+      let (b_cost, b_output) = weight(b);
+      a_cost.total_cmp(&b_cost).then(a_output.cmp(&b_output))
+    })
+    .map(|(model, _)| model.clone())
+}
+
+/// Tokens in context after a Claude assistant message, from that API call's own
+/// usage. The `result` usage sums every call of the turn, so it overcounts.
+pub fn parse_message_context_tokens(agent_type: &str, data: &Value) -> Option<i64> {
+  if agent_type != "claude" || data.get("type").and_then(Value::as_str) != Some("assistant") {
+    return None;
+  }
+  let usage = data.get("message")?.get("usage")?;
+  let count = |key: &str| usage.get(key).and_then(Value::as_i64).unwrap_or(0);
+  Some(
+    count("input_tokens")
+      + count("cache_read_input_tokens")
+      + count("cache_creation_input_tokens")
+      + count("output_tokens"),
+  )
+}
+
+/// Whether a Claude stream message marks a context compaction.
+pub fn is_compaction_message(data: &Value) -> bool {
+  data.get("type").and_then(Value::as_str) == Some("system")
+    && data.get("subtype").and_then(Value::as_str) == Some("compact_boundary")
+}
+
+/// Tokens in use according to the output of Claude's `/context` command, which
+/// reports them as `used/limit tokens` or `**Tokens:** used / limit`.
+pub fn parse_context_tokens(output: &str) -> Option<i64> {
+  output
+    .lines()
+    .filter(|line| line.to_ascii_lowercase().contains("token"))
+    .find_map(|line| {
+      line.match_indices('/').find_map(|(index, _)| {
+        let used = parse_token_count(line[..index].trim_end().rsplit(' ').next()?)?;
+        let limit = parse_token_count(line[index + 1..].trim_start().split(' ').next()?)?;
+        (limit >= 1000 && used <= limit).then_some(used)
+      })
+    })
+}
+
+/// Parse counts such as `18,500`, `45.2k` or `1M`.
+fn parse_token_count(raw: &str) -> Option<i64> {
+  let raw = raw.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '.');
+  let (number, multiplier) = match raw.chars().last()? {
+    'k' | 'K' => (&raw[..raw.len() - 1], 1_000.0),
+    'm' | 'M' => (&raw[..raw.len() - 1], 1_000_000.0),
+    _ => (raw, 1.0),
+  };
+  let value: f64 = number.replace(',', "").parse().ok()?;
+  Some((value * multiplier).round() as i64)
+}
+
+/// Collect the text of a `/context` reply, whatever message shape it came in.
+pub fn collect_text(value: &Value, out: &mut String) {
+  match value {
+    Value::String(text) => {
+      out.push_str(text);
+      out.push('\n');
+    }
+    Value::Array(items) => items.iter().for_each(|item| collect_text(item, out)),
+    Value::Object(map) => map.values().for_each(|item| collect_text(item, out)),
+    _ => {}
+  }
+}
+
+pub async fn record_turn_usage(pool: &SqlitePool, entry: NewTurnUsage<'_>) -> Result<(), DbError> {
+  sqlx::query(
+    "INSERT INTO session_turn_usage
+      (session_id, turn_id, workspace_id, repo_id, agent_type, model, input_tokens, output_tokens,
+       cache_read_tokens, cache_creation_tokens, cost_usd)
+     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+     ON CONFLICT(session_id, turn_id) DO UPDATE SET
+       model = excluded.model,
+       input_tokens = excluded.input_tokens,
+       output_tokens = excluded.output_tokens,
+       cache_read_tokens = excluded.cache_read_tokens,
+       cache_creation_tokens = excluded.cache_creation_tokens,
+       cost_usd = excluded.cost_usd",
+  )
+  .bind(entry.session_id)
+  .bind(entry.turn_id)
+  .bind(entry.workspace_id)
+  .bind(entry.repo_id)
+  .bind(entry.agent_type)
+  .bind(&entry.usage.model)
+  .bind(entry.usage.input_tokens)
+  .bind(entry.usage.output_tokens)
+  .bind(entry.usage.cache_read_tokens)
+  .bind(entry.usage.cache_creation_tokens)
+  .bind(entry.usage.cost_usd)
+  .execute(pool)
+  .await?;
+  Ok(())
+}
+
+/// Roll usage up over `range` (`day`, `week`, `month` or `all`) grouped by
+/// `session`, `workspace`, `repo` or `model`, most expensive first.
+pub async fn usage_report(
+  pool: &SqlitePool,
+  range: &str,
+  group_by: &str,
+) -> Result<UsageReport, DbError> {
+  let since = match range {
+    "day" => Some("-1 day"),
+    "week" => Some("-7 days"),
+    "month" => Some("-30 days"),
+    "all" => None,
+    other => return Err(DbError::Parse(format!("Unknown usage range: {other}"))),
+  };
+  let (key, label) = match group_by {
+    "session" => ("u.session_id", "COALESCE(s.title, u.session_id)"),
+    "workspace" => (
+      "u.workspace_id",
+      "COALESCE(w.directory_name, w.branch, u.workspace_id)",
+    ),
+    "repo" => ("u.repo_id", "COALESCE(r.name, u.repo_id)"),
+    "model" => ("COALESCE(u.model, u.agent_type)", "COALESCE(u.model, u.agent_type)"),
+    other => return Err(DbError::Parse(format!("Unknown usage grouping: {other}"))),
+  };
+  let rows = sqlx::query_as::<_, UsageReportRow>(&format!(
+    "SELECT {key} AS key,
+            {label} AS label,
+            COUNT(*) AS turns,
+            COALESCE(SUM(u.input_tokens), 0) AS input_tokens,
+            COALESCE(SUM(u.output_tokens), 0) AS output_tokens,
+            COALESCE(SUM(u.cache_read_tokens), 0) AS cache_read_tokens,
+            COALESCE(SUM(u.cache_creation_tokens), 0) AS cache_creation_tokens,
+            COALESCE(SUM(u.cost_usd), 0.0) AS cost_usd
+     FROM session_turn_usage u
+     LEFT JOIN sessions s ON s.id = u.session_id
+     LEFT JOIN workspaces w ON w.id = u.workspace_id
+     LEFT JOIN repos r ON r.id = u.repo_id
+     WHERE (? IS NULL OR u.created_at >= datetime('now', ?))
+     GROUP BY {key}
+     ORDER BY cost_usd DESC, input_tokens + output_tokens DESC"
+  ))
+  .bind(since)
+  .bind(since)
+  .fetch_all(pool)
+  .await?;
+
+  let total = rows.iter().fold(
+    UsageReportRow {
+      key: "total".to_string(),
+      label: "Total".to_string(),
+      turns: 0,
+      input_tokens: 0,
+      output_tokens: 0,
+      cache_read_tokens: 0,
+      cache_creation_tokens: 0,
+      cost_usd: 0.0,
+    },
+    |mut total, row| {
+      total.turns += row.turns;
+      total.input_tokens += row.input_tokens;
+      total.output_tokens += row.output_tokens;
+      total.cache_read_tokens += row.cache_read_tokens;
+      total.cache_creation_tokens += row.cache_creation_tokens;
+      total.cost_usd += row.cost_usd;
+      total
+    },
+  );
+  Ok(UsageReport {
+    range: range.to_string(),
+    group_by: group_by.to_string(),
+    rows,
+    total,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{parse_context_tokens, parse_message_context_tokens, parse_turn_usage};
+  use serde_json::json;
+
+  #[test]
+  fn parses_turn_usage_from_result_messages() {
+    let claude = parse_turn_usage(
+      "claude",
+      &json!({
+        "type": "result",
+        "subtype": "success",
+        "total_cost_usd": 0.042,
+        "usage": { "input_tokens": 12, "output_tokens": 340,
+                   "cache_read_input_tokens": 18000, "cache_creation_input_tokens": 2100 },
+        "modelUsage": {
+          "claude-haiku-4-5": { "costUSD": 0.002, "outputTokens": 40 },
+          "claude-sonnet-4-5": { "costUSD": 0.04, "outputTokens": 300 }
+        }
+      }),
+    )
+    .unwrap();
+    assert_eq!(claude.model.as_deref(), Some("claude-sonnet-4-5"));
+    assert_eq!(claude.input_tokens, 12);
+    assert_eq!(claude.cache_read_tokens, 18000);
+    assert_eq!(claude.cache_creation_tokens, 2100);
+    assert_eq!(claude.cost_usd, Some(0.042));
+
+    let codex = parse_turn_usage(
+      "codex",
+      &json!({
+        "type": "turn.completed",
+        "usage": { "input_tokens": 5000, "cached_input_tokens": 4000, "output_tokens": 120 }
+      }),
+    )
+    .unwrap();
+    assert_eq!(codex.input_tokens, 1000);
+    assert_eq!(codex.cache_read_tokens, 4000);
+    assert_eq!(codex.context_tokens(), 5120);
+
+    assert!(parse_turn_usage("claude", &json!({ "type": "assistant" })).is_none());
+
+    let message = json!({
+      "type": "assistant",
+      "message": { "usage": { "input_tokens": 3, "output_tokens": 50,
+                              "cache_read_input_tokens": 9000, "cache_creation_input_tokens": 700 } }
+    });
+    assert_eq!(parse_message_context_tokens("claude", &message), Some(9753));
+    assert_eq!(parse_message_context_tokens("codex", &message), None);
+  }
+
+  #[test]
+  fn parses_context_command_output() {
+    assert_eq!(
+      parse_context_tokens("claude-sonnet-4-5 · 45.2k/200k tokens (23%)"),
+      Some(45_200)
+    );
+    assert_eq!(
+      parse_context_tokens("## Context Usage\n**Tokens:** 18,500 / 200,000 (9%)"),
+      Some(18_500)
+    );
+    assert_eq!(parse_context_tokens("src/main.rs uses 3 tokens"), None);
+  }
+}
@@ -188,6 +188,34 @@ pub async fn find_active_workspace_for_branch(
   Ok(row)
 }
 
+pub async fn list_active_workspace_branches(
+  pool: &SqlitePool,
+  repo_id: &str,
+) -> Result<Vec<(String, String)>, DbError> {
+  let rows = sqlx::query_as::<_, (String, String)>(
+    "SELECT id, branch FROM workspaces WHERE repo_id = ? AND state = ?",
+  )
+  .bind(repo_id)
+  .bind(WORKSPACE_STATE_ACTIVE)
+  .fetch_all(pool)
+  .await?;
+  Ok(rows)
+}
+
+/// Branch of every workspace in the repo, archived ones included, as `(id, branch)`.
+pub async fn list_workspace_branches(
+  pool: &SqlitePool,
+  repo_id: &str,
+) -> Result<Vec<(String, String)>, DbError> {
+  let rows = sqlx::query_as::<_, (String, String)>(
+    "SELECT id, branch FROM workspaces WHERE repo_id = ?",
+  )
+  .bind(repo_id)
+  .fetch_all(pool)
+  .await?;
+  Ok(rows)
+}
+
 pub async fn insert_workspace(pool: &SqlitePool, new_workspace: NewWorkspace) -> Result<WorkspaceRecord, DbError> {
   let result = sqlx::query(
     "INSERT INTO workspaces
@@ -0,0 +1,155 @@
+import { useEffect, useRef, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Dialog, DialogContent } from '@/components/ui/dialog';
+import { Input } from '@/components/ui/input';
+import type { SessionSearchFilters, SessionSearchResult } from '@/types/session';
+
+type SessionSearchProps = {
+  open: boolean;
+  workspaceId?: string | null;
+  workspaceLabel?: string;
+  describeWorkspace: (workspaceId: string) => string;
+  onOpenChange: (open: boolean) => void;
+  onSelectResult: (result: SessionSearchResult) => void;
+};
+
+const SEARCH_DEBOUNCE_MS = 150;
+
+export default function SessionSearch({
+  open,
+  workspaceId,
+  workspaceLabel,
+  describeWorkspace,
+  onOpenChange,
+  onSelectResult,
+}: SessionSearchProps) {
+  const [query, setQuery] = useState('');
+  const [currentWorkspaceOnly, setCurrentWorkspaceOnly] = useState(false);
+  const [results, setResults] = useState<SessionSearchResult[]>([]);
+  const [error, setError] = useState<string | null>(null);
+  const inputRef = useRef<HTMLInputElement>(null);
+
+  useEffect(() => {
+    if (open) {
+      setQuery('');
+      setResults([]);
+      setError(null);
+    }
+  }, [open]);
+
+  useEffect(() => {
+    if (!open) {
+      return;
+    }
+    const id = window.setTimeout(() => {
+      inputRef.current?.focus();
+    }, 10);
+    return () => window.clearTimeout(id);
+  }, [open]);
+
+  useEffect(() => {
+    if (!open || !query.trim()) {
+      setResults([]);
+      return;
+    }
+    let active = true;
+    const filters: SessionSearchFilters = {
+      workspaceId: currentWorkspaceOnly ? workspaceId : null,
+    };
+    const id = window.setTimeout(() => {
+      invoke<SessionSearchResult[]>('searchSessions', { query, filters })
+        .then((next) => {
+          if (active) {
+            setResults(next);
+            setError(null);
+          }
+        })
+        .catch((err) => {
+          if (active) {
+            setError(String(err));
+          }
+        });
+    }, SEARCH_DEBOUNCE_MS);
+    return () => {
+      active = false;
+      window.clearTimeout(id);
+    };
+  }, [currentWorkspaceOnly, open, query, workspaceId]);
+
+  const handleSelect = (result: SessionSearchResult) => {
+    onSelectResult(result);
+    onOpenChange(false);
+  };
+
+  return (
+    <Dialog open={open} onOpenChange={onOpenChange}>
+      <DialogContent className="max-w-2xl p-0">
+        <div className="border-b border-slate-800 px-4 py-3">
+          <div className="flex items-center justify-between gap-3">
+            <div className="text-xs uppercase tracking-[0.3em] text-slate-500">
+              Search chats
+            </div>
+            {workspaceId ? (
+              <label className="flex items-center gap-2 text-xs text-slate-400">
+                <input
+                  type="checkbox"
+                  checked={currentWorkspaceOnly}
+                  onChange={(event) => setCurrentWorkspaceOnly(event.target.checked)}
+                  className="h-3.5 w-3.5 rounded border-slate-700 bg-slate-950"
+                />
+                <span>Only {workspaceLabel ?? 'this workspace'}</span>
+              </label>
+            ) : null}
+          </div>
+          <Input
+            ref={inputRef}
+            value={query}
+            onChange={(event) => setQuery(event.target.value)}
+            placeholder="Search messages and tool calls..."
+            className="mt-3"
+          />
+        </div>
+        <div className="max-h-96 overflow-auto p-2">
+          {error ? (
+            <div className="px-3 py-6 text-center text-sm text-red-400">{error}</div>
+          ) : results.length === 0 ? (
+            <div className="px-3 py-6 text-center text-sm text-slate-500">
+              {query.trim() ? 'No matches found.' : 'Type to search across all chats.'}
+            </div>
+          ) : (
+            <div className="space-y-1">
+              {results.map((result) => (
+                <button
+                  key={`${result.kind}-${result.sourceId}`}
+                  type="button"
+                  onClick={() => handleSelect(result)}
+                  className="flex w-full flex-col gap-1 rounded-md px-3 py-2 text-left text-sm text-slate-200 transition hover:bg-slate-900"
+                >
+                  <span className="line-clamp-2 whitespace-pre-wrap break-words">
+                    {result.snippet.map((segment, index) =>
+                      segment.highlighted ? (
+                        <mark
+                          key={index}
+                          className="rounded-sm bg-amber-400/20 px-0.5 text-amber-200"
+                        >
+                          {segment.text}
+                        </mark>
+                      ) : (
+                        <span key={index}>{segment.text}</span>
+                      ),
+                    )}
+                  </span>
+                  <span className="text-xs text-slate-500">
+                    {result.sessionTitle ?? 'Untitled chat'} ·{' '}
+                    {describeWorkspace(result.workspaceId)} · Turn {result.turnId} ·{' '}
+                    {result.kind === 'tool_call' ? 'Tool call' : result.role}
+                  </span>
+                </button>
+              ))}
+            </div>
+          )}
+        </div>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -2,9 +2,10 @@ import { useCallback, useEffect, useMemo, useRef, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { getCurrentWindow } from '@tauri-apps/api/window';
-import { open } from '@tauri-apps/plugin-dialog';
+import { open, save } from '@tauri-apps/plugin-dialog';
 import CommandPalette, { type CommandPaletteItem } from '@/components/CommandPalette';
 import FileOpener from '@/components/FileOpener';
+import SessionSearch from '@/components/SessionSearch';
 import TerminalPanel from '@/components/TerminalPanel';
 import { Button } from '@/components/ui/button';
 import {
@@ -20,13 +21,23 @@ import type {
   AttachmentRecord,
   ExitPlanModeEvent,
   SessionErrorEvent,
+  SessionMessageDispatchedEvent,
   SessionMessageEvent,
   SessionMessageRecord,
   SessionPlanModeEvent,
   SessionRecord,
+  SessionSidecarRestartedEvent,
+  SessionContextUsageEvent,
   SessionStatusEvent,
+  SessionToolCallRecord,
 } from '@/types/session';
-import type { FilePreview, WorkspaceInfo } from '@/types/workspace';
+import type {
+  FilePreview,
+  SpotlightRecoveryItem,
+  SpotlightStatus,
+  SpotlightSyncEvent,
+  WorkspaceInfo,
+} from '@/types/workspace';
 import RepositoryPage from './RepositoryPage';
 import SettingsPage from './SettingsPage';
 import WorkspacesPage from './WorkspacesPage';
@@ -358,6 +369,9 @@ const normalizeSessionMessage = (
   streaming: false,
 });
 
+const isQueuedMessage = (message: SessionMessageItem) =>
+  message.role === 'user' && message.sentAt === null;
+
 const formatAgentLabel = (agentType: SessionRecord['agentType']) => {
   if (agentType === 'codex') {
     return 'Codex';
@@ -627,6 +641,7 @@ export default function AppShell() {
   const rightResizeState = useRef<{ startX: number; startWidth: number } | null>(null);
   const [commandPaletteOpen, setCommandPaletteOpen] = useState(false);
   const [fileOpenerOpen, setFileOpenerOpen] = useState(false);
+  const [sessionSearchOpen, setSessionSearchOpen] = useState(false);
   const [fileListError, setFileListError] = useState<string | null>(null);
   const [filePreviewError, setFilePreviewError] = useState<string | null>(null);
   const [filesByWorkspace, setFilesByWorkspace] = useState<Record<string, string[]>>({});
@@ -688,6 +703,9 @@ export default function AppShell() {
   >({});
   const [diffHistoryExpandedByMessage, setDiffHistoryExpandedByMessage] =
     useState<Record<string, boolean>>({});
+  const [toolCallsByMessage, setToolCallsByMessage] = useState<
+    Record<string, SessionToolCallRecord[] | undefined>
+  >({});
   const [githubAuthStatus, setGithubAuthStatus] =
     useState<GithubAuthStatus | null>(null);
   const [githubAuthLoading, setGithubAuthLoading] = useState(false);
@@ -746,6 +764,8 @@ export default function AppShell() {
   const [spotlightLoadingByWorkspace, setSpotlightLoadingByWorkspace] = useState<
     Record<string, boolean>
   >({});
+  const [spotlightRecovery, setSpotlightRecovery] = useState<SpotlightRecoveryItem[]>([]);
+  const [spotlightRecoveryError, setSpotlightRecoveryError] = useState<string | null>(null);
   const [reviewModel, setReviewModel] = useState<string | null>(null);
   const [reviewThinkingLevel, setReviewThinkingLevel] = useState<string | null>(
     null,
@@ -780,6 +800,7 @@ export default function AppShell() {
   const [sessionListError, setSessionListError] = useState<string | null>(null);
   const [sendError, setSendError] = useState<string | null>(null);
   const [resettingTurnId, setResettingTurnId] = useState<number | null>(null);
+  const [forkingTurnId, setForkingTurnId] = useState<number | null>(null);
   const [pendingAskQueue, setPendingAskQueue] = useState<AskUserQuestionEvent[]>(
     [],
   );
@@ -893,7 +914,12 @@ export default function AppShell() {
     if (!activeSessionId) {
       return [];
     }
-    return messagesBySession[activeSessionId] ?? [];
+    const messages = messagesBySession[activeSessionId] ?? [];
+    // Queued prompts stay below the transcript until they are sent.
+    return [
+      ...messages.filter((message) => !isQueuedMessage(message)),
+      ...messages.filter(isQueuedMessage),
+    ];
   }, [activeSessionId, messagesBySession]);
   const activeSessionAttachments = useMemo(() => {
     if (!activeSessionId) {
@@ -942,9 +968,7 @@ export default function AppShell() {
   }, [activeSession, newChatAgentType, newChatModelByAgent]);
   const modelGroupsForSelection = useMemo(() => MODEL_GROUPS, []);
   const canSendMessage =
-    Boolean(activeWorkspaceId) &&
-    composerValue.trim().length > 0 &&
-    activeSessionStatus !== 'running';
+    Boolean(activeWorkspaceId) && composerValue.trim().length > 0;
   const canCancelSession = Boolean(activeSession) && activeSessionStatus === 'running';
   const activeAskRequest = pendingAskQueue[0] ?? null;
   const activeExitRequest = pendingExitQueue[0] ?? null;
@@ -1623,12 +1647,16 @@ export default function AppShell() {
   const loadSpotlightStatus = useCallback(async (workspaceId: string) => {
     setSpotlightErrorByWorkspace((prev) => ({ ...prev, [workspaceId]: null }));
     try {
-      const active = await invoke<boolean>('getSpotlightStatus', {
+      const status = await invoke<SpotlightStatus>('getSpotlightStatus', {
         workspaceId,
       });
       setSpotlightActiveByWorkspace((prev) => ({
         ...prev,
-        [workspaceId]: active,
+        [workspaceId]: status.active,
+      }));
+      setSpotlightErrorByWorkspace((prev) => ({
+        ...prev,
+        [workspaceId]: status.lastError ?? null,
       }));
     } catch (err) {
       setSpotlightErrorByWorkspace((prev) => ({
@@ -1638,6 +1666,15 @@ export default function AppShell() {
     }
   }, []);
 
+  const loadSpotlightRecovery = useCallback(async () => {
+    try {
+      const items = await invoke<SpotlightRecoveryItem[]>('listSpotlightRecovery');
+      setSpotlightRecovery(items);
+    } catch (err) {
+      setSpotlightRecoveryError(String(err));
+    }
+  }, []);
+
   const loadReviewSettings = useCallback(async () => {
     try {
       const entries = await invoke<SettingsEntry[]>('listSettings');
@@ -1808,6 +1845,10 @@ export default function AppShell() {
     void loadReviewSettings();
   }, [loadReviewSettings]);
 
+  useEffect(() => {
+    void loadSpotlightRecovery();
+  }, [loadSpotlightRecovery]);
+
   useEffect(() => {
     const timeoutMap = notesSaveTimeoutRef.current;
     return () => {
@@ -2001,7 +2042,7 @@ export default function AppShell() {
             role: message.role,
             content: message.content,
             metadata,
-            checkpointId: null,
+            checkpointId: message.checkpointId ?? null,
             streaming: message.streaming,
           };
           if (index >= 0) {
@@ -2021,6 +2062,25 @@ export default function AppShell() {
         });
       },
     );
+    const sessionDispatchedUnlisten = listen<SessionMessageDispatchedEvent>(
+      'session-message-dispatched',
+      (event) => {
+        const { sessionId, message } = event.payload;
+        const normalized = normalizeSessionMessage(message);
+        setMessagesBySession((prev) => {
+          const existing = prev[sessionId] ?? [];
+          return {
+            ...prev,
+            [sessionId]: [
+              ...existing.filter((item) => item.id !== normalized.id),
+              normalized,
+            ],
+          };
+        });
+        setSessionErrors((prev) => ({ ...prev, [sessionId]: null }));
+        setSessionStatuses((prev) => ({ ...prev, [sessionId]: 'running' }));
+      },
+    );
     const sessionErrorUnlisten = listen<SessionErrorEvent>(
       'session-error',
       (event) => {
@@ -2036,6 +2096,58 @@ export default function AppShell() {
         setSessionStatuses((prev) => ({ ...prev, [sessionId]: status }));
       },
     );
+    const sessionRestartedUnlisten = listen<SessionSidecarRestartedEvent>(
+      'session-sidecar-restarted',
+      (event) => {
+        const { sessionId, restartCount, reissued } = event.payload;
+        setSessionsByWorkspace((prev) => {
+          const next: typeof prev = {};
+          for (const [workspaceId, list] of Object.entries(prev)) {
+            next[workspaceId] = list.map((session) =>
+              session.id === sessionId
+                ? { ...session, sidecarRestartCount: restartCount }
+                : session,
+            );
+          }
+          return next;
+        });
+        if (reissued) {
+          setSessionErrors((prev) => ({ ...prev, [sessionId]: null }));
+        }
+      },
+    );
+    const sessionContextUsageUnlisten = listen<SessionContextUsageEvent>(
+      'session-context-usage',
+      (event) => {
+        const { sessionId, contextTokenCount, isCompacted } = event.payload;
+        setSessionsByWorkspace((prev) => {
+          const next: typeof prev = {};
+          for (const [workspaceId, list] of Object.entries(prev)) {
+            next[workspaceId] = list.map((session) =>
+              session.id === sessionId
+                ? { ...session, contextTokenCount, isCompacted }
+                : session,
+            );
+          }
+          return next;
+        });
+      },
+    );
+    const sessionUpdatedUnlisten = listen<SessionRecord>('session-updated', (event) => {
+      const updated = event.payload;
+      setSessionsByWorkspace((prev) => {
+        const list = prev[updated.workspaceId];
+        if (!list) {
+          return prev;
+        }
+        return {
+          ...prev,
+          [updated.workspaceId]: list.map((session) =>
+            session.id === updated.id ? { ...session, ...updated } : session,
+          ),
+        };
+      });
+    });
     const sessionPlanModeUnlisten = listen<SessionPlanModeEvent>(
       'session-plan-mode',
       (event) => {
@@ -2054,15 +2166,32 @@ export default function AppShell() {
         }
       },
     );
+    const spotlightSyncUnlisten = listen<SpotlightSyncEvent>('spotlight-sync', (event) => {
+      const { workspaceId, outcome, status } = event.payload;
+      setSpotlightErrorByWorkspace((prev) => ({
+        ...prev,
+        [workspaceId]:
+          outcome === 'failed'
+            ? status.lastError ?? 'Spotlight sync failed'
+            : outcome === 'conflict'
+              ? status.lastSkipReason ?? 'Spotlight sync paused'
+              : null,
+      }));
+    });
     return () => {
       void runOutputUnlisten.then((unlisten) => unlisten());
       void runExitUnlisten.then((unlisten) => unlisten());
       void terminalExitUnlisten.then((unlisten) => unlisten());
       void sessionMessageUnlisten.then((unlisten) => unlisten());
+      void sessionDispatchedUnlisten.then((unlisten) => unlisten());
       void sessionErrorUnlisten.then((unlisten) => unlisten());
       void sessionStatusUnlisten.then((unlisten) => unlisten());
+      void sessionRestartedUnlisten.then((unlisten) => unlisten());
+      void sessionContextUsageUnlisten.then((unlisten) => unlisten());
+      void sessionUpdatedUnlisten.then((unlisten) => unlisten());
       void sessionPlanModeUnlisten.then((unlisten) => unlisten());
       void sessionRequestUnlisten.then((unlisten) => unlisten());
+      void spotlightSyncUnlisten.then((unlisten) => unlisten());
     };
   }, []);
 
@@ -2549,9 +2678,6 @@ export default function AppShell() {
     if (!rawPrompt) {
       return;
     }
-    if (activeSessionStatus === 'running') {
-      return;
-    }
     setSendError(null);
     setActiveTabByWorkspace((prev) => ({
       ...prev,
@@ -2614,14 +2740,15 @@ export default function AppShell() {
         }));
         setPendingIssueId(null);
       }
-      setSessionStatuses((prev) => ({ ...prev, [session.id]: 'running' }));
+      if (!isQueuedMessage(normalized)) {
+        setSessionStatuses((prev) => ({ ...prev, [session.id]: 'running' }));
+      }
     } catch (err) {
       setSendError(String(err));
       setSessionErrors((prev) => ({ ...prev, [session.id]: String(err) }));
     }
   }, [
     activeSession,
-    activeSessionStatus,
     activeWorkspaceId,
     composerValue,
     createSessionForWorkspace,
@@ -2687,7 +2814,9 @@ export default function AppShell() {
         const list = prev[session.id] ?? [];
         return { ...prev, [session.id]: [...list, normalized] };
       });
-      setSessionStatuses((prev) => ({ ...prev, [session.id]: 'running' }));
+      if (!isQueuedMessage(normalized)) {
+        setSessionStatuses((prev) => ({ ...prev, [session.id]: 'running' }));
+      }
       setActiveTabByWorkspace((prev) => ({
         ...prev,
         [activeWorkspaceId]: 'session',
@@ -2751,7 +2880,9 @@ export default function AppShell() {
         const list = prev[session.id] ?? [];
         return { ...prev, [session.id]: [...list, normalized] };
       });
-      setSessionStatuses((prev) => ({ ...prev, [session.id]: 'running' }));
+      if (!isQueuedMessage(normalized)) {
+        setSessionStatuses((prev) => ({ ...prev, [session.id]: 'running' }));
+      }
       setActiveTabByWorkspace((prev) => ({
         ...prev,
         [workspaceId]: 'session',
@@ -2999,8 +3130,20 @@ export default function AppShell() {
     }
     setSendError(null);
     try {
-      await invoke('cancelSession', { sessionId: activeSession.id });
-      setSessionStatuses((prev) => ({ ...prev, [activeSession.id]: 'idle' }));
+      const sessionId = activeSession.id;
+      const cancelled = await invoke<SessionMessageRecord[]>('cancelSession', { sessionId });
+      if (cancelled.length > 0) {
+        const updates = new Map(
+          cancelled.map((message) => [message.id, normalizeSessionMessage(message)]),
+        );
+        setMessagesBySession((prev) => ({
+          ...prev,
+          [sessionId]: (prev[sessionId] ?? []).map(
+            (message) => updates.get(message.id) ?? message,
+          ),
+        }));
+      }
+      setSessionStatuses((prev) => ({ ...prev, [sessionId]: 'idle' }));
     } catch (err) {
       setSendError(String(err));
     }
@@ -3064,6 +3207,128 @@ export default function AppShell() {
     ],
   );
 
+  const handleForkAtTurn = useCallback(
+    async (turnId?: number) => {
+      if (!activeSession || !activeWorkspaceId || turnId === undefined) {
+        return;
+      }
+      const confirmed = globalThis.confirm?.(
+        'Fork the chat at this point? The workspace files will be restored to how they were after this turn.',
+      );
+      if (!confirmed) {
+        return;
+      }
+      setSessionErrors((prev) => ({ ...prev, [activeSession.id]: null }));
+      setForkingTurnId(turnId);
+      try {
+        const session = await invoke<SessionRecord>('forkSession', {
+          payload: { sessionId: activeSession.id, turnId },
+        });
+        await loadSessions();
+        await loadGitStatus(activeWorkspaceId);
+        const selectedDiff = selectedDiffPathByWorkspace[activeWorkspaceId] ?? null;
+        await loadWorkspaceDiff(activeWorkspaceId, selectedDiff);
+        handleSelectSession(session.workspaceId, session.id);
+      } catch (err) {
+        setSessionErrors((prev) => ({
+          ...prev,
+          [activeSession.id]: String(err),
+        }));
+      } finally {
+        setForkingTurnId(null);
+      }
+    },
+    [
+      activeSession,
+      activeWorkspaceId,
+      handleSelectSession,
+      loadGitStatus,
+      loadSessions,
+      loadWorkspaceDiff,
+      selectedDiffPathByWorkspace,
+    ],
+  );
+
+  const handleExportSession = useCallback(
+    async (format: 'markdown' | 'json') => {
+      if (!activeSession) {
+        return;
+      }
+      const extension = format === 'markdown' ? 'md' : 'json';
+      const baseName = (activeSession.title ?? 'chat').replace(/[^\w.-]+/g, '-');
+      try {
+        const path = await save({
+          title: 'Export chat',
+          defaultPath: `${baseName}.${extension}`,
+          filters: [
+            { name: format === 'markdown' ? 'Markdown' : 'JSON', extensions: [extension] },
+          ],
+        });
+        if (!path) {
+          return;
+        }
+        await invoke<string>('exportSession', {
+          sessionId: activeSession.id,
+          format,
+          path,
+        });
+      } catch (err) {
+        setSessionErrors((prev) => ({ ...prev, [activeSession.id]: String(err) }));
+      }
+    },
+    [activeSession],
+  );
+
+  const handleRenameSession = useCallback(async () => {
+    if (!activeSession) {
+      return;
+    }
+    const title = globalThis.prompt('Rename chat', activeSession.title ?? '');
+    if (title === null || !title.trim()) {
+      return;
+    }
+    try {
+      const session = await invoke<SessionRecord>('renameSession', {
+        sessionId: activeSession.id,
+        title,
+      });
+      setSessionsByWorkspace((prev) => ({
+        ...prev,
+        [session.workspaceId]: (prev[session.workspaceId] ?? []).map((item) =>
+          item.id === session.id ? session : item,
+        ),
+      }));
+    } catch (err) {
+      setSessionErrors((prev) => ({ ...prev, [activeSession.id]: String(err) }));
+    }
+  }, [activeSession]);
+
+  const handleImportSession = useCallback(async () => {
+    if (!activeWorkspaceId) {
+      return;
+    }
+    setSendError(null);
+    try {
+      const result = await open({
+        multiple: false,
+        title: 'Import chat',
+        filters: [{ name: 'JSON', extensions: ['json'] }],
+      });
+      if (!result) {
+        return;
+      }
+      const path = Array.isArray(result) ? result[0] : result;
+      const session = await invoke<SessionRecord>('importSession', {
+        workspaceId: activeWorkspaceId,
+        path,
+      });
+      await loadSessions();
+      handleSelectSession(session.workspaceId, session.id);
+    } catch (err) {
+      setSendError(String(err));
+    }
+  }, [activeWorkspaceId, handleSelectSession, loadSessions]);
+
   const handlePermissionModeChange = useCallback(
     async (mode: string) => {
       if (!activeSession) {
@@ -3387,6 +3652,86 @@ export default function AppShell() {
     }
   }, []);
 
+  const handleToggleToolCalls = useCallback(
+    async (message: SessionMessageItem) => {
+      if (toolCallsByMessage[message.id]) {
+        setToolCallsByMessage((prev) => ({ ...prev, [message.id]: undefined }));
+        return;
+      }
+      if (message.turnId === undefined) {
+        return;
+      }
+      try {
+        const calls = await invoke<SessionToolCallRecord[]>('listSessionToolCalls', {
+          sessionId: message.sessionId,
+          turnId: message.turnId,
+        });
+        setToolCallsByMessage((prev) => ({ ...prev, [message.id]: calls }));
+      } catch (err) {
+        setSessionErrors((prev) => ({ ...prev, [message.sessionId]: String(err) }));
+      }
+    },
+    [toolCallsByMessage],
+  );
+
+  const handleMoveQueuedMessage = useCallback(
+    async (messageId: string, offset: number) => {
+      if (!activeSession) {
+        return;
+      }
+      const sessionId = activeSession.id;
+      const queuedIds = activeSessionMessages
+        .filter(isQueuedMessage)
+        .map((message) => message.id);
+      const index = queuedIds.indexOf(messageId);
+      const target = index + offset;
+      if (index < 0 || target < 0 || target >= queuedIds.length) {
+        return;
+      }
+      [queuedIds[index], queuedIds[target]] = [queuedIds[target], queuedIds[index]];
+      try {
+        const queued = await invoke<SessionMessageRecord[]>('reorderQueuedMessages', {
+          sessionId,
+          messageIds: queuedIds,
+        });
+        setMessagesBySession((prev) => {
+          const existing = prev[sessionId] ?? [];
+          return {
+            ...prev,
+            [sessionId]: [
+              ...existing.filter((message) => !isQueuedMessage(message)),
+              ...queued.map(normalizeSessionMessage),
+            ],
+          };
+        });
+      } catch (err) {
+        setSessionErrors((prev) => ({ ...prev, [sessionId]: String(err) }));
+      }
+    },
+    [activeSession, activeSessionMessages],
+  );
+
+  const handleCancelQueuedMessage = useCallback(
+    async (messageId: string) => {
+      if (!activeSession) {
+        return;
+      }
+      const sessionId = activeSession.id;
+      try {
+        await invoke('cancelQueuedMessage', { sessionId, messageId });
+        setMessagesBySession((prev) => ({
+          ...prev,
+          [sessionId]: (prev[sessionId] ?? []).filter(
+            (message) => message.id !== messageId,
+          ),
+        }));
+      } catch (err) {
+        setSessionErrors((prev) => ({ ...prev, [sessionId]: String(err) }));
+      }
+    },
+    [activeSession],
+  );
+
   const handleComposerDragOver = useCallback(
     (event: React.DragEvent<HTMLDivElement>) => {
       event.preventDefault();
@@ -3529,6 +3874,11 @@ export default function AppShell() {
       const key = event.key.toLowerCase();
       const primary = isMac ? event.metaKey : event.ctrlKey;
 
+      if (primary && event.shiftKey && key === 'f') {
+        event.preventDefault();
+        setSessionSearchOpen(true);
+        return;
+      }
       if (primary && key === 'k') {
         event.preventDefault();
         setCommandPaletteOpen(true);
@@ -3892,6 +4242,30 @@ export default function AppShell() {
     }
   }, [activeSpotlightActive, activeWorkspaceId]);
 
+  const handleRecoverSpotlight = useCallback(
+    async (item: SpotlightRecoveryItem, action: 'resume' | 'rollback') => {
+      setSpotlightRecoveryError(null);
+      try {
+        await invoke('recoverSpotlight', {
+          rollbackCheckpointId: item.rollbackCheckpointId,
+          action,
+        });
+        setSpotlightRecovery((prev) =>
+          prev.filter((entry) => entry.rollbackCheckpointId !== item.rollbackCheckpointId),
+        );
+        if (action === 'resume') {
+          setSpotlightActiveByWorkspace((prev) => ({
+            ...prev,
+            [item.workspaceId]: true,
+          }));
+        }
+      } catch (err) {
+        setSpotlightRecoveryError(String(err));
+      }
+    },
+    [],
+  );
+
   const handleToggleLinkedWorkspace = useCallback(
     async (targetId: string, enabled: boolean) => {
       if (!activeWorkspaceId) {
@@ -4172,8 +4546,55 @@ export default function AppShell() {
         group: 'Navigation',
         onSelect: () => setActiveView('settings'),
       },
+      {
+        id: 'nav-search-chats',
+        label: 'Search chats',
+        description: isMac ? 'Cmd+Shift+F' : 'Ctrl+Shift+F',
+        group: 'Navigation',
+        keywords: ['find', 'messages', 'history'],
+        onSelect: () => setSessionSearchOpen(true),
+      },
     ];
 
+    if (activeSession) {
+      items.push(
+        {
+          id: 'chat-rename',
+          label: 'Rename chat',
+          description: activeSession.title ?? 'Chat',
+          group: 'Chat',
+          keywords: ['title', 'name'],
+          onSelect: () => void handleRenameSession(),
+        },
+        {
+          id: 'chat-export-markdown',
+          label: 'Export chat as Markdown',
+          description: activeSession.title ?? 'Chat',
+          group: 'Chat',
+          keywords: ['transcript', 'download', 'save'],
+          onSelect: () => void handleExportSession('markdown'),
+        },
+        {
+          id: 'chat-export-json',
+          label: 'Export chat as JSON',
+          description: activeSession.title ?? 'Chat',
+          group: 'Chat',
+          keywords: ['transcript', 'download', 'save'],
+          onSelect: () => void handleExportSession('json'),
+        },
+      );
+    }
+    if (activeWorkspaceId) {
+      items.push({
+        id: 'chat-import-json',
+        label: 'Import chat from JSON',
+        description: workspaceLabel,
+        group: 'Chat',
+        keywords: ['transcript', 'upload', 'open'],
+        onSelect: () => void handleImportSession(),
+      });
+    }
+
     repos.forEach((repo) => {
       items.push({
         id: `repo-${repo.id}`,
@@ -4246,7 +4667,20 @@ export default function AppShell() {
     });
 
     return items;
-  }, [handleSelectSession, handleSelectWorkspace, repos, sessionsByWorkspace, workspaces]);
+  }, [
+    activeSession,
+    activeWorkspaceId,
+    handleExportSession,
+    handleImportSession,
+    handleRenameSession,
+    handleSelectSession,
+    handleSelectWorkspace,
+    isMac,
+    repos,
+    sessionsByWorkspace,
+    workspaceLabel,
+    workspaces,
+  ]);
 
   useEffect(() => {
     if (!addRepoOpen) {
@@ -5189,6 +5623,41 @@ export default function AppShell() {
                   </div>
                 </div>
 
+                {spotlightRecovery.map((item) => (
+                  <div
+                    key={item.rollbackCheckpointId}
+                    className="flex items-center justify-between gap-3 rounded-md border border-amber-500/40 bg-amber-500/10 px-3 py-2 text-xs text-amber-200"
+                  >
+                    <span>
+                      Spotlight was left running in {item.repoRoot}. Resume syncing or roll the
+                      repo root back?
+                    </span>
+                    <div className="flex items-center gap-2">
+                      {item.resumable ? (
+                        <Button
+                          size="sm"
+                          variant="outline"
+                          onClick={() => void handleRecoverSpotlight(item, 'resume')}
+                        >
+                          Resume
+                        </Button>
+                      ) : null}
+                      <Button
+                        size="sm"
+                        variant="outline"
+                        onClick={() => void handleRecoverSpotlight(item, 'rollback')}
+                      >
+                        Roll back
+                      </Button>
+                    </div>
+                  </div>
+                ))}
+                {spotlightRecoveryError ? (
+                  <div className="rounded-md border border-amber-500/40 bg-amber-500/10 px-3 py-2 text-xs text-amber-200">
+                    {spotlightRecoveryError}
+                  </div>
+                ) : null}
+
                 {activeSpotlightError ? (
                   <div className="rounded-md border border-amber-500/40 bg-amber-500/10 px-3 py-2 text-xs text-amber-200">
                     {activeSpotlightError}
@@ -5645,9 +6114,16 @@ export default function AppShell() {
                             message.role === 'user' &&
                             message.turnId !== undefined &&
                             Boolean(message.checkpointId) &&
-                            activeSession?.agentType === 'claude' &&
                             !hasAnyRunningSessions;
                           const isResetting = resettingTurnId === message.turnId;
+                          const canForkMessage =
+                            message.role === 'assistant' &&
+                            message.turnId !== undefined &&
+                            Boolean(message.checkpointId) &&
+                            !message.streaming &&
+                            !hasAnyRunningSessions;
+                          const isForking = forkingTurnId === message.turnId;
+                          const isQueued = isQueuedMessage(message);
                           return (
                             <div
                               key={message.id}
@@ -5663,8 +6139,45 @@ export default function AppShell() {
                                   {message.streaming ? (
                                     <span className="text-emerald-400">Streaming…</span>
                                   ) : null}
+                                  {isQueued ? (
+                                    <span className="text-amber-400">Queued</span>
+                                  ) : null}
+                                  {message.cancelledAt ? (
+                                    <span className="text-rose-400">Cancelled</span>
+                                  ) : null}
                                 </div>
                                 <div className="flex items-center gap-2">
+                                  {isQueued ? (
+                                    <>
+                                      <button
+                                        type="button"
+                                        onClick={() => void handleMoveQueuedMessage(message.id, -1)}
+                                        title="Send earlier"
+                                        aria-label="Send earlier"
+                                        className="rounded-md border border-slate-800 px-2 py-1 text-[10px] uppercase tracking-widest text-slate-500 transition hover:bg-slate-900 hover:text-slate-100"
+                                      >
+                                        ↑
+                                      </button>
+                                      <button
+                                        type="button"
+                                        onClick={() => void handleMoveQueuedMessage(message.id, 1)}
+                                        title="Send later"
+                                        aria-label="Send later"
+                                        className="rounded-md border border-slate-800 px-2 py-1 text-[10px] uppercase tracking-widest text-slate-500 transition hover:bg-slate-900 hover:text-slate-100"
+                                      >
+                                        ↓
+                                      </button>
+                                      <button
+                                        type="button"
+                                        onClick={() => void handleCancelQueuedMessage(message.id)}
+                                        title="Remove from queue"
+                                        aria-label="Remove from queue"
+                                        className="rounded-md border border-slate-800 px-2 py-1 text-[10px] uppercase tracking-widest text-slate-500 transition hover:bg-slate-900 hover:text-slate-100"
+                                      >
+                                        ✕
+                                      </button>
+                                    </>
+                                  ) : null}
                                   {canResetMessage ? (
                                     <button
                                       type="button"
@@ -5677,6 +6190,18 @@ export default function AppShell() {
                                       ↺
                                     </button>
                                   ) : null}
+                                  {canForkMessage ? (
+                                    <button
+                                      type="button"
+                                      onClick={() => void handleForkAtTurn(message.turnId)}
+                                      disabled={isForking}
+                                      title="Fork chat from here"
+                                      aria-label="Fork chat from here"
+                                      className="rounded-md border border-slate-800 px-2 py-1 text-[10px] uppercase tracking-widest text-slate-500 opacity-0 transition group-hover:opacity-100 focus-visible:opacity-100 disabled:cursor-not-allowed disabled:opacity-40 hover:bg-slate-900 hover:text-slate-100"
+                                    >
+                                      Fork
+                                    </button>
+                                  ) : null}
                                   <button
                                     type="button"
                                     onClick={() => handleCopyMessage(message.content)}
@@ -5724,6 +6249,62 @@ export default function AppShell() {
                                   ))}
                                 </div>
                               ) : null}
+                              {message.role === 'assistant' &&
+                              message.turnId !== undefined &&
+                              !message.streaming ? (
+                                <div className="mt-3 rounded-md border border-slate-800 bg-slate-950/60 p-3 text-xs text-slate-400">
+                                  <div className="flex items-center justify-between">
+                                    <div className="text-[10px] uppercase tracking-[0.3em] text-slate-500">
+                                      Tool calls
+                                    </div>
+                                    <button
+                                      type="button"
+                                      onClick={() => void handleToggleToolCalls(message)}
+                                      className="rounded-md border border-slate-800 px-2 py-1 text-[10px] uppercase tracking-widest text-slate-500 transition hover:bg-slate-900 hover:text-slate-100"
+                                    >
+                                      {toolCallsByMessage[message.id] ? 'Hide' : 'Show'}
+                                    </button>
+                                  </div>
+                                  {toolCallsByMessage[message.id]?.length === 0 ? (
+                                    <div className="mt-2 text-[11px] text-slate-500">
+                                      No tool calls recorded for this turn.
+                                    </div>
+                                  ) : null}
+                                  {toolCallsByMessage[message.id]?.map((call) => (
+                                    <div key={call.id} className="mt-2">
+                                      <div className="flex items-center gap-2 text-[11px]">
+                                        <span className="text-slate-200">{call.toolName}</span>
+                                        <span
+                                          className={
+                                            call.status === 'completed'
+                                              ? 'text-emerald-400'
+                                              : call.status === 'running'
+                                                ? 'text-sky-400'
+                                                : 'text-rose-400'
+                                          }
+                                        >
+                                          {call.status}
+                                        </span>
+                                        {call.durationMs != null ? (
+                                          <span className="text-slate-500">
+                                            {call.durationMs} ms
+                                          </span>
+                                        ) : null}
+                                      </div>
+                                      {call.inputJson ? (
+                                        <pre className="mt-1 max-h-32 overflow-auto whitespace-pre-wrap text-[11px] text-slate-400">
+                                          {call.inputJson}
+                                        </pre>
+                                      ) : null}
+                                      {call.output ? (
+                                        <pre className="mt-1 max-h-48 overflow-auto whitespace-pre-wrap text-[11px] text-slate-500">
+                                          {call.output}
+                                        </pre>
+                                      ) : null}
+                                    </div>
+                                  ))}
+                                </div>
+                              ) : null}
                               {message.role === 'assistant' && diffStat ? (
                                 <div className="mt-3 rounded-md border border-slate-800 bg-slate-950/60 p-3 text-xs text-slate-400">
                                   <div className="text-[10px] uppercase tracking-[0.3em] text-slate-500">
@@ -6007,7 +6588,7 @@ export default function AppShell() {
                   </Button>
                 ) : null}
                 <Button onClick={handleSendMessage} disabled={!canSendMessage}>
-                  {activeSessionStatus === 'running' ? 'Running...' : 'Send'}
+                  {activeSessionStatus === 'running' ? 'Queue' : 'Send'}
                 </Button>
               </div>
             </div>
@@ -6445,6 +7026,21 @@ export default function AppShell() {
       items={commandPaletteItems}
       onOpenChange={setCommandPaletteOpen}
     />
+    <SessionSearch
+      open={sessionSearchOpen}
+      workspaceId={activeWorkspaceId}
+      workspaceLabel={workspaceLabel}
+      describeWorkspace={(workspaceId) => {
+        const workspace = workspaces.find((item) => item.id === workspaceId);
+        if (!workspace) {
+          return 'Workspace';
+        }
+        const repoName = repos.find((repo) => repo.id === workspace.repoId)?.name ?? 'Repository';
+        return `${repoName} · ${workspace.branch}`;
+      }}
+      onOpenChange={setSessionSearchOpen}
+      onSelectResult={(result) => handleSelectSession(result.workspaceId, result.sessionId)}
+    />
     <FileOpener
       open={fileOpenerOpen}
       files={workspaceFiles}
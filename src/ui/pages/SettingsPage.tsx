@@ -1,6 +1,7 @@
 import { useEffect, useMemo, useRef, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { Button } from '@/components/ui/button';
+import type { UsageReport } from '@/types/session';
 
 type AppPaths = {
   appDataDir: string;
@@ -31,9 +32,16 @@ const sections = [
   'Agents',
   'Memory',
   'Hooks',
+  'Usage',
   'Experimental',
 ];
 
+const usageRanges: UsageReport['range'][] = ['day', 'week', 'month', 'all'];
+const usageGroups: UsageReport['groupBy'][] = ['session', 'workspace', 'repo', 'model'];
+
+const formatTokens = (value: number) => value.toLocaleString();
+const formatCost = (value: number) => `$${value.toFixed(2)}`;
+
 /**
  * Settings view with Env editor and app path diagnostics.
  */
@@ -47,6 +55,13 @@ export default function SettingsPage() {
   const [spotlightEnabled, setSpotlightEnabled] = useState(false);
   const [spotlightState, setSpotlightState] = useState<'idle' | 'saving' | 'error'>('idle');
   const [spotlightError, setSpotlightError] = useState<string | null>(null);
+  const [autoTitleEnabled, setAutoTitleEnabled] = useState(false);
+  const [titleModel, setTitleModel] = useState('');
+  const [titleError, setTitleError] = useState<string | null>(null);
+  const [usageRange, setUsageRange] = useState<UsageReport['range']>('week');
+  const [usageGroupBy, setUsageGroupBy] = useState<UsageReport['groupBy']>('session');
+  const [usageReport, setUsageReport] = useState<UsageReport | null>(null);
+  const [usageError, setUsageError] = useState<string | null>(null);
 
   useEffect(() => {
     let active = true;
@@ -78,6 +93,8 @@ export default function SettingsPage() {
       if (settingsResult.status === 'fulfilled') {
         const byKey = new Map(settingsResult.value.map((item) => [item.key, item.value]));
         setSpotlightEnabled(byKey.get('spotlight_enabled') === 'true');
+        setAutoTitleEnabled(byKey.get('session_auto_title') === 'true');
+        setTitleModel(byKey.get('session_title_model') ?? '');
       } else {
         setSpotlightError(String(settingsResult.reason));
       }
@@ -92,6 +109,28 @@ export default function SettingsPage() {
     };
   }, []);
 
+  useEffect(() => {
+    if (activeSection !== 'Usage') {
+      return;
+    }
+    let active = true;
+    setUsageError(null);
+    invoke<UsageReport>('getUsageReport', { range: usageRange, groupBy: usageGroupBy })
+      .then((report) => {
+        if (active) {
+          setUsageReport(report);
+        }
+      })
+      .catch((err) => {
+        if (active) {
+          setUsageError(String(err));
+        }
+      });
+    return () => {
+      active = false;
+    };
+  }, [activeSection, usageRange, usageGroupBy]);
+
   useEffect(() => {
     return () => {
       if (saveTimeoutRef.current !== null) {
@@ -161,14 +200,40 @@ export default function SettingsPage() {
     }
   };
 
+  const handleToggleAutoTitle = async () => {
+    const next = !autoTitleEnabled;
+    setTitleError(null);
+    try {
+      await invoke('setSetting', {
+        key: 'session_auto_title',
+        value: next ? 'true' : 'false',
+      });
+      setAutoTitleEnabled(next);
+    } catch (err) {
+      setTitleError(String(err));
+    }
+  };
+
+  const handleSaveTitleModel = async () => {
+    setTitleError(null);
+    try {
+      await invoke('setSetting', { key: 'session_title_model', value: titleModel.trim() });
+    } catch (err) {
+      setTitleError(String(err));
+    }
+  };
+
   const isSectionEnabled = useMemo(
-    () => (section: string) => section === 'Env' || section === 'Experimental',
+    () => (section: string) =>
+      section === 'Env' || section === 'Usage' || section === 'Experimental',
     [],
   );
   const headerDescription =
     activeSection === 'Experimental'
       ? 'Turn on experimental features and preview upcoming workflows.'
-      : 'Configure environment variables and verify local app paths.';
+      : activeSection === 'Usage'
+        ? 'Review token usage and agent costs across your sessions.'
+        : 'Configure environment variables and verify local app paths.';
 
   return (
     <div className="flex h-full gap-6">
@@ -207,7 +272,80 @@ export default function SettingsPage() {
           </p>
         </header>
 
-        {activeSection === 'Experimental' ? (
+        {activeSection === 'Usage' ? (
+          <div className="rounded-lg border border-slate-800 bg-slate-900/40 p-5">
+            <div className="flex flex-wrap items-center justify-between gap-3">
+              <div className="text-xs uppercase tracking-widest text-slate-500">
+                Usage
+              </div>
+              <div className="flex items-center gap-2 text-xs">
+                <select
+                  value={usageRange}
+                  onChange={(event) =>
+                    setUsageRange(event.target.value as UsageReport['range'])
+                  }
+                  className="rounded-md border border-slate-800 bg-slate-950 px-2 py-1 text-slate-200"
+                >
+                  {usageRanges.map((range) => (
+                    <option key={range} value={range}>
+                      {range === 'all' ? 'All time' : `Last ${range}`}
+                    </option>
+                  ))}
+                </select>
+                <select
+                  value={usageGroupBy}
+                  onChange={(event) =>
+                    setUsageGroupBy(event.target.value as UsageReport['groupBy'])
+                  }
+                  className="rounded-md border border-slate-800 bg-slate-950 px-2 py-1 text-slate-200"
+                >
+                  {usageGroups.map((group) => (
+                    <option key={group} value={group}>
+                      By {group}
+                    </option>
+                  ))}
+                </select>
+              </div>
+            </div>
+            {usageError ? (
+              <div className="mt-3 text-xs text-red-400">{usageError}</div>
+            ) : usageReport ? (
+              <table className="mt-4 w-full text-left text-sm text-slate-300">
+                <thead className="text-xs text-slate-500">
+                  <tr>
+                    <th className="py-1 font-normal">Name</th>
+                    <th className="py-1 text-right font-normal">Turns</th>
+                    <th className="py-1 text-right font-normal">Input</th>
+                    <th className="py-1 text-right font-normal">Output</th>
+                    <th className="py-1 text-right font-normal">Cache read</th>
+                    <th className="py-1 text-right font-normal">Cost</th>
+                  </tr>
+                </thead>
+                <tbody>
+                  {[...usageReport.rows, usageReport.total].map((row) => (
+                    <tr
+                      key={row.key}
+                      className={
+                        row === usageReport.total
+                          ? 'border-t border-slate-800 font-semibold text-slate-100'
+                          : ''
+                      }
+                    >
+                      <td className="max-w-xs truncate py-1">{row.label}</td>
+                      <td className="py-1 text-right">{row.turns}</td>
+                      <td className="py-1 text-right">{formatTokens(row.inputTokens)}</td>
+                      <td className="py-1 text-right">{formatTokens(row.outputTokens)}</td>
+                      <td className="py-1 text-right">{formatTokens(row.cacheReadTokens)}</td>
+                      <td className="py-1 text-right">{formatCost(row.costUsd)}</td>
+                    </tr>
+                  ))}
+                </tbody>
+              </table>
+            ) : (
+              <div className="mt-4 text-sm text-slate-500">Loading usage...</div>
+            )}
+          </div>
+        ) : activeSection === 'Experimental' ? (
           <div className="rounded-lg border border-slate-800 bg-slate-900/40 p-5">
             <div className="text-xs uppercase tracking-widest text-slate-500">
               Experimental
@@ -237,6 +375,35 @@ export default function SettingsPage() {
             {spotlightError ? (
               <div className="mt-3 text-xs text-red-400">{spotlightError}</div>
             ) : null}
+            <div className="mt-6 flex flex-wrap items-start justify-between gap-4 border-t border-slate-800 pt-5 text-sm text-slate-200">
+              <div className="space-y-2">
+                <div className="text-sm font-semibold text-slate-100">
+                  Auto-generated Claude chat titles
+                </div>
+                <div className="text-xs text-slate-400">
+                  Name new Claude chats from their first exchange. Codex chats are not renamed,
+                  and chats you rename keep your title.
+                </div>
+                <input
+                  value={titleModel}
+                  onChange={(event) => setTitleModel(event.target.value)}
+                  onBlur={handleSaveTitleModel}
+                  disabled={!autoTitleEnabled}
+                  placeholder="Claude title model"
+                  className="w-64 rounded-md border border-slate-800 bg-slate-950 px-2 py-1 text-xs text-slate-200 disabled:opacity-50"
+                />
+              </div>
+              <label className="flex items-center gap-3 text-xs text-slate-400">
+                <input
+                  type="checkbox"
+                  checked={autoTitleEnabled}
+                  onChange={handleToggleAutoTitle}
+                  className="h-4 w-4 rounded border-slate-700 bg-slate-950"
+                />
+                <span>{autoTitleEnabled ? 'Enabled' : 'Disabled'}</span>
+              </label>
+            </div>
+            {titleError ? <div className="mt-3 text-xs text-red-400">{titleError}</div> : null}
           </div>
         ) : (
           <>
@@ -14,6 +14,10 @@ export type SessionRecord = {
   codexSessionId?: string | null;
   contextTokenCount?: number | null;
   isCompacted: boolean;
+  sidecarRestartCount: number;
+  forkedFromSessionId?: string | null;
+  forkedFromTurnId?: number | null;
+  resumeSessionAt?: string | null;
 };
 
 export type SessionMessageRecord = {
@@ -26,6 +30,44 @@ export type SessionMessageRecord = {
   cancelledAt?: string | null;
   metadataJson?: string | null;
   checkpointId?: string | null;
+  queuePosition?: number | null;
+};
+
+export type SessionToolCallRecord = {
+  id: string;
+  sessionId: string;
+  turnId: number;
+  messageId?: string | null;
+  callId: string;
+  toolName: string;
+  inputJson?: string | null;
+  output?: string | null;
+  status: 'running' | 'completed' | 'error' | 'cancelled' | 'interrupted';
+  startedAtMs: number;
+  finishedAtMs?: number | null;
+  durationMs?: number | null;
+};
+
+export type SessionSearchFilters = {
+  workspaceId?: string | null;
+  repoId?: string | null;
+  sessionId?: string | null;
+  role?: SessionRole | null;
+  includeToolCalls?: boolean;
+  limit?: number;
+};
+
+export type SessionSearchResult = {
+  kind: 'message' | 'tool_call';
+  sourceId: string;
+  sessionId: string;
+  sessionTitle?: string | null;
+  workspaceId: string;
+  repoId: string;
+  turnId: number;
+  role?: SessionRole | null;
+  snippet: Array<{ text: string; highlighted: boolean }>;
+  rank: number;
 };
 
 export type SessionMessageEvent = {
@@ -35,13 +77,20 @@ export type SessionMessageEvent = {
     role: SessionRole;
     content: string;
     metadata?: unknown;
+    checkpointId?: string | null;
     streaming: boolean;
   };
 };
 
+export type SessionMessageDispatchedEvent = {
+  sessionId: string;
+  message: SessionMessageRecord;
+};
+
 export type SessionStatusEvent = {
   sessionId: string;
   status: SessionStatus;
+  reason?: 'finished' | 'cancelled' | 'error' | 'closed';
 };
 
 export type SessionErrorEvent = {
@@ -49,6 +98,36 @@ export type SessionErrorEvent = {
   error: string;
 };
 
+export type SessionSidecarRestartedEvent = {
+  sessionId: string;
+  restartCount: number;
+  reissued: boolean;
+};
+
+export type SessionContextUsageEvent = {
+  sessionId: string;
+  contextTokenCount: number;
+  isCompacted: boolean;
+};
+
+export type UsageReportRow = {
+  key: string;
+  label: string;
+  turns: number;
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  costUsd: number;
+};
+
+export type UsageReport = {
+  range: 'day' | 'week' | 'month' | 'all';
+  groupBy: 'session' | 'workspace' | 'repo' | 'model';
+  rows: UsageReportRow[];
+  total: UsageReportRow;
+};
+
 export type SessionPlanModeEvent = {
   sessionId: string;
 };
@@ -23,3 +23,32 @@ export type FilePreview = {
   truncated: boolean;
   binary: boolean;
 };
+
+export type SpotlightStatus = {
+  active: boolean;
+  paused: boolean;
+  activeSince?: number | null;
+  lastSyncAt?: number | null;
+  lastSyncDurationMs?: number | null;
+  lastSyncMode?: 'incremental' | 'full' | null;
+  filesSynced: number;
+  syncCount: number;
+  lastError?: string | null;
+  lastSkipReason?: string | null;
+};
+
+export type SpotlightRecoveryItem = {
+  workspaceId: string;
+  workspacePath?: string | null;
+  repoRoot: string;
+  rollbackCheckpointId: string;
+  syncCheckpointId: string;
+  created?: number | null;
+  resumable: boolean;
+};
+
+export type SpotlightSyncEvent = {
+  workspaceId: string;
+  outcome: 'synced' | 'skipped' | 'failed' | 'conflict';
+  status: SpotlightStatus;
+};